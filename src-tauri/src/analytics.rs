@@ -0,0 +1,1627 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::{Datelike, Months, NaiveDate};
+
+use crate::models::*;
+use crate::prediction;
+use crate::symptoms;
+
+/// Cycle days reached by fewer past cycles than this are left off the
+/// heatmap; a single cycle makes every rate 0 or 100%.
+const MIN_HEATMAP_CYCLES: usize = 2;
+/// Rolling window sizes, in months.
+const ROLLING_WINDOWS: [u32; 3] = [3, 6, 12];
+/// Points per rolling series: a year of month ends.
+const ROLLING_POINTS: u32 = 12;
+/// Symptoms listed in the year review.
+const TOP_SYMPTOMS: usize = 5;
+/// Other cycles needed before a period is compared against them.
+const MIN_FLOW_COMPARISONS: usize = 3;
+/// Intensity this many times the usual marks a heavier period.
+const HEAVIER_THAN_USUAL: f32 = 1.5;
+/// Logged days needed before any correlation is reported.
+const MIN_CORRELATION_DAYS: usize = 30;
+/// A symptom has to come up this often to be correlated at all.
+const MIN_SYMPTOM_DAYS: usize = 5;
+/// Pairs seen together fewer times than this are left out.
+const MIN_TOGETHER: usize = 3;
+/// Calendar months covered by the logging completeness figures.
+const COMPLETENESS_MONTHS: u32 = 12;
+/// Days after a missed pill that breakthrough bleeding is put down to it.
+const MISSED_PILL_EFFECT_DAYS: i64 = 3;
+
+/// Past symptoms lined up by cycle day, over completed cycles that count
+/// towards stats.
+pub struct CycleDayCounts<'a> {
+    /// How many completed cycles reached each cycle day.
+    pub reached: HashMap<u32, usize>,
+    /// How many of them had each symptom logged on that cycle day.
+    pub counts: HashMap<(u32, &'a str), usize>,
+}
+
+/// Align every symptom logged in a completed cycle to its cycle day, with
+/// day 1 the first day of the period.
+pub fn align_symptoms(data: &AppData) -> CycleDayCounts<'_> {
+    let mut starts: Vec<NaiveDate> = data.cycles.iter().map(|c| c.start_date).collect();
+    starts.sort();
+
+    let excluded = prediction::excluded_starts(&data.cycles, &data.settings);
+
+    let mut reached: HashMap<u32, usize> = HashMap::new();
+    let mut counts: HashMap<(u32, &str), usize> = HashMap::new();
+    for w in starts.windows(2).filter(|w| !excluded.contains(&w[0])) {
+        for (date, symptoms) in data.symptoms.range(w[0]..w[1]) {
+            let cycle_day = (*date - w[0]).num_days() as u32 + 1;
+            for symptom in symptoms {
+                *counts
+                    .entry((cycle_day, &symptom.symptom_type))
+                    .or_default() += 1;
+            }
+        }
+        for cycle_day in 1..=(w[1] - w[0]).num_days() as u32 {
+            *reached.entry(cycle_day).or_default() += 1;
+        }
+    }
+    CycleDayCounts { reached, counts }
+}
+
+/// Share of past cycles with each symptom on each cycle day, as a matrix of
+/// one row per symptom and one column per cycle day.
+pub fn symptom_heatmap(data: &AppData) -> SymptomHeatmap {
+    let aligned = align_symptoms(data);
+    let cycle_days = (1..)
+        .take_while(|day| aligned.reached.get(day).copied().unwrap_or(0) >= MIN_HEATMAP_CYCLES)
+        .count() as u32;
+    let cycles_reached: Vec<usize> = (1..=cycle_days).map(|day| aligned.reached[&day]).collect();
+
+    let mut symptom_types: Vec<&str> = aligned
+        .counts
+        .keys()
+        .filter(|(day, _)| *day <= cycle_days)
+        .map(|&(_, key)| key)
+        .collect();
+    symptom_types.sort();
+    symptom_types.dedup();
+
+    let rows = symptom_types
+        .into_iter()
+        .map(|key| HeatmapRow {
+            symptom_type: key.to_string(),
+            rates: (1..=cycle_days)
+                .zip(&cycles_reached)
+                .map(|(day, &cycles)| {
+                    let count = aligned.counts.get(&(day, key)).copied().unwrap_or(0);
+                    count as f32 / cycles as f32
+                })
+                .collect(),
+        })
+        .collect();
+
+    SymptomHeatmap {
+        cycle_days,
+        cycles_reached,
+        rows,
+    }
+}
+
+/// Cycle length, period length and symptom burden averaged over 3, 6 and
+/// 12 month windows, each as a monthly series ending today, so recent months
+/// can be set against the longer baseline.
+pub fn rolling_trends(data: &AppData, today: NaiveDate) -> Vec<RollingSeries> {
+    let mut cycles: Vec<&Cycle> = data.cycles.iter().collect();
+    cycles.sort_by_key(|c| c.start_date);
+    let excluded = prediction::excluded_starts(&data.cycles, &data.settings);
+
+    ROLLING_WINDOWS
+        .iter()
+        .map(|&window_months| RollingSeries {
+            window_months,
+            points: (0..ROLLING_POINTS)
+                .rev()
+                .filter_map(|back| {
+                    let end = today.checked_sub_months(Months::new(back))?;
+                    let start = end.checked_sub_months(Months::new(window_months))?;
+                    Some(rolling_point(data, &cycles, &excluded, start, end))
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Averages over `start` (exclusive) to `end` (inclusive). `cycles` is
+/// sorted by start; lengths of `excluded` cycles are left out.
+fn rolling_point(
+    data: &AppData,
+    cycles: &[&Cycle],
+    excluded: &BTreeSet<NaiveDate>,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> RollingPoint {
+    let in_window = |date: NaiveDate| date > start && date <= end;
+    let average = |values: Vec<i64>| {
+        (!values.is_empty()).then(|| values.iter().sum::<i64>() as f32 / values.len() as f32)
+    };
+
+    let cycle_lengths: Vec<i64> = cycles
+        .windows(2)
+        .filter(|w| in_window(w[0].start_date) && w[1].start_date <= end)
+        .filter(|w| !excluded.contains(&w[0].start_date))
+        .map(|w| (w[1].start_date - w[0].start_date).num_days())
+        .collect();
+    let period_lengths: Vec<i64> = cycles
+        .iter()
+        .filter(|c| in_window(c.start_date))
+        .filter_map(|c| Some((c.end_date.filter(|&e| e <= end)? - c.start_date).num_days() + 1))
+        .collect();
+    let severity: u32 = data
+        .symptoms
+        .range(start.succ_opt().unwrap_or(start)..=end)
+        .flat_map(|(_, symptoms)| symptoms)
+        .map(|s| s.severity as u32)
+        .sum();
+
+    RollingPoint {
+        end,
+        avg_cycle_length: average(cycle_lengths),
+        avg_period_length: average(period_lengths),
+        symptom_burden: severity as f32 / (end - start).num_days() as f32,
+    }
+}
+
+/// Summary of one calendar year. A cycle belongs to the year it started
+/// in; its length still counts when the next one starts in January.
+pub fn year_review(data: &AppData, year: i32) -> YearReview {
+    let in_year = |date: NaiveDate| date.year() == year;
+    let mut starts: Vec<NaiveDate> = data.cycles.iter().map(|c| c.start_date).collect();
+    starts.sort();
+
+    let excluded = prediction::excluded_starts(&data.cycles, &data.settings);
+
+    let lengths: Vec<i64> = starts
+        .windows(2)
+        .filter(|w| in_year(w[0]) && !excluded.contains(&w[0]))
+        .map(|w| (w[1] - w[0]).num_days())
+        .collect();
+    let avg_cycle_length =
+        (!lengths.is_empty()).then(|| lengths.iter().sum::<i64>() as f32 / lengths.len() as f32);
+
+    let mut symptom_days: HashMap<&str, usize> = HashMap::new();
+    for (_, symptoms) in data.symptoms.iter().filter(|(date, _)| in_year(**date)) {
+        for symptom in symptoms {
+            *symptom_days.entry(&symptom.symptom_type).or_default() += 1;
+        }
+    }
+    let mut top_symptoms: Vec<SymptomCount> = symptom_days
+        .into_iter()
+        .map(|(key, days)| SymptomCount {
+            symptom_type: key.to_string(),
+            days,
+        })
+        .collect();
+    top_symptoms.sort_by(|a, b| {
+        b.days
+            .cmp(&a.days)
+            .then_with(|| a.symptom_type.cmp(&b.symptom_type))
+    });
+    top_symptoms.truncate(TOP_SYMPTOMS);
+
+    let mut flow_by_month = [0u32; 12];
+    for log in data.day_logs.values().filter(|l| in_year(l.date)) {
+        flow_by_month[log.date.month0() as usize] += flow_score(&log.flow_level);
+    }
+    let months_with_flow = || (1..=12u32).filter(|&m| flow_by_month[m as usize - 1] > 0);
+    let heaviest_month = months_with_flow().max_by_key(|&m| flow_by_month[m as usize - 1]);
+    let lightest_month = months_with_flow().min_by_key(|&m| flow_by_month[m as usize - 1]);
+
+    let logged: BTreeSet<NaiveDate> = logged_days(data).filter(|&d| in_year(d)).collect();
+
+    YearReview {
+        year,
+        cycles: starts.iter().filter(|&&s| in_year(s)).count(),
+        avg_cycle_length,
+        regularity: prediction::irregularity(&lengths),
+        top_symptoms,
+        heaviest_month,
+        lightest_month,
+        logged_days: logged.len(),
+        longest_logging_streak: streaks(&logged).max().unwrap_or(0),
+    }
+}
+
+/// Symptom days and average severity per calendar month of `year`, all
+/// twelve months included so empty ones show as gaps.
+pub fn monthly_symptom_summary(data: &AppData, year: i32) -> Vec<MonthlySymptoms> {
+    let mut by_month: [HashMap<&str, (usize, u32)>; 12] = Default::default();
+    for (date, symptoms) in data.symptoms.iter().filter(|(d, _)| d.year() == year) {
+        for symptom in symptoms {
+            let entry = by_month[date.month0() as usize]
+                .entry(&symptom.symptom_type)
+                .or_default();
+            entry.0 += 1;
+            entry.1 += symptom.severity as u32;
+        }
+    }
+
+    by_month
+        .into_iter()
+        .zip(1..)
+        .map(|(counts, month)| {
+            let mut symptoms: Vec<SymptomMonthStats> = counts
+                .into_iter()
+                .map(|(key, (days, severity))| SymptomMonthStats {
+                    symptom_type: key.to_string(),
+                    days,
+                    avg_severity: severity as f32 / days as f32,
+                })
+                .collect();
+            symptoms.sort_by(|a, b| {
+                b.days
+                    .cmp(&a.days)
+                    .then_with(|| a.symptom_type.cmp(&b.symptom_type))
+            });
+            MonthlySymptoms { month, symptoms }
+        })
+        .collect()
+}
+
+/// Logging streaks up to `today` and completeness for the last
+/// `COMPLETENESS_MONTHS` calendar months, the current one counted up to
+/// `today`.
+pub fn logging_stats(data: &AppData, today: NaiveDate) -> LoggingStats {
+    let logged: BTreeSet<NaiveDate> = logged_days(data).filter(|&d| d <= today).collect();
+
+    // A streak still counts as current until a whole day has been missed
+    let yesterday = today.pred_opt().unwrap_or(today);
+    let runs: Vec<u32> = streaks(&logged).collect();
+    let current_streak = match logged.last() {
+        Some(&last) if last >= yesterday => runs.last().copied().unwrap_or(0),
+        _ => 0,
+    };
+
+    let this_month = today.with_day(1).expect("day 1 exists");
+    let months = (0..COMPLETENESS_MONTHS)
+        .rev()
+        .map(|back| {
+            let first = this_month - Months::new(back);
+            let next = first + Months::new(1);
+            let end = next.min(today.succ_opt().unwrap_or(today));
+            let days = (end - first).num_days() as usize;
+            let logged_days = logged.range(first..end).count();
+            MonthCompleteness {
+                year: first.year(),
+                month: first.month(),
+                logged_days,
+                days,
+                completeness: logged_days as f32 / days as f32,
+            }
+        })
+        .collect();
+
+    LoggingStats {
+        current_streak,
+        longest_streak: runs.into_iter().max().unwrap_or(0),
+        months,
+    }
+}
+
+/// Every date with a day log or a symptom. May repeat dates.
+fn logged_days(data: &AppData) -> impl Iterator<Item = NaiveDate> + '_ {
+    data.day_logs.keys().chain(data.symptoms.keys()).copied()
+}
+
+/// Lengths of the runs of consecutive dates in `dates`, oldest first.
+fn streaks(dates: &BTreeSet<NaiveDate>) -> impl Iterator<Item = u32> {
+    let mut runs = Vec::new();
+    let mut previous: Option<NaiveDate> = None;
+    for &date in dates {
+        match previous {
+            Some(p) if p.succ_opt() == Some(date) => *runs.last_mut().expect("run started") += 1,
+            _ => runs.push(1),
+        }
+        previous = Some(date);
+    }
+    runs.into_iter()
+}
+
+/// Pill-taking record from the pack start to `today`. `None` unless a pill
+/// regimen is set.
+pub fn pill_adherence(data: &AppData, today: NaiveDate) -> Option<PillAdherence> {
+    let ContraceptionMode::Pill {
+        pack_start,
+        active_days,
+        break_days,
+    } = data.settings.contraception_mode
+    else {
+        return None;
+    };
+    let pack_len = (active_days + break_days).max(1) as i64;
+    let active =
+        |date: NaiveDate| (date - pack_start).num_days().rem_euclid(pack_len) < active_days as i64;
+    let scheduled: Vec<NaiveDate> = pack_start
+        .iter_days()
+        .take_while(|&d| d <= today)
+        .filter(|&d| active(d))
+        .collect();
+
+    let doses: BTreeMap<NaiveDate, bool> = data
+        .pill_doses
+        .iter()
+        .filter(|d| d.date >= pack_start && d.date <= today && active(d.date))
+        .map(|d| (d.date, d.taken))
+        .collect();
+    let taken = doses.values().filter(|&&taken| taken).count();
+    let missed = doses.len() - taken;
+
+    // Runs of missed pills, counting scheduled days only so a break in
+    // between doesn't end a run
+    let mut runs: Vec<u32> = Vec::new();
+    let mut run = 0;
+    for taken in doses.values() {
+        if *taken {
+            run = 0;
+        } else {
+            if run == 0 {
+                runs.push(0);
+            }
+            run += 1;
+            *runs.last_mut().expect("run started") = run;
+        }
+    }
+
+    let missed_days: Vec<NaiveDate> = doses
+        .iter()
+        .filter(|(_, &taken)| !taken)
+        .map(|(&date, _)| date)
+        .collect();
+    let after_missed = |date: NaiveDate| {
+        missed_days
+            .iter()
+            .any(|&m| date > m && (date - m).num_days() <= MISSED_PILL_EFFECT_DAYS)
+    };
+    let bleeding = |date: NaiveDate| {
+        data.day_logs
+            .get(&date)
+            .is_some_and(|log| log.flow_level != FlowLevel::None)
+    };
+    let rate = |days: Vec<&NaiveDate>| {
+        (!days.is_empty())
+            .then(|| days.iter().filter(|&&&d| bleeding(d)).count() as f32 / days.len() as f32)
+    };
+    let (affected, unaffected): (Vec<&NaiveDate>, Vec<&NaiveDate>) =
+        scheduled.iter().partition(|&&d| after_missed(d));
+
+    Some(PillAdherence {
+        scheduled_days: scheduled.len(),
+        taken,
+        missed,
+        adherence: (!doses.is_empty()).then(|| taken as f32 / doses.len() as f32),
+        current_missed_streak: run,
+        longest_missed_streak: runs.into_iter().max().unwrap_or(0),
+        bleeding_after_missed: rate(affected),
+        bleeding_otherwise: rate(unaffected),
+    })
+}
+
+/// `metric` between `from` and `to` (inclusive), averaged per bucket of
+/// `resolution` and optionally smoothed with a trailing moving average over
+/// `smoothing` points. Buckets with no values are left out.
+pub fn series(
+    data: &AppData,
+    metric: &SeriesMetric,
+    from: NaiveDate,
+    to: NaiveDate,
+    resolution: SeriesResolution,
+    smoothing: Option<usize>,
+) -> Vec<SeriesPoint> {
+    let days = from..=to;
+    let raw: Vec<(NaiveDate, f32)> = match metric {
+        SeriesMetric::CycleLength | SeriesMetric::PeriodLength => {
+            let mut cycles: Vec<&Cycle> = data.cycles.iter().collect();
+            cycles.sort_by_key(|c| c.start_date);
+            if *metric == SeriesMetric::CycleLength {
+                let excluded = prediction::excluded_starts(&data.cycles, &data.settings);
+                cycles
+                    .windows(2)
+                    .filter(|w| !excluded.contains(&w[0].start_date))
+                    .map(|w| {
+                        (
+                            w[0].start_date,
+                            (w[1].start_date - w[0].start_date).num_days() as f32,
+                        )
+                    })
+                    .collect()
+            } else {
+                cycles
+                    .iter()
+                    .filter_map(|c| {
+                        let days = (c.end_date? - c.start_date).num_days() + 1;
+                        Some((c.start_date, days as f32))
+                    })
+                    .collect()
+            }
+        }
+        SeriesMetric::Bbt => data
+            .day_logs
+            .range(days.clone())
+            .filter_map(|(&date, log)| {
+                Some((
+                    date,
+                    data.settings.temperature_unit.convert_celsius(log.bbt?),
+                ))
+            })
+            .collect(),
+        SeriesMetric::Libido => data
+            .day_logs
+            .range(days.clone())
+            .filter_map(|(&date, log)| Some((date, log.libido? as f32)))
+            .collect(),
+        SeriesMetric::Mood => data
+            .symptoms
+            .range(days.clone())
+            .filter_map(|(&date, logged)| {
+                let severity = |key: &str| {
+                    logged
+                        .iter()
+                        .find(|s| s.symptom_type == key)
+                        .map(|s| s.severity as f32)
+                };
+                let (high, low) = (severity(symptoms::MOOD_HIGH), severity(symptoms::MOOD_LOW));
+                (high.is_some() || low.is_some())
+                    .then(|| high.unwrap_or(0.0) - low.unwrap_or(0.0))
+                    .map(|mood| (date, mood))
+            })
+            .collect(),
+        SeriesMetric::Symptom { symptom_type } => data
+            .symptoms
+            .range(days.clone())
+            .filter_map(|(&date, logged)| {
+                let symptom = logged.iter().find(|s| &s.symptom_type == symptom_type)?;
+                Some((date, symptom.severity as f32))
+            })
+            .collect(),
+    };
+
+    let bucket_start = |date: NaiveDate| match resolution {
+        SeriesResolution::Day => date,
+        SeriesResolution::Week => {
+            date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+        }
+        SeriesResolution::Month => date.with_day(1).expect("every month has a first day"),
+    };
+    let mut buckets: Vec<(NaiveDate, f32, usize)> = Vec::new();
+    for (date, value) in raw.into_iter().filter(|(date, _)| days.contains(date)) {
+        let start = bucket_start(date);
+        match buckets.last_mut() {
+            Some(bucket) if bucket.0 == start => {
+                bucket.1 += value;
+                bucket.2 += 1;
+            }
+            _ => buckets.push((start, value, 1)),
+        }
+    }
+    let points: Vec<SeriesPoint> = buckets
+        .into_iter()
+        .map(|(date, sum, count)| SeriesPoint {
+            date,
+            value: sum / count as f32,
+        })
+        .collect();
+
+    match smoothing.filter(|&window| window > 1) {
+        Some(window) => (0..points.len())
+            .map(|i| {
+                let trailing = &points[i.saturating_sub(window - 1)..=i];
+                SeriesPoint {
+                    date: points[i].date,
+                    value: trailing.iter().map(|p| p.value).sum::<f32>() / trailing.len() as f32,
+                }
+            })
+            .collect(),
+        None => points,
+    }
+}
+
+/// The last `cycles` cycles up to `today`, oldest first, each as a day by
+/// day series of flow, symptoms and temperature from its first day.
+pub fn cycle_overlay(data: &AppData, today: NaiveDate, cycles: usize) -> Vec<OverlayCycle> {
+    let mut starts: Vec<NaiveDate> = data
+        .cycles
+        .iter()
+        .map(|c| c.start_date)
+        .filter(|&s| s <= today)
+        .collect();
+    starts.sort();
+    let unit = data.settings.temperature_unit;
+    let first = starts.len().saturating_sub(cycles);
+
+    (first..starts.len())
+        .map(|i| {
+            let start = starts[i];
+            let next = starts.get(i + 1).copied();
+            let last = next.and_then(|n| n.pred_opt()).unwrap_or(today);
+            let days = start
+                .iter_days()
+                .take_while(|&date| date <= last)
+                .map(|date| {
+                    let log = data.day_logs.get(&date);
+                    OverlayDay {
+                        cycle_day: (date - start).num_days() as u32 + 1,
+                        date,
+                        flow_level: log.map_or(FlowLevel::None, |l| l.flow_level.clone()),
+                        symptoms: data
+                            .symptoms
+                            .get(&date)
+                            .into_iter()
+                            .flatten()
+                            .map(|s| (s.symptom_type.clone(), s.severity))
+                            .collect(),
+                        bbt: log.and_then(|l| l.bbt).map(|t| unit.convert_celsius(t)),
+                    }
+                })
+                .collect();
+            OverlayCycle {
+                cycle_start: start,
+                current: next.is_none(),
+                days,
+            }
+        })
+        .collect()
+}
+
+/// Temperatures of the cycle `date` falls in, smoothed and with the shift
+/// evaluated on undisturbed readings only. `None` before the first cycle.
+pub fn bbt_chart(data: &AppData, date: NaiveDate) -> Option<BbtChart> {
+    let mut starts: Vec<NaiveDate> = data.cycles.iter().map(|c| c.start_date).collect();
+    starts.sort();
+    let current = starts.iter().rposition(|&s| s <= date)?;
+    let cycle_start = starts[current];
+    let days = cycle_start..starts.get(current + 1).copied().unwrap_or(NaiveDate::MAX);
+    let unit = data.settings.temperature_unit;
+
+    let logs: Vec<&DayLog> = data
+        .day_logs
+        .range(days.clone())
+        .map(|(_, log)| log)
+        .filter(|log| log.bbt.is_some())
+        .collect();
+    let clean: Vec<(NaiveDate, f32)> = logs
+        .iter()
+        .filter(|log| log.bbt_disturbances.is_empty())
+        .filter_map(|log| Some((log.date, log.bbt?)))
+        .collect();
+    let smoothed = |date: NaiveDate| {
+        let i = clean.iter().position(|&(d, _)| d == date)?;
+        let around = &clean[i.saturating_sub(1)..(i + 2).min(clean.len())];
+        Some(around.iter().map(|&(_, t)| t).sum::<f32>() / around.len() as f32)
+    };
+
+    let points = logs
+        .iter()
+        .filter_map(|log| {
+            Some(BbtPoint {
+                date: log.date,
+                cycle_day: (log.date - cycle_start).num_days() as u32 + 1,
+                temperature: unit.convert_celsius(log.bbt?),
+                smoothed: smoothed(log.date).map(|t| unit.convert_celsius(t)),
+                disturbances: log.bbt_disturbances.clone(),
+            })
+        })
+        .collect();
+    let shift = prediction::ovulation::coverline(&data.day_logs, days);
+
+    Some(BbtChart {
+        cycle_start,
+        points,
+        coverline: shift.map(|(_, t)| unit.convert_celsius(t)),
+        ovulation_day: shift.map(|(day, _)| day),
+    })
+}
+
+/// The logs from `date`'s cycle day in each earlier cycle. `None` when
+/// `date` is before the first cycle.
+pub fn same_cycle_day(data: &AppData, date: NaiveDate) -> Option<SameCycleDay> {
+    let mut starts: Vec<NaiveDate> = data.cycles.iter().map(|c| c.start_date).collect();
+    starts.sort();
+    let current = starts.iter().rposition(|&s| s <= date)?;
+    let offset = date - starts[current];
+
+    let previous = starts[..=current]
+        .windows(2)
+        .rev()
+        .filter_map(|w| {
+            let day = w[0] + offset;
+            (day < w[1]).then(|| PastCycleDay {
+                cycle_start: w[0],
+                date: day,
+                day_log: data.day_logs.get(&day).cloned(),
+                symptoms: data.symptoms.get(&day).cloned().unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    Some(SameCycleDay {
+        date,
+        cycle_day: offset.num_days() as u32 + 1,
+        previous,
+    })
+}
+
+/// How the current cycle up to `today` compares with past cycles over
+/// their same first days. `None` before any cycle has started.
+pub fn compare_current_cycle(data: &AppData, today: NaiveDate) -> Option<CycleComparison> {
+    let mut starts: Vec<NaiveDate> = data.cycles.iter().map(|c| c.start_date).collect();
+    starts.sort();
+    let current = starts.iter().rposition(|&s| s <= today)?;
+    let cycle_start = starts[current];
+    let cycle_day = (today - cycle_start).num_days() as u32 + 1;
+    let so_far = |start: NaiveDate| start..start + chrono::Duration::days(cycle_day as i64);
+
+    let flow = |start: NaiveDate| {
+        let scores: Vec<u32> = data
+            .day_logs
+            .range(so_far(start))
+            .map(|(_, log)| flow_score(&log.flow_level))
+            .filter(|&score| score > 0)
+            .collect();
+        (scores.len(), scores.iter().sum::<u32>())
+    };
+    let symptom_days = |start: NaiveDate| {
+        let mut days: HashMap<&str, usize> = HashMap::new();
+        for symptom in data.symptoms.range(so_far(start)).flat_map(|(_, s)| s) {
+            *days.entry(&symptom.symptom_type).or_default() += 1;
+        }
+        days
+    };
+
+    // Past cycles long enough to have reached today's cycle day
+    let excluded = prediction::excluded_starts(&data.cycles, &data.settings);
+    let past: Vec<NaiveDate> = starts[..=current]
+        .windows(2)
+        .filter(|w| (w[1] - w[0]).num_days() >= cycle_day as i64)
+        .filter(|w| !excluded.contains(&w[0]))
+        .map(|w| w[0])
+        .collect();
+    let typical = |total: usize| (!past.is_empty()).then(|| total as f32 / past.len() as f32);
+
+    let (flow_days, flow_intensity) = flow(cycle_start);
+    let past_flow: Vec<(usize, u32)> = past.iter().map(|&start| flow(start)).collect();
+    let mut typical_symptoms: HashMap<&str, usize> = HashMap::new();
+    for &start in &past {
+        for (key, days) in symptom_days(start) {
+            *typical_symptoms.entry(key).or_default() += days;
+        }
+    }
+    let current_symptoms = symptom_days(cycle_start);
+
+    let mut keys: Vec<&str> = current_symptoms
+        .keys()
+        .chain(typical_symptoms.keys())
+        .copied()
+        .collect();
+    keys.sort();
+    keys.dedup();
+    let mut symptoms: Vec<SymptomComparison> = keys
+        .into_iter()
+        .map(|key| SymptomComparison {
+            symptom_type: key.to_string(),
+            days_so_far: current_symptoms.get(key).copied().unwrap_or(0),
+            typical_days: typical(typical_symptoms.get(key).copied().unwrap_or(0)).unwrap_or(0.0),
+        })
+        .collect();
+    symptoms.sort_by(|a, b| {
+        b.days_so_far
+            .cmp(&a.days_so_far)
+            .then_with(|| b.typical_days.total_cmp(&a.typical_days))
+    });
+
+    Some(CycleComparison {
+        cycle_start,
+        cycle_day,
+        past_cycles: past.len(),
+        flow_days,
+        typical_flow_days: typical(past_flow.iter().map(|&(days, _)| days).sum()),
+        flow_intensity,
+        typical_flow_intensity: typical(past_flow.iter().map(|&(_, score)| score as usize).sum()),
+        symptoms,
+    })
+}
+
+/// Per symptom, the mean severity in each cycle it was logged in and a
+/// trailing moving average over `window` of those cycles, so a change in
+/// treatment shows up as a trend rather than one good or bad month.
+pub fn severity_trends(data: &AppData, window: usize) -> Vec<SeverityTrend> {
+    let mut starts: Vec<NaiveDate> = data.cycles.iter().map(|c| c.start_date).collect();
+    starts.sort();
+
+    let excluded = prediction::excluded_starts(&data.cycles, &data.settings);
+
+    let mut by_symptom: BTreeMap<&str, Vec<CycleSeverity>> = BTreeMap::new();
+    for (i, &start) in starts.iter().enumerate() {
+        if excluded.contains(&start) {
+            continue;
+        }
+        let end = starts.get(i + 1).copied().unwrap_or(NaiveDate::MAX);
+        let mut totals: BTreeMap<&str, (u32, usize)> = BTreeMap::new();
+        for symptom in data.symptoms.range(start..end).flat_map(|(_, s)| s) {
+            let total = totals.entry(&symptom.symptom_type).or_default();
+            total.0 += symptom.severity as u32;
+            total.1 += 1;
+        }
+        for (key, (sum, days)) in totals {
+            by_symptom.entry(key).or_default().push(CycleSeverity {
+                cycle_start: start,
+                mean_severity: sum as f32 / days as f32,
+                days,
+                moving_average: 0.0,
+            });
+        }
+    }
+
+    let window = window.max(1);
+    by_symptom
+        .into_iter()
+        .map(|(key, mut points)| {
+            for i in 0..points.len() {
+                let trailing = &points[i.saturating_sub(window - 1)..=i];
+                let average =
+                    trailing.iter().map(|p| p.mean_severity).sum::<f32>() / trailing.len() as f32;
+                points[i].moving_average = average;
+            }
+            SeverityTrend {
+                symptom_type: key.to_string(),
+                points,
+            }
+        })
+        .collect()
+}
+
+/// Days at each flow level and a weighted intensity for every cycle's
+/// period (flow days from its start to its end, or the next cycle if still
+/// open), oldest first. Periods well above the median of the other cycles
+/// are marked heavier than usual; excluded cycles are still listed but
+/// don't count towards what is usual.
+pub fn flow_profiles(data: &AppData) -> Vec<CycleFlowProfile> {
+    let mut cycles: Vec<&Cycle> = data.cycles.iter().collect();
+    cycles.sort_by_key(|c| c.start_date);
+
+    let mut profiles: Vec<CycleFlowProfile> = cycles
+        .iter()
+        .enumerate()
+        .map(|(i, cycle)| {
+            let end = cycle.end_date.unwrap_or_else(|| {
+                cycles
+                    .get(i + 1)
+                    .and_then(|next| next.start_date.pred_opt())
+                    .unwrap_or(NaiveDate::MAX)
+            });
+            let mut days = [0usize; 4];
+            for (_, log) in data.day_logs.range(cycle.start_date..=end) {
+                days[flow_score(&log.flow_level) as usize] += 1;
+            }
+            CycleFlowProfile {
+                cycle_id: cycle.id,
+                start_date: cycle.start_date,
+                light_days: days[1],
+                medium_days: days[2],
+                heavy_days: days[3],
+                intensity: (days[1] + 2 * days[2] + 3 * days[3]) as u32,
+                relative_intensity: None,
+                heavier_than_usual: false,
+            }
+        })
+        .collect();
+
+    let excluded = prediction::excluded_starts(&data.cycles, &data.settings);
+    let intensities: Vec<(NaiveDate, f64)> = profiles
+        .iter()
+        .map(|p| (p.start_date, p.intensity as f64))
+        .collect();
+    for profile in &mut profiles {
+        let others: Vec<f64> = intensities
+            .iter()
+            .filter(|&&(start, _)| start != profile.start_date && !excluded.contains(&start))
+            .map(|&(_, v)| v)
+            .collect();
+        if others.len() < MIN_FLOW_COMPARISONS {
+            continue;
+        }
+        let usual = prediction::median(&others);
+        if usual > 0.0 {
+            let relative = (profile.intensity as f64 / usual) as f32;
+            profile.relative_intensity = Some(relative);
+            profile.heavier_than_usual = relative >= HEAVIER_THAN_USUAL;
+        }
+    }
+    profiles
+}
+
+/// Pairwise correlations between symptoms on the same day and the day
+/// after, and between each symptom and flow level. Only days with a log
+/// count, and rare symptoms and pairs are left out so a couple of
+/// coincidences don't read as a pattern.
+pub fn cooccurrence(data: &AppData) -> CooccurrenceReport {
+    let days: Vec<NaiveDate> = data.day_logs.keys().copied().collect();
+    let mut report = CooccurrenceReport {
+        logged_days: days.len(),
+        symptoms: Vec::new(),
+        with_flow: Vec::new(),
+    };
+    if days.len() < MIN_CORRELATION_DAYS {
+        return report;
+    }
+
+    let has = |date: NaiveDate, key: &str| {
+        data.symptoms
+            .get(&date)
+            .is_some_and(|logged| logged.iter().any(|s| s.symptom_type == key))
+    };
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for &date in &days {
+        for symptom in data.symptoms.get(&date).into_iter().flatten() {
+            *counts.entry(&symptom.symptom_type).or_default() += 1;
+        }
+    }
+    let mut frequent: Vec<&str> = counts
+        .into_iter()
+        .filter(|&(_, n)| n >= MIN_SYMPTOM_DAYS)
+        .map(|(key, _)| key)
+        .collect();
+    frequent.sort();
+
+    let next_day_pairs: Vec<(NaiveDate, NaiveDate)> = days
+        .windows(2)
+        .filter(|w| w[0].succ_opt() == Some(w[1]))
+        .map(|w| (w[0], w[1]))
+        .collect();
+    for &first in &frequent {
+        for &second in &frequent {
+            // Same-day correlation is symmetric, next-day isn't
+            let lags: &[i64] = match first.cmp(second) {
+                std::cmp::Ordering::Less => &[0, 1],
+                std::cmp::Ordering::Greater => &[1],
+                std::cmp::Ordering::Equal => &[],
+            };
+            for &lag_days in lags {
+                let pairs: Vec<(bool, bool)> = if lag_days == 0 {
+                    days.iter()
+                        .map(|&d| (has(d, first), has(d, second)))
+                        .collect()
+                } else {
+                    next_day_pairs
+                        .iter()
+                        .map(|&(d, next)| (has(d, first), has(next, second)))
+                        .collect()
+                };
+                let together = pairs.iter().filter(|&&(a, b)| a && b).count();
+                if together < MIN_TOGETHER {
+                    continue;
+                }
+                let (xs, ys): (Vec<f64>, Vec<f64>) = pairs
+                    .iter()
+                    .map(|&(a, b)| (f64::from(u8::from(a)), f64::from(u8::from(b))))
+                    .unzip();
+                if let Some(correlation) = pearson(&xs, &ys) {
+                    report.symptoms.push(SymptomCorrelation {
+                        first: first.to_string(),
+                        second: second.to_string(),
+                        lag_days,
+                        together,
+                        correlation,
+                    });
+                }
+            }
+        }
+    }
+
+    let flow: Vec<f64> = data
+        .day_logs
+        .values()
+        .map(|log| f64::from(flow_score(&log.flow_level)))
+        .collect();
+    for &key in &frequent {
+        let present: Vec<f64> = days
+            .iter()
+            .map(|&d| f64::from(u8::from(has(d, key))))
+            .collect();
+        let together = present
+            .iter()
+            .zip(&flow)
+            .filter(|&(&p, &f)| p > 0.0 && f > 0.0)
+            .count();
+        if together < MIN_TOGETHER {
+            continue;
+        }
+        if let Some(correlation) = pearson(&present, &flow) {
+            report.with_flow.push(FlowCorrelation {
+                symptom_type: key.to_string(),
+                together,
+                correlation,
+            });
+        }
+    }
+
+    report
+        .symptoms
+        .sort_by(|a, b| b.correlation.abs().total_cmp(&a.correlation.abs()));
+    report
+        .with_flow
+        .sort_by(|a, b| b.correlation.abs().total_cmp(&a.correlation.abs()));
+    report
+}
+
+/// Flow level as a number, 0 for none to 3 for heavy.
+fn flow_score(level: &FlowLevel) -> u32 {
+    match level {
+        FlowLevel::None => 0,
+        FlowLevel::Light => 1,
+        FlowLevel::Medium => 2,
+        FlowLevel::Heavy => 3,
+    }
+}
+
+/// Pearson correlation, or `None` when either series is constant.
+fn pearson(xs: &[f64], ys: &[f64]) -> Option<f32> {
+    let n = xs.len() as f64;
+    let (x_mean, y_mean) = (xs.iter().sum::<f64>() / n, ys.iter().sum::<f64>() / n);
+    let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        sxy += (x - x_mean) * (y - y_mean);
+        sxx += (x - x_mean).powi(2);
+        syy += (y - y_mean).powi(2);
+    }
+    (sxx > 0.0 && syy > 0.0).then(|| (sxy / (sxx * syy).sqrt()) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn heatmap_rates_per_cycle_day() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let cycle = |start: &str| Cycle {
+            id: Uuid::new_v4(),
+            start_date: date(start),
+            end_date: Some(date(start) + chrono::Duration::days(4)),
+            source: CycleSource::Detected,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let mut data = AppData {
+            // 28 and 30 days, then the open cycle
+            cycles: vec![
+                cycle("2026-01-01"),
+                cycle("2026-01-29"),
+                cycle("2026-02-28"),
+            ],
+            ..AppData::default()
+        };
+        // Cramps on day 1 of both cycles, a headache on day 2 of one
+        for (d, key) in [
+            ("2026-01-01", "cramps"),
+            ("2026-01-29", "cramps"),
+            ("2026-01-30", "headache"),
+            ("2026-03-01", "headache"),
+        ] {
+            data.symptoms.insert(
+                date(d),
+                vec![Symptom {
+                    date: date(d),
+                    symptom_type: key.to_string(),
+                    severity: 1,
+                    created_at: None,
+                    updated_at: None,
+                }],
+            );
+        }
+
+        let heatmap = symptom_heatmap(&data);
+        assert_eq!(heatmap.cycle_days, 28);
+        assert_eq!(heatmap.rows.len(), 2);
+        assert_eq!(heatmap.rows[0].symptom_type, "cramps");
+        assert_eq!(heatmap.rows[0].rates[0], 1.0);
+        assert_eq!(heatmap.rows[1].rates[1], 0.5);
+        assert_eq!(heatmap.rows[1].rates[0], 0.0);
+    }
+
+    #[test]
+    fn year_review_summarises_logs() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        let flow = [
+            ("2025-12-30", FlowLevel::Heavy),
+            ("2026-01-01", FlowLevel::Heavy),
+            ("2026-01-02", FlowLevel::Heavy),
+            ("2026-01-03", FlowLevel::Light),
+            ("2026-01-29", FlowLevel::Light),
+            ("2026-01-30", FlowLevel::Light),
+            ("2026-02-27", FlowLevel::Medium),
+        ];
+        for (d, flow_level) in flow {
+            data.day_logs.insert(
+                date(d),
+                DayLog {
+                    flow_level,
+                    ..DayLog::new(date(d))
+                },
+            );
+            data.symptoms.insert(
+                date(d),
+                vec![Symptom {
+                    date: date(d),
+                    symptom_type: "cramps".to_string(),
+                    severity: 2,
+                    created_at: None,
+                    updated_at: None,
+                }],
+            );
+        }
+        for start in ["2025-12-30", "2026-01-29", "2026-02-27"] {
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: date(start),
+                end_date: Some(date(start) + chrono::Duration::days(3)),
+                source: CycleSource::Detected,
+                include_in_stats: None,
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+        }
+
+        let review = year_review(&data, 2026);
+        assert_eq!(review.cycles, 2);
+        assert_eq!(review.avg_cycle_length, Some(29.0));
+        assert_eq!(review.top_symptoms[0].symptom_type, "cramps");
+        assert_eq!(review.top_symptoms[0].days, 6);
+        assert_eq!(review.heaviest_month, Some(1));
+        assert_eq!(review.lightest_month, Some(2));
+        assert_eq!(review.logged_days, 6);
+        assert_eq!(review.longest_logging_streak, 3);
+        assert!(review.regularity.is_none());
+    }
+
+    #[test]
+    fn excluded_cycles_skipped_unless_asked() {
+        let mut data = AppData::default();
+        let mut start = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        for length in [28, 28, 28, 40, 28] {
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: start,
+                end_date: Some(start + chrono::Duration::days(4)),
+                source: CycleSource::Detected,
+                include_in_stats: (length == 40).then_some(false),
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+            start += chrono::Duration::days(length);
+        }
+
+        let review = year_review(&data, 2026);
+        assert_eq!(review.avg_cycle_length, Some(28.0));
+        assert_eq!(symptom_heatmap(&data).cycles_reached[0], 3);
+
+        let everything = prediction::including_excluded(&data);
+        let review = year_review(&everything, 2026);
+        assert_eq!(review.avg_cycle_length, Some(31.0));
+        assert_eq!(symptom_heatmap(&everything).cycles_reached[0], 4);
+    }
+
+    #[test]
+    fn pill_adherence_and_breakthrough_bleeding() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        data.settings.contraception_mode = ContraceptionMode::Pill {
+            pack_start: date("2026-03-01"),
+            active_days: 21,
+            break_days: 7,
+        };
+        for day in date("2026-03-01").iter_days().take(30) {
+            let taken = !["2026-03-05", "2026-03-06", "2026-03-30"]
+                .contains(&day.format("%Y-%m-%d").to_string().as_str());
+            data.pill_doses.push(PillDose { date: day, taken });
+        }
+        data.day_logs.insert(
+            date("2026-03-08"),
+            DayLog {
+                flow_level: FlowLevel::Light,
+                ..DayLog::new(date("2026-03-08"))
+            },
+        );
+
+        let adherence = pill_adherence(&data, date("2026-03-30")).unwrap();
+        // 21 active days in the first pack, 2 in the second; break days
+        // logged as taken don't count
+        assert_eq!(adherence.scheduled_days, 23);
+        assert_eq!((adherence.taken, adherence.missed), (20, 3));
+        assert_eq!(adherence.current_missed_streak, 1);
+        assert_eq!(adherence.longest_missed_streak, 2);
+        // Mar 6-9 follow a missed pill, and Mar 8 had bleeding
+        assert_eq!(adherence.bleeding_after_missed, Some(0.25));
+        assert_eq!(adherence.bleeding_otherwise, Some(0.0));
+
+        data.settings.contraception_mode = ContraceptionMode::None;
+        assert!(pill_adherence(&data, date("2026-03-30")).is_none());
+    }
+
+    #[test]
+    fn symptoms_summarised_per_month() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        for (day, key, severity) in [
+            ("2026-01-03", "cramps", 3),
+            ("2026-01-04", "cramps", 2),
+            ("2026-01-04", "headache", 1),
+            ("2026-03-20", "bloating", 2),
+            ("2025-12-31", "cramps", 3),
+        ] {
+            data.symptoms.entry(date(day)).or_default().push(Symptom {
+                date: date(day),
+                symptom_type: key.to_string(),
+                severity,
+                created_at: None,
+                updated_at: None,
+            });
+        }
+
+        let months = monthly_symptom_summary(&data, 2026);
+        assert_eq!(months.len(), 12);
+        let january = &months[0].symptoms;
+        assert_eq!(january[0].symptom_type, "cramps");
+        assert_eq!(january[0].days, 2);
+        assert!((january[0].avg_severity - 2.5).abs() < 1e-6);
+        assert_eq!(january[1].symptom_type, "headache");
+        assert!(months[1].symptoms.is_empty());
+        assert_eq!(months[2].symptoms[0].symptom_type, "bloating");
+    }
+
+    #[test]
+    fn logging_streaks_and_completeness() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        for day in [
+            "2026-02-10",
+            "2026-02-11",
+            "2026-02-12",
+            "2026-02-13",
+            "2026-03-01",
+            "2026-03-02",
+            "2026-03-03",
+        ] {
+            data.symptoms.insert(
+                date(day),
+                vec![Symptom {
+                    date: date(day),
+                    symptom_type: "cramps".to_string(),
+                    severity: 1,
+                    created_at: None,
+                    updated_at: None,
+                }],
+            );
+        }
+
+        // Nothing logged yet today, but the streak is still alive
+        let stats = logging_stats(&data, date("2026-03-04"));
+        assert_eq!(stats.current_streak, 3);
+        assert_eq!(stats.longest_streak, 4);
+        assert_eq!(stats.months.len(), 12);
+        let march = stats.months.last().unwrap();
+        assert_eq!((march.year, march.month), (2026, 3));
+        assert_eq!((march.logged_days, march.days), (3, 4));
+        assert_eq!(stats.months[10].logged_days, 4);
+        assert_eq!(stats.months[10].days, 28);
+
+        assert_eq!(logging_stats(&data, date("2026-03-05")).current_streak, 0);
+    }
+
+    #[test]
+    fn series_bucketed_and_smoothed() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        // Monday Mar 2 to Sunday Mar 15, severity rising by one each week
+        for (d, severity) in [
+            ("2026-03-02", 1),
+            ("2026-03-04", 1),
+            ("2026-03-09", 2),
+            ("2026-03-15", 3),
+        ] {
+            data.symptoms.insert(
+                date(d),
+                vec![Symptom {
+                    date: date(d),
+                    symptom_type: "cramps".to_string(),
+                    severity,
+                    created_at: None,
+                    updated_at: None,
+                }],
+            );
+        }
+        let metric = SeriesMetric::Symptom {
+            symptom_type: "cramps".to_string(),
+        };
+        let weekly = series(
+            &data,
+            &metric,
+            date("2026-03-01"),
+            date("2026-03-31"),
+            SeriesResolution::Week,
+            None,
+        );
+        assert_eq!(
+            weekly,
+            [
+                SeriesPoint {
+                    date: date("2026-03-02"),
+                    value: 1.0
+                },
+                SeriesPoint {
+                    date: date("2026-03-09"),
+                    value: 2.5
+                },
+            ]
+        );
+
+        let smoothed = series(
+            &data,
+            &metric,
+            date("2026-03-01"),
+            date("2026-03-31"),
+            SeriesResolution::Day,
+            Some(2),
+        );
+        let values: Vec<f32> = smoothed.iter().map(|p| p.value).collect();
+        assert_eq!(values, [1.0, 1.0, 1.5, 2.5]);
+    }
+
+    #[test]
+    fn bloating_precedes_cramps() {
+        let mut data = AppData::default();
+        let first = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        for i in 0..60 {
+            let date = first + chrono::Duration::days(i);
+            // Bloating every 10th day, cramps the day after with flow
+            let (flow_level, key) = match i % 10 {
+                0 => (FlowLevel::None, Some("bloating")),
+                1 => (FlowLevel::Heavy, Some("cramps")),
+                _ => (FlowLevel::None, None),
+            };
+            data.day_logs.insert(
+                date,
+                DayLog {
+                    flow_level,
+                    ..DayLog::new(date)
+                },
+            );
+            if let Some(key) = key {
+                data.symptoms.insert(
+                    date,
+                    vec![Symptom {
+                        date,
+                        symptom_type: key.to_string(),
+                        severity: 2,
+                        created_at: None,
+                        updated_at: None,
+                    }],
+                );
+            }
+        }
+
+        let report = cooccurrence(&data);
+        assert_eq!(report.logged_days, 60);
+        assert_eq!(report.symptoms.len(), 1);
+        let pair = &report.symptoms[0];
+        assert_eq!(
+            (pair.first.as_str(), pair.second.as_str()),
+            ("bloating", "cramps")
+        );
+        assert_eq!(pair.lag_days, 1);
+        assert!((pair.correlation - 1.0).abs() < 1e-6);
+        assert_eq!(report.with_flow[0].symptom_type, "cramps");
+        assert!((report.with_flow[0].correlation - 1.0).abs() < 1e-6);
+
+        // Too few days to say anything
+        data.day_logs
+            .retain(|date, _| *date < first + chrono::Duration::days(20));
+        assert!(cooccurrence(&data).symptoms.is_empty());
+    }
+
+    #[test]
+    fn current_cycle_against_same_days_before() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        // Three days of medium flow in past cycles, five heavy this time
+        for (start, days, flow_level) in [
+            ("2026-01-01", 3, FlowLevel::Medium),
+            ("2026-01-29", 3, FlowLevel::Medium),
+            ("2026-02-26", 5, FlowLevel::Heavy),
+        ] {
+            let start = date(start);
+            for offset in 0..days {
+                let day = start + chrono::Duration::days(offset);
+                data.day_logs.insert(
+                    day,
+                    DayLog {
+                        flow_level: flow_level.clone(),
+                        ..DayLog::new(day)
+                    },
+                );
+                data.symptoms.insert(
+                    day,
+                    vec![Symptom {
+                        date: day,
+                        symptom_type: "cramps".to_string(),
+                        severity: 2,
+                        created_at: None,
+                        updated_at: None,
+                    }],
+                );
+            }
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: start,
+                end_date: Some(start + chrono::Duration::days(days - 1)),
+                source: CycleSource::Detected,
+                include_in_stats: None,
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+        }
+
+        let comparison = compare_current_cycle(&data, date("2026-03-02")).unwrap();
+        assert_eq!(comparison.cycle_day, 5);
+        assert_eq!(comparison.past_cycles, 2);
+        assert_eq!(comparison.flow_days, 5);
+        assert_eq!(comparison.typical_flow_days, Some(3.0));
+        assert_eq!(comparison.flow_intensity, 15);
+        assert_eq!(comparison.typical_flow_intensity, Some(6.0));
+        assert_eq!(comparison.symptoms[0].days_so_far, 5);
+        assert_eq!(comparison.symptoms[0].typical_days, 3.0);
+
+        assert!(compare_current_cycle(&data, date("2025-12-31")).is_none());
+    }
+
+    #[test]
+    fn overlay_lines_up_recent_cycles() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        for start in ["2026-01-01", "2026-01-29", "2026-02-26", "2026-03-25"] {
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: date(start),
+                end_date: Some(date(start) + chrono::Duration::days(4)),
+                source: CycleSource::Detected,
+                include_in_stats: None,
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+        }
+        data.symptoms.insert(
+            date("2026-02-27"),
+            vec![Symptom {
+                date: date("2026-02-27"),
+                symptom_type: "cramps".to_string(),
+                severity: 2,
+                created_at: None,
+                updated_at: None,
+            }],
+        );
+
+        let overlay = cycle_overlay(&data, date("2026-03-30"), 2);
+        assert_eq!(overlay.len(), 2);
+        assert_eq!(overlay[0].cycle_start, date("2026-02-26"));
+        assert!(!overlay[0].current);
+        assert_eq!(overlay[0].days.len(), 27);
+        assert_eq!(overlay[0].days[1].symptoms, [("cramps".to_string(), 2)]);
+        assert!(overlay[1].current);
+        assert_eq!(overlay[1].days.len(), 6);
+    }
+
+    #[test]
+    fn bbt_chart_smooths_around_disturbed_days() {
+        let start = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let mut data = AppData::default();
+        data.cycles.push(Cycle {
+            id: Uuid::new_v4(),
+            start_date: start,
+            end_date: Some(start + chrono::Duration::days(4)),
+            source: CycleSource::Detected,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: None,
+            updated_at: None,
+        });
+        for (i, t) in [36.4, 36.6, 37.5, 36.5].into_iter().enumerate() {
+            let date = start + chrono::Duration::days(i as i64);
+            data.day_logs.insert(
+                date,
+                DayLog {
+                    bbt: Some(t),
+                    bbt_disturbances: if i == 2 {
+                        vec![BbtDisturbance::Alcohol]
+                    } else {
+                        Vec::new()
+                    },
+                    ..DayLog::new(date)
+                },
+            );
+        }
+
+        let chart = bbt_chart(&data, start + chrono::Duration::days(3)).unwrap();
+        assert_eq!(chart.points.len(), 4);
+        assert_eq!(chart.points[2].smoothed, None);
+        assert!((chart.points[1].smoothed.unwrap() - 36.5).abs() < 1e-4);
+        assert_eq!(chart.coverline, None);
+    }
+
+    #[test]
+    fn same_cycle_day_in_earlier_cycles() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        // 28, 22 and 30 day cycles, then the current one
+        for start in ["2026-01-01", "2026-01-29", "2026-02-20", "2026-03-22"] {
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: date(start),
+                end_date: Some(date(start) + chrono::Duration::days(4)),
+                source: CycleSource::Detected,
+                include_in_stats: None,
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+        }
+        data.symptoms.insert(
+            date("2026-01-25"),
+            vec![Symptom {
+                date: date("2026-01-25"),
+                symptom_type: "cramps".to_string(),
+                severity: 2,
+                created_at: None,
+                updated_at: None,
+            }],
+        );
+
+        // Cycle day 25: the 22-day cycle never got there
+        let lookup = same_cycle_day(&data, date("2026-04-15")).unwrap();
+        assert_eq!(lookup.cycle_day, 25);
+        let dates: Vec<NaiveDate> = lookup.previous.iter().map(|p| p.date).collect();
+        assert_eq!(dates, [date("2026-03-16"), date("2026-01-25")]);
+        assert_eq!(lookup.previous[1].symptoms[0].symptom_type, "cramps");
+    }
+
+    #[test]
+    fn cramp_severity_trend_eases() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        for (start, severity) in [
+            ("2026-01-01", 3),
+            ("2026-01-29", 3),
+            ("2026-02-26", 2),
+            ("2026-03-26", 1),
+        ] {
+            let start = date(start);
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: start,
+                end_date: Some(start + chrono::Duration::days(4)),
+                source: CycleSource::Detected,
+                include_in_stats: None,
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+            data.symptoms.insert(
+                start,
+                vec![Symptom {
+                    date: start,
+                    symptom_type: "cramps".to_string(),
+                    severity,
+                    created_at: None,
+                    updated_at: None,
+                }],
+            );
+        }
+
+        let trends = severity_trends(&data, 3);
+        assert_eq!(trends.len(), 1);
+        let averages: Vec<f32> = trends[0].points.iter().map(|p| p.moving_average).collect();
+        assert_eq!(averages, [3.0, 3.0, 8.0 / 3.0, 2.0]);
+    }
+
+    #[test]
+    fn heavier_period_stands_out() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        for (start, heavy) in [
+            ("2026-01-01", 1),
+            ("2026-01-29", 1),
+            ("2026-02-26", 1),
+            ("2026-03-26", 4),
+        ] {
+            let start = date(start);
+            for offset in 0..4 {
+                let day = start + chrono::Duration::days(offset);
+                let flow_level = if offset < heavy {
+                    FlowLevel::Heavy
+                } else {
+                    FlowLevel::Light
+                };
+                data.day_logs.insert(
+                    day,
+                    DayLog {
+                        flow_level,
+                        ..DayLog::new(day)
+                    },
+                );
+            }
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: start,
+                end_date: Some(start + chrono::Duration::days(3)),
+                source: CycleSource::Detected,
+                include_in_stats: None,
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+        }
+
+        let profiles = flow_profiles(&data);
+        assert_eq!(profiles.len(), 4);
+        assert_eq!(
+            (
+                profiles[0].light_days,
+                profiles[0].heavy_days,
+                profiles[0].intensity
+            ),
+            (3, 1, 6)
+        );
+        assert_eq!(profiles[3].intensity, 12);
+        assert_eq!(profiles[3].relative_intensity, Some(2.0));
+        assert!(profiles[3].heavier_than_usual);
+        assert!(!profiles[0].heavier_than_usual);
+    }
+
+    #[test]
+    fn rolling_windows_track_recent_change() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        // A year of 28-day cycles, then 35-day ones
+        let mut start = date("2025-01-01");
+        for length in [28; 13].into_iter().chain([35; 4]) {
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: start,
+                end_date: Some(start + chrono::Duration::days(4)),
+                source: CycleSource::Detected,
+                include_in_stats: None,
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+            start += chrono::Duration::days(length);
+        }
+        let today = start - chrono::Duration::days(1);
+
+        let series = rolling_trends(&data, today);
+        assert_eq!(series.len(), 3);
+        assert!(series.iter().all(|s| s.points.len() == 12));
+        let latest = |months: u32| {
+            let series = series.iter().find(|s| s.window_months == months).unwrap();
+            let point = series.points.last().unwrap();
+            assert_eq!(point.end, today);
+            assert_eq!(point.avg_period_length, Some(5.0));
+            point.avg_cycle_length.unwrap()
+        };
+        assert_eq!(latest(3), 35.0);
+        assert!(latest(12) < latest(6));
+        assert!(latest(6) < 35.0);
+    }
+}
@@ -0,0 +1,148 @@
+//! The `.cykelbak` backup bundle: everything needed to restore the app on
+//! another device, under its own passphrase.
+//!
+//! ```text
+//! "CYKELBAK" | format version (1 byte) | salt (32) | nonce (12) | ciphertext
+//! ```
+//!
+//! The header is left in the clear so a file can be recognised before
+//! asking for a passphrase. The rest is [`crypto::encrypt`] output, whose
+//! plaintext is the JSON of [`Contents`]: a manifest, the app data and
+//! attachments. AES-GCM already rejects a bundle altered in any way; the
+//! manifest's record counts also catch one written from incomplete data.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::{self, CryptoError};
+use crate::migrations;
+use crate::models::*;
+
+const HEADER: &[u8] = b"CYKELBAK";
+/// Bumped whenever the layout above or [`Contents`] changes incompatibly.
+pub const BUNDLE_FORMAT_VERSION: u8 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BundleError {
+    #[error("not a cykel backup")]
+    NotABundle,
+    #[error("backup format {0} is newer than this version of cykel supports")]
+    UnsupportedFormat(u8),
+    #[error("backup schema {0} is newer than this version of cykel supports")]
+    NewerSchema(u32),
+    #[error("backup contents don't match its manifest")]
+    Incomplete,
+    #[error(transparent)]
+    Crypto(#[from] CryptoError),
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Contents {
+    manifest: BundleManifest,
+    data: AppData,
+    /// Files kept alongside the data. Nothing in the app has attachments
+    /// yet; the list is part of format 1 so they can be added without a
+    /// new format.
+    #[serde(default)]
+    attachments: Vec<BundleAttachment>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BundleAttachment {
+    name: String,
+    media_type: String,
+    content: Vec<u8>,
+}
+
+/// A bundle of `data` encrypted with `passphrase`.
+pub fn create(
+    data: &AppData,
+    passphrase: &str,
+    created_at: DateTime<Utc>,
+) -> Result<(BundleManifest, Vec<u8>), BundleError> {
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        schema_version: data.schema_version,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at,
+        records: RecordCounts::of(data),
+    };
+    let contents = Contents {
+        manifest: manifest.clone(),
+        data: data.clone(),
+        attachments: Vec::new(),
+    };
+    let json = zeroize::Zeroizing::new(serde_json::to_vec(&contents)?);
+
+    let mut bundle = HEADER.to_vec();
+    bundle.push(BUNDLE_FORMAT_VERSION);
+    bundle.extend(crypto::encrypt(passphrase, &json)?);
+    Ok((manifest, bundle))
+}
+
+/// The manifest and data in `bundle`, checked against each other but not
+/// yet migrated.
+pub fn open(bundle: &[u8], passphrase: &str) -> Result<(BundleManifest, AppData), BundleError> {
+    let rest = bundle.strip_prefix(HEADER).ok_or(BundleError::NotABundle)?;
+    let (&format_version, encrypted) = rest.split_first().ok_or(BundleError::NotABundle)?;
+    if format_version > BUNDLE_FORMAT_VERSION {
+        return Err(BundleError::UnsupportedFormat(format_version));
+    }
+
+    let json = zeroize::Zeroizing::new(crypto::decrypt(passphrase, encrypted)?);
+    let contents: Contents = serde_json::from_slice(&json)?;
+    let manifest = contents.manifest;
+    if manifest.schema_version > migrations::CURRENT_SCHEMA_VERSION {
+        return Err(BundleError::NewerSchema(manifest.schema_version));
+    }
+    if manifest.records != RecordCounts::of(&contents.data) {
+        return Err(BundleError::Incomplete);
+    }
+    Ok((manifest, contents.data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn bundle_roundtrip_and_rejections() {
+        let mut data = AppData::default();
+        migrations::migrate(&mut data);
+        let date = NaiveDate::from_ymd_opt(2026, 1, 4).unwrap();
+        data.day_logs.insert(
+            date,
+            DayLog {
+                flow_level: FlowLevel::Medium,
+                notes: "backed up".to_string(),
+                ..DayLog::new(date)
+            },
+        );
+
+        let (manifest, bundle) = create(&data, "bundle pass", Utc::now()).unwrap();
+        assert!(bundle.starts_with(b"CYKELBAK\x01"));
+        assert_eq!(manifest.records.day_logs, 1);
+
+        let (opened, restored) = open(&bundle, "bundle pass").unwrap();
+        assert_eq!(opened.records, manifest.records);
+        assert_eq!(restored.day_logs, data.day_logs);
+
+        assert!(matches!(
+            open(&bundle, "wrong"),
+            Err(BundleError::Crypto(CryptoError::Decryption))
+        ));
+        assert!(matches!(
+            open(b"{\"cycles\": []}", "bundle pass"),
+            Err(BundleError::NotABundle)
+        ));
+        let mut newer = bundle.clone();
+        newer[HEADER.len()] = BUNDLE_FORMAT_VERSION + 1;
+        assert!(matches!(
+            open(&newer, "bundle pass"),
+            Err(BundleError::UnsupportedFormat(_))
+        ));
+    }
+}
@@ -1,17 +1,76 @@
-use chrono::NaiveDate;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
-use tauri::State;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
 use uuid::Uuid;
 use zeroize::Zeroize;
 
+use crate::analytics;
+use crate::bundle;
+use crate::error::CommandError;
+use crate::export;
+use crate::importers::{self, ImportSource};
+use crate::insights;
+use crate::jobs;
+use crate::merge;
+use crate::migrations;
 use crate::models::*;
 use crate::prediction;
+use crate::progress::{Cancellations, Progress};
+use crate::report;
+use crate::search;
 use crate::storage;
+use crate::symptoms;
+use crate::timeline;
+use crate::transfer;
+use crate::undo::{self, History};
+use crate::validation::{self, Validator};
+
+/// Emitted after the app locks, whether asked to or on its own.
+pub const LOCKED_EVENT: &str = "cykel://locked";
+/// Emitted after setup or a successful unlock.
+pub const UNLOCKED_EVENT: &str = "cykel://unlocked";
+/// Emitted after the data file was written.
+pub const SAVED_EVENT: &str = "cykel://saved";
+
+/// How often the idle session is checked for auto-lock.
+const AUTO_LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
 
 /// App state holding the decrypted data and passphrase while unlocked.
 pub struct AppState {
     pub passphrase: Mutex<Option<String>>,
     pub data: Mutex<Option<AppData>>,
+    /// Bumped whenever `data` changes, so derived results can be reused
+    /// until then.
+    generation: AtomicU64,
+    derived: Mutex<Option<Derived>>,
+    /// Long-running operations the user asked to stop.
+    pub cancellations: Cancellations,
+    /// Recently saved versions, for undo.
+    history: History,
+    /// Set once the app is running, so state changes can be announced.
+    app: Mutex<Option<AppHandle>>,
+    /// `None` while locked.
+    session: Mutex<Option<Session>>,
+}
+
+struct Session {
+    started_at: DateTime<Utc>,
+    last_active: Instant,
+}
+
+/// Predictions and stats for one generation of the data. They also depend
+/// on today's date, so a new day invalidates them too.
+#[derive(Clone)]
+struct Derived {
+    generation: u64,
+    today: NaiveDate,
+    predictions: Vec<Prediction>,
+    fertility: Option<FertilityWindow>,
+    stats: CycleStats,
 }
 
 impl AppState {
@@ -19,6 +78,69 @@ impl AppState {
         Self {
             passphrase: Mutex::new(None),
             data: Mutex::new(None),
+            generation: AtomicU64::new(0),
+            derived: Mutex::new(None),
+            cancellations: Cancellations::default(),
+            history: History::default(),
+            app: Mutex::new(None),
+            session: Mutex::new(None),
+        }
+    }
+
+    fn start_session(&self) -> Result<(), CommandError> {
+        *self.session.lock()? = Some(Session {
+            started_at: Utc::now(),
+            last_active: Instant::now(),
+        });
+        Ok(())
+    }
+
+    /// Push auto-lock back, as if the user had just done something.
+    fn keep_alive(&self) -> Result<(), CommandError> {
+        if let Some(session) = self.session.lock()?.as_mut() {
+            session.last_active = Instant::now();
+        }
+        Ok(())
+    }
+
+    fn lock_status(&self) -> Result<LockStatus, CommandError> {
+        let auto_lock_minutes = match self.data.lock()?.as_ref() {
+            Some(data) => data.settings.auto_lock_minutes,
+            None => return Ok(LockStatus::default()),
+        };
+        let session = self.session.lock()?;
+        Ok(LockStatus {
+            unlocked: true,
+            session_started: session.as_ref().map(|s| s.started_at),
+            seconds_until_lock: session
+                .as_ref()
+                .map(|s| seconds_until_lock(s.last_active.elapsed(), auto_lock_minutes)),
+        })
+    }
+
+    /// Lock once nothing has happened for `auto_lock_minutes`.
+    fn lock_if_idle(&self) {
+        let due = self
+            .lock_status()
+            .is_ok_and(|status| status.seconds_until_lock == Some(0));
+        if due {
+            self.lock();
+        }
+    }
+
+    /// Hand over the app handle once Tauri has built it.
+    pub fn attach(&self, app: AppHandle) {
+        if let Ok(mut handle) = self.app.lock() {
+            *handle = Some(app);
+        }
+    }
+
+    /// Tell every window about a state change. Nobody listening is fine.
+    fn announce(&self, event: &str) {
+        if let Ok(handle) = self.app.lock() {
+            if let Some(app) = handle.as_ref() {
+                let _ = app.emit(event, ());
+            }
         }
     }
 
@@ -33,42 +155,115 @@ impl AppState {
         if let Ok(mut data) = self.data.lock() {
             *data = None;
         }
+        if let Ok(mut derived) = self.derived.lock() {
+            *derived = None;
+        }
+        self.history.clear();
+        if let Ok(mut session) = self.session.lock() {
+            *session = None;
+        }
+        self.touch();
+        self.announce(LOCKED_EVENT);
+    }
+
+    /// Invalidate derived results after `data` changed.
+    fn touch(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Predictions, fertility window and stats for `data`, recomputed only
+    /// when the data changed since they were last asked for.
+    fn derived(&self, data: &AppData) -> Result<Derived, CommandError> {
+        let generation = self.generation.load(Ordering::SeqCst);
+        let today = chrono::Local::now().date_naive();
+        let mut cache = self.derived.lock()?;
+        if let Some(derived) = cache
+            .as_ref()
+            .filter(|d| d.generation == generation && d.today == today)
+        {
+            return Ok(derived.clone());
+        }
+
+        let derived = Derived {
+            generation,
+            today,
+            predictions: prediction::upcoming(data, 1),
+            fertility: if data.settings.show_fertility {
+                prediction::fertility_window(data)
+            } else {
+                None
+            },
+            stats: stats_for(data),
+        };
+        *cache = Some(derived.clone());
+        Ok(derived)
     }
 
-    fn save_data(&self) -> Result<(), String> {
-        let pass = self.passphrase.lock().map_err(|e| e.to_string())?;
-        let data = self.data.lock().map_err(|e| e.to_string())?;
+    /// Every mutation ends by saving, so this is also where the cache is
+    /// invalidated.
+    fn save_data(&self) -> Result<(), CommandError> {
+        self.touch();
+        let pass = self.passphrase.lock()?;
+        let data = self.data.lock()?;
         match (pass.as_ref(), data.as_ref()) {
-            (Some(p), Some(d)) => storage::save(p, d).map_err(|e| e.to_string()),
-            _ => Err("app is locked".into()),
+            (Some(p), Some(d)) => {
+                storage::save(p, d)?;
+                self.history.record(d)?;
+                self.keep_alive()?;
+            }
+            _ => return Err(CommandError::Locked),
         }
+        self.announce(SAVED_EVENT);
+        Ok(())
     }
 }
 
 #[tauri::command]
-pub fn is_setup() -> Result<bool, String> {
-    storage::data_exists().map_err(|e| e.to_string())
+pub fn is_setup() -> Result<bool, CommandError> {
+    Ok(storage::data_exists()?)
+}
+
+#[tauri::command]
+pub fn get_app_info(state: State<'_, AppState>) -> Result<AppInfo, CommandError> {
+    let data_lock = state.data.lock()?;
+    let records = data_lock.as_ref().map(RecordCounts::of);
+    Ok(AppInfo {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version: migrations::CURRENT_SCHEMA_VERSION,
+        storage_layout: storage::layout()?,
+        data_file_bytes: storage::data_file_size()?,
+        records,
+    })
 }
 
 #[tauri::command]
-pub fn setup(passphrase: String, state: State<'_, AppState>) -> Result<(), String> {
-    let data = AppData::default();
-    storage::save(&passphrase, &data).map_err(|e| e.to_string())?;
+pub fn setup(passphrase: String, state: State<'_, AppState>) -> Result<(), CommandError> {
+    let mut data = AppData::default();
+    migrations::migrate(&mut data);
+    storage::save(&passphrase, &data)?;
+    state.history.record(&data)?;
 
-    *state.passphrase.lock().map_err(|e| e.to_string())? = Some(passphrase);
-    *state.data.lock().map_err(|e| e.to_string())? = Some(data);
+    *state.passphrase.lock()? = Some(passphrase);
+    *state.data.lock()? = Some(data);
+    state.touch();
+    state.start_session()?;
+    state.announce(UNLOCKED_EVENT);
 
     Ok(())
 }
 
 #[tauri::command]
-pub fn unlock(passphrase: String, state: State<'_, AppState>) -> Result<bool, String> {
+pub fn unlock(passphrase: String, state: State<'_, AppState>) -> Result<bool, CommandError> {
     match storage::load(&passphrase) {
         Ok(mut data) => {
-            rebuild_cycles(&mut data);
-            *state.passphrase.lock().map_err(|e| e.to_string())? = Some(passphrase.clone());
-            *state.data.lock().map_err(|e| e.to_string())? = Some(data);
+            migrations::migrate(&mut data);
+            prediction::rebuild_cycles(&mut data);
+            prediction::accuracy::record(&mut data, chrono::Local::now().date_naive());
+            *state.passphrase.lock()? = Some(passphrase.clone());
+            *state.data.lock()? = Some(data);
+            state.start_session()?;
             state.save_data()?;
+            state.announce(UNLOCKED_EVENT);
             Ok(true)
         }
         Err(_) => Ok(false),
@@ -76,204 +271,3025 @@ pub fn unlock(passphrase: String, state: State<'_, AppState>) -> Result<bool, St
 }
 
 #[tauri::command]
-pub fn lock(state: State<'_, AppState>) -> Result<(), String> {
+pub fn lock(state: State<'_, AppState>) -> Result<(), CommandError> {
     state.lock();
     Ok(())
 }
 
+/// Whether the app is unlocked and how long until it locks itself.
+#[tauri::command]
+pub fn get_lock_status(state: State<'_, AppState>) -> Result<LockStatus, CommandError> {
+    state.lock_status()
+}
+
+/// Restart the auto-lock countdown, e.g. from a "stay unlocked" prompt or
+/// while the user is only reading.
+#[tauri::command]
+pub fn keep_unlocked(state: State<'_, AppState>) -> Result<LockStatus, CommandError> {
+    state.keep_alive()?;
+    state.lock_status()
+}
+
+/// Check in the background whether the session has been idle long enough
+/// to lock, for as long as the app runs.
+pub fn spawn_auto_lock(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(AUTO_LOCK_CHECK_INTERVAL);
+        app.state::<AppState>().lock_if_idle();
+    });
+}
+
+fn seconds_until_lock(idle: Duration, auto_lock_minutes: u32) -> u64 {
+    (u64::from(auto_lock_minutes) * 60).saturating_sub(idle.as_secs())
+}
+
+/// Go back to how the data was before the last change. Returns what was
+/// reverted, or `None` when there is nothing left to undo.
 #[tauri::command]
+pub fn undo_last_change(state: State<'_, AppState>) -> Result<Option<String>, CommandError> {
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let Some(previous) = state.history.undo()? else {
+        return Ok(None);
+    };
+    let description = undo::describe(data, &previous);
+    *data = previous;
+    drop(data_lock);
+    state.save_data()?;
+    Ok(Some(description))
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn log_day(
     date: String,
     flow_level: FlowLevel,
     notes: String,
-    symptoms: Vec<(SymptomType, u8)>,
+    symptoms: Vec<(String, u8)>,
+    cervix: Option<CervixObservation>,
+    libido: Option<u8>,
+    bbt: Option<f32>,
+    lh_test: Option<LhTestResult>,
+    bbt_disturbances: Option<Vec<BbtDisturbance>>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|e| e.to_string())?;
+) -> Result<(), CommandError> {
+    let mut v = Validator::new();
+    let date = v.past_date("date", &date);
+    v.max_chars("notes", &notes, validation::MAX_NOTE_CHARS);
 
-    let mut data_lock = state.data.lock().map_err(|e| e.to_string())?;
-    let data = data_lock.as_mut().ok_or("app is locked")?;
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+
+    // BBT arrives in the user's unit; store it in Celsius
+    let unit = data.settings.temperature_unit;
+    if let Some(t) = bbt {
+        v.in_range(
+            "bbt",
+            t,
+            unit.convert_celsius(validation::MIN_BBT_CELSIUS),
+            unit.convert_celsius(validation::MAX_BBT_CELSIUS),
+        );
+    }
+    let bbt = bbt.map(|t| round_temperature(unit.to_celsius(t)));
 
-    // Upsert day log
-    if let Some(existing) = data.day_logs.iter_mut().find(|l| l.date == date) {
-        existing.flow_level = flow_level.clone();
-        existing.notes = notes;
+    let libido = if data.settings.track_libido {
+        if let Some(l) = libido {
+            v.in_range("libido", l, 1, 3);
+        }
+        libido
     } else {
-        data.day_logs.push(DayLog {
+        None
+    };
+
+    // Resolve every symptom before touching the log, so an unknown key
+    // doesn't leave the day half-updated
+    let resolved = resolve_symptoms(&mut v, &data.symptom_registry, symptoms);
+
+    v.finish()?;
+    let date = date.expect("validated above");
+    let now = Utc::now();
+
+    // Upsert day log, keeping when it was first created and where an
+    // unchanged temperature came from
+    let previous = data.day_logs.get(&date);
+    let created_at = previous.map_or(Some(now), |log| log.created_at);
+    let bbt_source = match previous {
+        Some(log) if bbt.is_some() && log.bbt == bbt => log.bbt_source,
+        _ => TemperatureSource::Thermometer,
+    };
+    data.day_logs.insert(
+        date,
+        DayLog {
             date,
-            flow_level: flow_level.clone(),
+            flow_level,
             notes,
-        });
-    }
+            cervix,
+            libido,
+            bbt,
+            lh_test,
+            bbt_disturbances: bbt_disturbances.unwrap_or_default(),
+            bbt_source,
+            created_at,
+            updated_at: Some(now),
+        },
+    );
 
     // Replace symptoms for this date
-    data.symptoms.retain(|s| s.date != date);
-    for (symptom_type, severity) in symptoms {
-        data.symptoms.push(Symptom {
-            date,
-            symptom_type,
-            severity: severity.clamp(1, 3),
+    let previous = data.symptoms.get(&date).map_or(&[][..], Vec::as_slice);
+    let symptoms = stamp_symptoms(previous, resolved, date, now);
+    if symptoms.is_empty() {
+        data.symptoms.remove(&date);
+    } else {
+        data.symptoms.insert(date, symptoms);
+    }
+
+    prediction::rebuild_cycles(data);
+    prediction::accuracy::record(data, chrono::Local::now().date_naive());
+
+    drop(data_lock);
+    state.save_data()?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_symptom_registry(
+    state: State<'_, AppState>,
+) -> Result<Vec<SymptomDescriptor>, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(data.symptom_registry.clone())
+}
+
+/// Symptoms for the logging UI, in the user's order with hidden ones
+/// marked.
+#[tauri::command]
+pub fn get_symptom_catalogue(
+    state: State<'_, AppState>,
+) -> Result<Vec<CatalogueSymptom>, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(symptoms::catalogue(&data.symptom_registry, &data.settings))
+}
+
+#[tauri::command]
+pub fn add_custom_symptom(
+    label: String,
+    category: SymptomCategory,
+    max_severity: u8,
+    state: State<'_, AppState>,
+) -> Result<SymptomDescriptor, CommandError> {
+    let label = label.trim().to_string();
+    let mut v = Validator::new();
+    v.required("label", &label);
+    v.max_chars("label", &label, validation::MAX_LABEL_CHARS);
+    v.in_range("max_severity", max_severity, 1, 10);
+    v.finish()?;
+
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    if data
+        .symptom_registry
+        .iter()
+        .any(|d| d.label.eq_ignore_ascii_case(&label))
+    {
+        return Err(CommandError::Conflict {
+            message: format!("a symptom called \"{label}\" already exists"),
         });
     }
+    let descriptor = SymptomDescriptor {
+        key: symptoms::custom_key(&label, &data.symptom_registry),
+        label,
+        category,
+        max_severity,
+        builtin: false,
+    };
+    data.symptom_registry.push(descriptor.clone());
+    drop(data_lock);
+    state.save_data()?;
+    Ok(descriptor)
+}
 
-    rebuild_cycles(data);
+/// Set today's flow in one call, e.g. from the tray menu when a period
+/// starts. Notes, symptoms and everything else logged today are kept.
+#[tauri::command]
+pub fn quick_log_today(
+    flow_level: FlowLevel,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let today = chrono::Local::now().date_naive();
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    set_flow(data, vec![(today, today, flow_level)], Utc::now());
+    prediction::accuracy::record(data, today);
 
     drop(data_lock);
     state.save_data()?;
     Ok(())
 }
 
-/// Rebuild cycles from flow data.
-fn rebuild_cycles(data: &mut AppData) {
-    let mut flow_days: Vec<NaiveDate> = data
-        .day_logs
+/// Log a day from a note template, with `overrides` replacing its notes or
+/// flow. Anything else already logged on the day is kept.
+#[tauri::command]
+pub fn log_day_from_template(
+    date: String,
+    template_id: Uuid,
+    overrides: Option<TemplateOverrides>,
+    state: State<'_, AppState>,
+) -> Result<DayLog, CommandError> {
+    let overrides = overrides.unwrap_or_default();
+    let mut v = Validator::new();
+    let date = v.past_date("date", &date);
+    if let Some(notes) = &overrides.notes {
+        v.max_chars("overrides.notes", notes, validation::MAX_NOTE_CHARS);
+    }
+    v.finish()?;
+    let date = date.expect("validated above");
+
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let template = data
+        .settings
+        .note_templates
         .iter()
-        .filter(|l| l.flow_level != FlowLevel::None)
-        .map(|l| l.date)
-        .collect();
-    flow_days.sort();
-    flow_days.dedup();
+        .find(|t| t.id == template_id)
+        .cloned()
+        .ok_or(CommandError::NotFound {
+            entity: "note template",
+        })?;
+    let flow_level = overrides
+        .flow_level
+        .or(template.flow_level)
+        .or_else(|| data.day_logs.get(&date).map(|l| l.flow_level.clone()))
+        .unwrap_or(FlowLevel::None);
+    set_flow(data, vec![(date, date, flow_level)], Utc::now());
+    let log = data.day_logs.get_mut(&date).expect("logged by set_flow");
+    log.notes = overrides.notes.unwrap_or(template.notes);
+    let log = log.clone();
+    prediction::accuracy::record(data, chrono::Local::now().date_naive());
+
+    drop(data_lock);
+    state.save_data()?;
+    Ok(log)
+}
+
+/// Longest period a backfilled entry may span.
+const MAX_BACKFILL_PERIOD_DAYS: i64 = 15;
 
-    if flow_days.is_empty() {
-        data.cycles.clear();
-        return;
+/// Log several past periods at once, as `(start, end, flow_level)`, for
+/// new users entering their history. Flow is set on every day of each
+/// period, keeping anything else already logged on those days. Cycles are
+/// rebuilt and the data saved once.
+#[tauri::command]
+pub fn backfill_periods(
+    periods: Vec<(String, String, FlowLevel)>,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let mut v = Validator::new();
+    let mut parsed = Vec::with_capacity(periods.len());
+    for (i, (start, end, flow_level)) in periods.into_iter().enumerate() {
+        let start = v.past_date(&format!("periods[{i}].start"), &start);
+        let end = v.past_date(&format!("periods[{i}].end"), &end);
+        if flow_level == FlowLevel::None {
+            v.error(&format!("periods[{i}].flow_level"), "must not be None");
+        }
+        let (Some(start), Some(end)) = (start, end) else {
+            continue;
+        };
+        if end < start {
+            v.error(&format!("periods[{i}].end"), "must not be before the start");
+        } else if (end - start).num_days() >= MAX_BACKFILL_PERIOD_DAYS {
+            v.error(
+                &format!("periods[{i}].end"),
+                format!("must be within {MAX_BACKFILL_PERIOD_DAYS} days of the start"),
+            );
+        }
+        parsed.push((start, end, flow_level));
     }
+    v.finish()?;
 
-    let mut cycles: Vec<Cycle> = Vec::new();
-    let mut cycle_start = flow_days[0];
-    let mut cycle_end = flow_days[0];
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    set_flow(data, parsed, Utc::now());
+    prediction::accuracy::record(data, chrono::Local::now().date_naive());
 
-    for &day in &flow_days[1..] {
-        if (day - cycle_end).num_days() <= 2 {
-            cycle_end = day;
-        } else {
-            cycles.push(Cycle {
-                id: Uuid::new_v4(),
-                start_date: cycle_start,
-                end_date: Some(cycle_end),
+    drop(data_lock);
+    state.save_data()?;
+    Ok(())
+}
+
+/// Set flow on every day of each period, creating day logs as needed and
+/// keeping anything else logged, then rebuild cycles.
+fn set_flow(
+    data: &mut AppData,
+    periods: Vec<(NaiveDate, NaiveDate, FlowLevel)>,
+    now: DateTime<Utc>,
+) {
+    for (start, end, flow_level) in periods {
+        for date in start.iter_days().take_while(|&d| d <= end) {
+            let log = data.day_logs.entry(date).or_insert_with(|| DayLog {
+                created_at: Some(now),
+                ..DayLog::new(date)
             });
-            cycle_start = day;
-            cycle_end = day;
+            log.flow_level = flow_level.clone();
+            log.updated_at = Some(now);
         }
     }
+    prediction::rebuild_cycles(data);
+}
 
-    let today = chrono::Local::now().date_naive();
-    let last_end = if (today - cycle_end).num_days() <= 2 {
-        None
-    } else {
-        Some(cycle_end)
-    };
+/// Replace the pain entries for a date.
+#[tauri::command]
+pub fn log_pain(
+    date: String,
+    entries: Vec<(PainLocation, u8, PainCharacter)>,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let mut v = Validator::new();
+    let date = v.past_date("date", &date);
+    for (i, (_, intensity, _)) in entries.iter().enumerate() {
+        v.in_range(&format!("entries[{i}].intensity"), *intensity, 1, 3);
+    }
+    v.finish()?;
+    let date = date.expect("validated above");
+
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
 
-    cycles.push(Cycle {
+    data.pain.retain(|p| p.date != date);
+    for (location, intensity, character) in entries {
+        data.pain.push(PainEntry {
+            date,
+            location,
+            intensity,
+            character,
+        });
+    }
+
+    drop(data_lock);
+    state.save_data()?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_symptom_presets(
+    state: State<'_, AppState>,
+) -> Result<Vec<SymptomPreset>, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(data.symptom_presets.clone())
+}
+
+#[tauri::command]
+pub fn add_symptom_preset(
+    name: String,
+    symptoms: Vec<(String, u8)>,
+    state: State<'_, AppState>,
+) -> Result<SymptomPreset, CommandError> {
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let symptoms = check_preset(&data.symptom_registry, &name, symptoms)?;
+    let preset = SymptomPreset {
         id: Uuid::new_v4(),
-        start_date: cycle_start,
-        end_date: last_end,
-    });
+        name,
+        symptoms,
+    };
+    data.symptom_presets.push(preset.clone());
+    drop(data_lock);
+    state.save_data()?;
+    Ok(preset)
+}
 
-    data.cycles = cycles;
+#[tauri::command]
+pub fn update_symptom_preset(
+    id: Uuid,
+    name: String,
+    symptoms: Vec<(String, u8)>,
+    state: State<'_, AppState>,
+) -> Result<SymptomPreset, CommandError> {
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let symptoms = check_preset(&data.symptom_registry, &name, symptoms)?;
+    let preset =
+        data.symptom_presets
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or(CommandError::NotFound {
+                entity: "symptom preset",
+            })?;
+    preset.name = name;
+    preset.symptoms = symptoms;
+    let preset = preset.clone();
+    drop(data_lock);
+    state.save_data()?;
+    Ok(preset)
 }
 
 #[tauri::command]
-pub fn get_month(year: i32, month: u32, state: State<'_, AppState>) -> Result<MonthData, String> {
-    let data_lock = state.data.lock().map_err(|e| e.to_string())?;
-    let data = data_lock.as_ref().ok_or("app is locked")?;
+pub fn delete_symptom_preset(id: Uuid, state: State<'_, AppState>) -> Result<(), CommandError> {
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let before = data.symptom_presets.len();
+    data.symptom_presets.retain(|p| p.id != id);
+    if data.symptom_presets.len() == before {
+        return Err(CommandError::NotFound {
+            entity: "symptom preset",
+        });
+    }
+    drop(data_lock);
+    state.save_data()?;
+    Ok(())
+}
 
-    let first_day = NaiveDate::from_ymd_opt(year, month, 1).ok_or("invalid date")?;
-    let last_day = if month == 12 {
-        NaiveDate::from_ymd_opt(year + 1, 1, 1)
-    } else {
-        NaiveDate::from_ymd_opt(year, month + 1, 1)
+fn check_preset(
+    registry: &[SymptomDescriptor],
+    name: &str,
+    symptoms: Vec<(String, u8)>,
+) -> Result<Vec<(String, u8)>, CommandError> {
+    let mut v = Validator::new();
+    v.required("name", name);
+    v.max_chars("name", name, validation::MAX_LABEL_CHARS);
+    if symptoms.is_empty() {
+        v.error("symptoms", "must not be empty");
     }
-    .ok_or("invalid date")?
-        - chrono::Duration::days(1);
+    let resolved = resolve_symptoms(&mut v, registry, symptoms);
+    v.finish()?;
+    Ok(resolved)
+}
 
-    let day_logs: Vec<DayLog> = data
-        .day_logs
+/// Log every symptom in a preset on `date`. Symptoms the preset includes
+/// take its severity; others already logged that day are kept.
+#[tauri::command]
+pub fn apply_symptom_preset(
+    date: String,
+    preset_id: Uuid,
+    state: State<'_, AppState>,
+) -> Result<Vec<Symptom>, CommandError> {
+    let mut v = Validator::new();
+    let date = v.past_date("date", &date);
+    v.finish()?;
+    let date = date.expect("validated above");
+
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let preset = data
+        .symptom_presets
         .iter()
-        .filter(|l| l.date >= first_day && l.date <= last_day)
-        .cloned()
-        .collect();
+        .find(|p| p.id == preset_id)
+        .ok_or(CommandError::NotFound {
+            entity: "symptom preset",
+        })?;
+    let previous = data.symptoms.get(&date).map_or(&[][..], Vec::as_slice);
+    let symptoms = stamp_symptoms(
+        previous,
+        with_preset(previous, &preset.symptoms),
+        date,
+        Utc::now(),
+    );
+    data.symptoms.insert(date, symptoms.clone());
 
-    let symptoms: Vec<Symptom> = data
-        .symptoms
+    drop(data_lock);
+    state.save_data()?;
+    Ok(symptoms)
+}
+
+/// The day's `(symptom_type, severity)` pairs with `preset` laid over them.
+fn with_preset(previous: &[Symptom], preset: &[(String, u8)]) -> Vec<(String, u8)> {
+    let mut merged: Vec<(String, u8)> = previous
         .iter()
-        .filter(|s| s.date >= first_day && s.date <= last_day)
-        .cloned()
+        .map(|s| (s.symptom_type.clone(), s.severity))
         .collect();
+    for (symptom_type, severity) in preset {
+        match merged.iter_mut().find(|(t, _)| t == symptom_type) {
+            Some(existing) => existing.1 = *severity,
+            None => merged.push((symptom_type.clone(), *severity)),
+        }
+    }
+    merged
+}
 
-    let predictions: Vec<Prediction> = prediction::predict(&data.cycles).into_iter().collect();
+#[tauri::command]
+pub fn list_appointments(state: State<'_, AppState>) -> Result<Vec<Appointment>, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let mut appointments = data.appointments.clone();
+    appointments.sort_by_key(|a| a.date);
+    Ok(appointments)
+}
 
-    let fertility = if data.settings.show_fertility {
-        prediction::fertility_window(&data.cycles)
-    } else {
-        None
+#[tauri::command]
+pub fn add_appointment(
+    date: String,
+    appointment_type: AppointmentType,
+    notes: String,
+    follow_up_date: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Appointment, CommandError> {
+    let (date, follow_up_date) = check_appointment(&date, &notes, follow_up_date.as_deref())?;
+    let appointment = Appointment {
+        id: Uuid::new_v4(),
+        date,
+        appointment_type,
+        notes,
+        follow_up_date,
     };
 
-    let current_cycle = data.cycles.iter().find(|c| c.end_date.is_none()).cloned();
-    let stats = prediction::cycle_stats(&data.cycles);
-
-    Ok(MonthData {
-        year,
-        month,
-        day_logs,
-        symptoms,
-        predictions,
-        fertility,
-        current_cycle,
-        stats,
-    })
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    data.appointments.push(appointment.clone());
+    drop(data_lock);
+    state.save_data()?;
+    Ok(appointment)
 }
 
 #[tauri::command]
-pub fn get_predictions(state: State<'_, AppState>) -> Result<Vec<Prediction>, String> {
-    let data_lock = state.data.lock().map_err(|e| e.to_string())?;
-    let data = data_lock.as_ref().ok_or("app is locked")?;
-    Ok(prediction::predict(&data.cycles).into_iter().collect())
-}
+pub fn update_appointment(
+    id: Uuid,
+    date: String,
+    appointment_type: AppointmentType,
+    notes: String,
+    follow_up_date: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Appointment, CommandError> {
+    let (date, follow_up_date) = check_appointment(&date, &notes, follow_up_date.as_deref())?;
 
-#[tauri::command]
-pub fn get_stats(state: State<'_, AppState>) -> Result<CycleStats, String> {
-    let data_lock = state.data.lock().map_err(|e| e.to_string())?;
-    let data = data_lock.as_ref().ok_or("app is locked")?;
-    Ok(prediction::cycle_stats(&data.cycles))
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let appointment =
+        data.appointments
+            .iter_mut()
+            .find(|a| a.id == id)
+            .ok_or(CommandError::NotFound {
+                entity: "appointment",
+            })?;
+    appointment.date = date;
+    appointment.appointment_type = appointment_type;
+    appointment.notes = notes;
+    appointment.follow_up_date = follow_up_date;
+    let appointment = appointment.clone();
+    drop(data_lock);
+    state.save_data()?;
+    Ok(appointment)
 }
 
 #[tauri::command]
-pub fn toggle_fertility(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
-    let mut data_lock = state.data.lock().map_err(|e| e.to_string())?;
-    let data = data_lock.as_mut().ok_or("app is locked")?;
-    data.settings.show_fertility = enabled;
+pub fn delete_appointment(id: Uuid, state: State<'_, AppState>) -> Result<(), CommandError> {
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let before = data.appointments.len();
+    data.appointments.retain(|a| a.id != id);
+    if data.appointments.len() == before {
+        return Err(CommandError::NotFound {
+            entity: "appointment",
+        });
+    }
     drop(data_lock);
     state.save_data()?;
     Ok(())
 }
 
+fn check_appointment(
+    date: &str,
+    notes: &str,
+    follow_up_date: Option<&str>,
+) -> Result<(NaiveDate, Option<NaiveDate>), CommandError> {
+    let mut v = Validator::new();
+    let date = v.planned_date("date", date);
+    let follow_up = follow_up_date.map(|d| v.planned_date("follow_up_date", d));
+    v.max_chars("notes", notes, validation::MAX_NOTE_CHARS);
+    if let (Some(date), Some(Some(follow_up))) = (date, follow_up) {
+        if follow_up < date {
+            v.error("follow_up_date", "must not be before the appointment");
+        }
+    }
+    v.finish()?;
+    Ok((date.expect("validated above"), follow_up.flatten()))
+}
+
+/// Record whether the day's pill was taken, or clear the record with
+/// `None`.
 #[tauri::command]
-pub fn update_settings(auto_lock_minutes: u32, state: State<'_, AppState>) -> Result<(), String> {
-    let mut data_lock = state.data.lock().map_err(|e| e.to_string())?;
-    let data = data_lock.as_mut().ok_or("app is locked")?;
-    data.settings.auto_lock_minutes = auto_lock_minutes.clamp(1, 60);
+pub fn log_pill_dose(
+    date: String,
+    taken: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let mut v = Validator::new();
+    let date = v.past_date("date", &date);
+    v.finish()?;
+    let date = date.expect("validated above");
+
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+
+    data.pill_doses.retain(|d| d.date != date);
+    if let Some(taken) = taken {
+        data.pill_doses.push(PillDose { date, taken });
+    }
+
     drop(data_lock);
     state.save_data()?;
     Ok(())
 }
 
+/// List lab results by date, optionally only those for one analyte.
+#[tauri::command]
+pub fn list_lab_results(
+    analyte: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<LabResult>, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let mut results: Vec<LabResult> = data
+        .lab_results
+        .iter()
+        .filter(|r| {
+            analyte
+                .as_deref()
+                .is_none_or(|a| r.analyte.eq_ignore_ascii_case(a))
+        })
+        .cloned()
+        .collect();
+    results.sort_by_key(|r| r.date);
+    Ok(results)
+}
+
 #[tauri::command]
-pub fn export_data(state: State<'_, AppState>) -> Result<String, String> {
-    let data_lock = state.data.lock().map_err(|e| e.to_string())?;
-    let data = data_lock.as_ref().ok_or("app is locked")?;
-    serde_json::to_string_pretty(data).map_err(|e| e.to_string())
+pub fn add_lab_result(
+    date: String,
+    analyte: String,
+    value: f64,
+    unit: String,
+    reference_low: Option<f64>,
+    reference_high: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<LabResult, CommandError> {
+    let result = validated_lab_result(
+        Uuid::new_v4(),
+        &date,
+        analyte,
+        value,
+        unit,
+        reference_low,
+        reference_high,
+    )?;
+
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    data.lab_results.push(result.clone());
+    drop(data_lock);
+    state.save_data()?;
+    Ok(result)
 }
 
 #[tauri::command]
-pub fn wipe_all_data(state: State<'_, AppState>) -> Result<(), String> {
-    state.lock();
-    storage::wipe().map_err(|e| e.to_string())
+#[allow(clippy::too_many_arguments)]
+pub fn update_lab_result(
+    id: Uuid,
+    date: String,
+    analyte: String,
+    value: f64,
+    unit: String,
+    reference_low: Option<f64>,
+    reference_high: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<LabResult, CommandError> {
+    let updated = validated_lab_result(
+        id,
+        &date,
+        analyte,
+        value,
+        unit,
+        reference_low,
+        reference_high,
+    )?;
+
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let result =
+        data.lab_results
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or(CommandError::NotFound {
+                entity: "lab result",
+            })?;
+    *result = updated.clone();
+    drop(data_lock);
+    state.save_data()?;
+    Ok(updated)
 }
 
 #[tauri::command]
-pub fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
-    let data_lock = state.data.lock().map_err(|e| e.to_string())?;
-    let data = data_lock.as_ref().ok_or("app is locked")?;
-    Ok(data.settings.clone())
+pub fn delete_lab_result(id: Uuid, state: State<'_, AppState>) -> Result<(), CommandError> {
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let before = data.lab_results.len();
+    data.lab_results.retain(|r| r.id != id);
+    if data.lab_results.len() == before {
+        return Err(CommandError::NotFound {
+            entity: "lab result",
+        });
+    }
+    drop(data_lock);
+    state.save_data()?;
+    Ok(())
+}
+
+/// Validate lab result input and build the record from it.
+fn validated_lab_result(
+    id: Uuid,
+    date: &str,
+    analyte: String,
+    value: f64,
+    unit: String,
+    reference_low: Option<f64>,
+    reference_high: Option<f64>,
+) -> Result<LabResult, CommandError> {
+    let analyte = analyte.trim().to_string();
+    let unit = unit.trim().to_string();
+
+    let mut v = Validator::new();
+    let date = v.past_date("date", date);
+    v.required("analyte", &analyte);
+    v.max_chars("analyte", &analyte, validation::MAX_LABEL_CHARS);
+    v.max_chars("unit", &unit, validation::MAX_LABEL_CHARS);
+    v.finite("value", value);
+    if let Some(low) = reference_low {
+        v.finite("reference_low", low);
+    }
+    if let Some(high) = reference_high {
+        v.finite("reference_high", high);
+    }
+    if let (Some(low), Some(high)) = (reference_low, reference_high) {
+        if low > high {
+            v.error("reference_low", "must not be above reference_high");
+        }
+    }
+    v.finish()?;
+
+    Ok(LabResult {
+        id,
+        date: date.expect("validated above"),
+        analyte,
+        value,
+        unit,
+        reference_low,
+        reference_high,
+    })
+}
+
+/// Registry keys for `(key, severity)` pairs, reporting unknown symptoms
+/// and out-of-range severities as `symptoms[i]` fields.
+fn resolve_symptoms(
+    v: &mut Validator,
+    registry: &[SymptomDescriptor],
+    symptoms: Vec<(String, u8)>,
+) -> Vec<(String, u8)> {
+    let mut resolved = Vec::with_capacity(symptoms.len());
+    for (i, (key, severity)) in symptoms.into_iter().enumerate() {
+        let Some(descriptor) = symptoms::resolve(registry, &key) else {
+            v.error(&format!("symptoms[{i}].symptom_type"), "unknown symptom");
+            continue;
+        };
+        v.in_range(
+            &format!("symptoms[{i}].severity"),
+            severity,
+            1,
+            descriptor.max_severity,
+        );
+        resolved.push((descriptor.key.clone(), severity));
+    }
+    resolved
+}
+
+/// Builds a day's new symptom list, carrying timestamps over from the
+/// previous entry for the same symptom so unchanged ones keep theirs.
+fn stamp_symptoms(
+    previous: &[Symptom],
+    resolved: Vec<(String, u8)>,
+    date: NaiveDate,
+    now: DateTime<Utc>,
+) -> Vec<Symptom> {
+    resolved
+        .into_iter()
+        .map(|(symptom_type, severity)| {
+            let old = previous.iter().find(|s| s.symptom_type == symptom_type);
+            let (created_at, updated_at) = match old {
+                Some(old) if old.severity == severity => (old.created_at, old.updated_at),
+                Some(old) => (old.created_at, Some(now)),
+                None => (Some(now), Some(now)),
+            };
+            Symptom {
+                date,
+                symptom_type,
+                severity,
+                created_at,
+                updated_at,
+            }
+        })
+        .collect()
+}
+
+fn round_temperature(value: f32) -> f32 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Convert stored Celsius temperatures into the user's unit for display.
+fn localize_temperatures<'a>(
+    day_logs: impl IntoIterator<Item = &'a mut DayLog>,
+    unit: TemperatureUnit,
+) {
+    for log in day_logs {
+        log.bbt = log.bbt.map(|t| round_temperature(unit.convert_celsius(t)));
+    }
+}
+
+/// Re-detect cycles and ovulation days and refresh predictions, e.g. after
+/// an import or an update that changed detection. Reports what moved.
+#[tauri::command]
+pub fn recalculate(state: State<'_, AppState>) -> Result<Recalculation, CommandError> {
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let previous_prediction = state.derived(data)?.predictions.into_iter().next();
+    let before = data.cycles.clone();
+    prediction::rebuild_cycles(data);
+    prediction::accuracy::record(data, chrono::Local::now().date_naive());
+    let (added, removed, changed) = cycle_changes(&before, &data.cycles);
+    drop(data_lock);
+    state.save_data()?;
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let prediction = state.derived(data)?.predictions.into_iter().next();
+    Ok(Recalculation {
+        added,
+        removed,
+        changed,
+        previous_prediction,
+        prediction,
+    })
+}
+
+/// Cycles only in `after`, only in `before`, and in both (by id) but
+/// different, in that order.
+fn cycle_changes(before: &[Cycle], after: &[Cycle]) -> (Vec<Cycle>, Vec<Cycle>, Vec<Cycle>) {
+    let find = |cycles: &[Cycle], id| cycles.iter().find(|c: &&Cycle| c.id == id).cloned();
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    for cycle in after {
+        match find(before, cycle.id) {
+            None => added.push(cycle.clone()),
+            Some(old) if old != *cycle => changed.push(cycle.clone()),
+            Some(_) => {}
+        }
+    }
+    let removed = before
+        .iter()
+        .filter(|c| find(after, c.id).is_none())
+        .cloned()
+        .collect();
+    (added, removed, changed)
+}
+
+/// Accept a detected cycle's boundaries so later flow edits don't move them.
+#[tauri::command]
+pub fn confirm_cycle(id: Uuid, state: State<'_, AppState>) -> Result<Cycle, CommandError> {
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let cycle = data
+        .cycles
+        .iter_mut()
+        .find(|c| c.id == id)
+        .ok_or(CommandError::NotFound { entity: "cycle" })?;
+    if cycle.end_date.is_none() {
+        return Err(CommandError::Conflict {
+            message: "the current period hasn't ended yet".to_string(),
+        });
+    }
+    if cycle.source == CycleSource::Detected {
+        cycle.source = CycleSource::Confirmed;
+        stamp_cycle(cycle, Utc::now());
+    }
+    let cycle = cycle.clone();
+    drop(data_lock);
+    state.save_data()?;
+    Ok(cycle)
+}
+
+/// Record a past period that wasn't logged day by day.
+#[tauri::command]
+pub fn add_cycle(
+    start_date: String,
+    end_date: String,
+    state: State<'_, AppState>,
+) -> Result<Cycle, CommandError> {
+    let mut v = Validator::new();
+    let start_date = v.past_date("start_date", &start_date);
+    let end_date = v.past_date("end_date", &end_date);
+    if let (Some(start), Some(end)) = (start_date, end_date) {
+        if end < start {
+            v.error("end_date", "must not be before the start date");
+        }
+    }
+    v.finish()?;
+
+    let now = Utc::now();
+    let cycle = Cycle {
+        id: Uuid::new_v4(),
+        start_date: start_date.expect("validated above"),
+        end_date,
+        source: CycleSource::Manual,
+        include_in_stats: None,
+        ovulation: None,
+        created_at: Some(now),
+        updated_at: Some(now),
+    };
+
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let clashes = data
+        .cycles
+        .iter()
+        .any(|c| c.source != CycleSource::Detected && c.overlaps(&cycle));
+    if clashes {
+        return Err(CommandError::Conflict {
+            message: "overlaps a cycle that was already confirmed or entered".to_string(),
+        });
+    }
+    data.cycles.push(cycle.clone());
+    prediction::rebuild_cycles(data);
+    prediction::accuracy::record(data, chrono::Local::now().date_naive());
+
+    drop(data_lock);
+    state.save_data()?;
+    Ok(cycle)
+}
+
+/// Force a cycle into or out of the averages, or with `None` go back to
+/// automatic outlier detection.
+#[tauri::command]
+pub fn set_cycle_in_stats(
+    id: Uuid,
+    include: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let cycle = data
+        .cycles
+        .iter_mut()
+        .find(|c| c.id == id)
+        .ok_or(CommandError::NotFound { entity: "cycle" })?;
+    cycle.include_in_stats = include;
+    stamp_cycle(cycle, Utc::now());
+    drop(data_lock);
+    state.save_data()?;
+    Ok(())
+}
+
+/// Mark `cycle` as changed by the user at `now`.
+fn stamp_cycle(cycle: &mut Cycle, now: DateTime<Utc>) {
+    cycle.created_at = cycle.created_at.or(Some(now));
+    cycle.updated_at = Some(now);
+}
+
+#[tauri::command]
+pub fn get_month(
+    year: i32,
+    month: u32,
+    state: State<'_, AppState>,
+) -> Result<MonthData, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let (first_day, last_day) = month_bounds(year, month)?;
+
+    let mut day_logs: Vec<DayLog> = data
+        .day_logs
+        .range(first_day..=last_day)
+        .map(|(_, l)| l.clone())
+        .collect();
+    localize_temperatures(&mut day_logs, data.settings.temperature_unit);
+
+    let symptoms: Vec<Symptom> = data
+        .symptoms
+        .range(first_day..=last_day)
+        .flat_map(|(_, s)| s.iter().cloned())
+        .collect();
+
+    let pain: Vec<PainEntry> = data
+        .pain
+        .iter()
+        .filter(|p| p.date >= first_day && p.date <= last_day)
+        .cloned()
+        .collect();
+
+    let derived = state.derived(data)?;
+    let current_cycle = data.cycles.iter().find(|c| c.end_date.is_none()).cloned();
+
+    Ok(MonthData {
+        year,
+        month,
+        day_logs,
+        symptoms,
+        pain,
+        predictions: derived.predictions,
+        fertility: derived.fertility,
+        current_cycle,
+        stats: derived.stats,
+    })
+}
+
+/// Days whose notes mention `query` and that pass `filters`, newest first.
+#[tauri::command]
+pub fn search(
+    query: String,
+    filters: SearchFilters,
+    state: State<'_, AppState>,
+) -> Result<Vec<SearchHit>, CommandError> {
+    let mut v = Validator::new();
+    v.max_chars("query", &query, validation::MAX_LABEL_CHARS);
+    if let Some(severity) = filters.min_severity {
+        v.in_range("filters.min_severity", severity, 1, 10);
+    }
+    if let (Some(from), Some(to)) = (filters.from, filters.to) {
+        if from > to {
+            v.error("filters.to", "must not be before from");
+        }
+    }
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let mut filters = filters;
+    if let Some(key) = &filters.symptom_type {
+        match symptoms::resolve(&data.symptom_registry, key) {
+            Some(descriptor) => filters.symptom_type = Some(descriptor.key.clone()),
+            None => v.error("filters.symptom_type", "unknown symptom"),
+        }
+    }
+    v.finish()?;
+    Ok(search::search(data, &query, &filters))
+}
+
+/// Dates between `from` and `to` (inclusive) with anything logged, oldest
+/// first, for year-overview dots and jumping to the last entry.
+#[tauri::command]
+pub fn get_logged_dates(
+    from: String,
+    to: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<NaiveDate>, CommandError> {
+    let mut v = Validator::new();
+    let from = v.planned_date("from", &from);
+    let to = v.planned_date("to", &to);
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            v.error("to", "must not be before from");
+        }
+    }
+    v.finish()?;
+    let (from, to) = (from.expect("validated above"), to.expect("validated above"));
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(logged_dates(data, from, to))
+}
+
+fn logged_dates(data: &AppData, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+    // A log cleared back to nothing doesn't count
+    let mut dates: Vec<NaiveDate> = data
+        .day_logs
+        .range(from..=to)
+        .filter(|(_, log)| {
+            log.flow_level != FlowLevel::None
+                || !log.notes.trim().is_empty()
+                || log.cervix.is_some()
+                || log.libido.is_some()
+                || log.bbt.is_some()
+                || log.lh_test.is_some()
+        })
+        .map(|(&d, _)| d)
+        .chain(data.symptoms.range(from..=to).map(|(&d, _)| d))
+        .chain(
+            data.pain
+                .iter()
+                .map(|p| p.date)
+                .filter(|d| (from..=to).contains(d)),
+        )
+        .collect();
+    dates.sort();
+    dates.dedup();
+    dates
+}
+
+/// Logged days per page unless asked, and the most a page may hold.
+const DEFAULT_PAGE_DAYS: usize = 100;
+const MAX_PAGE_DAYS: usize = 500;
+
+/// Days with a log or symptoms between `from` and `to` (inclusive), a page
+/// at a time. Days with nothing logged are skipped and don't count towards
+/// `limit`.
+#[tauri::command]
+pub fn get_range(
+    from: String,
+    to: String,
+    cursor: Option<String>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<RangePage, CommandError> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_DAYS);
+    let mut v = Validator::new();
+    let from = v.planned_date("from", &from);
+    let to = v.planned_date("to", &to);
+    let cursor = cursor.and_then(|c| v.planned_date("cursor", &c));
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            v.error("to", "must not be before from");
+        }
+    }
+    v.in_range("limit", limit, 1, MAX_PAGE_DAYS);
+    v.finish()?;
+    let (from, to) = (from.expect("validated above"), to.expect("validated above"));
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let mut page = range_page(data, cursor.map_or(from, |c| c.max(from)), to, limit);
+    localize_temperatures(
+        page.days.iter_mut().filter_map(|d| d.day_log.as_mut()),
+        data.settings.temperature_unit,
+    );
+    Ok(page)
+}
+
+/// Up to `limit` logged days from `from` to `to`, with the date to carry on
+/// from when more remain.
+fn range_page(data: &AppData, from: NaiveDate, to: NaiveDate, limit: usize) -> RangePage {
+    let mut dates: Vec<NaiveDate> = data
+        .day_logs
+        .range(from..=to)
+        .map(|(&d, _)| d)
+        .chain(data.symptoms.range(from..=to).map(|(&d, _)| d))
+        .collect();
+    dates.sort();
+    dates.dedup();
+
+    let next_cursor = dates.get(limit).copied();
+    dates.truncate(limit);
+    RangePage {
+        days: dates
+            .into_iter()
+            .map(|date| LoggedDay {
+                date,
+                day_log: data.day_logs.get(&date).cloned(),
+                symptoms: data.symptoms.get(&date).cloned().unwrap_or_default(),
+            })
+            .collect(),
+        next_cursor,
+    }
+}
+
+/// Every kind of record between `from` and `to` (inclusive) interleaved by
+/// date, `limit` dates at a time.
+#[tauri::command]
+pub fn get_timeline(
+    from: String,
+    to: String,
+    cursor: Option<String>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<TimelinePage, CommandError> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_DAYS);
+    let mut v = Validator::new();
+    let from = v.planned_date("from", &from);
+    let to = v.planned_date("to", &to);
+    let cursor = cursor.and_then(|c| v.planned_date("cursor", &c));
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            v.error("to", "must not be before from");
+        }
+    }
+    v.in_range("limit", limit, 1, MAX_PAGE_DAYS);
+    v.finish()?;
+    let (from, to) = (from.expect("validated above"), to.expect("validated above"));
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let mut page = timeline::timeline(data, cursor.map_or(from, |c| c.max(from)), to, limit);
+    localize_temperatures(
+        page.entries.iter_mut().filter_map(|e| match e {
+            TimelineEntry::DayLog(l) => Some(l),
+            _ => None,
+        }),
+        data.settings.temperature_unit,
+    );
+    Ok(page)
+}
+
+#[tauri::command]
+pub fn get_day(date: String, state: State<'_, AppState>) -> Result<DayData, CommandError> {
+    let mut v = Validator::new();
+    let date = v.planned_date("date", &date);
+    v.finish()?;
+    let date = date.expect("validated above");
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+
+    let mut day_log = data.day_logs.get(&date).cloned();
+    localize_temperatures(day_log.as_mut(), data.settings.temperature_unit);
+    let cycle_day = data
+        .cycles
+        .iter()
+        .map(|c| c.start_date)
+        .filter(|&start| start <= date)
+        .max()
+        .map(|start| (date - start).num_days() as u32 + 1);
+
+    Ok(DayData {
+        date,
+        day_log,
+        symptoms: data.symptoms.get(&date).cloned().unwrap_or_default(),
+        pain: data
+            .pain
+            .iter()
+            .filter(|p| p.date == date)
+            .cloned()
+            .collect(),
+        pill_taken: data
+            .pill_doses
+            .iter()
+            .find(|d| d.date == date)
+            .map(|d| d.taken),
+        lab_results: data
+            .lab_results
+            .iter()
+            .filter(|r| r.date == date)
+            .cloned()
+            .collect(),
+        appointments: data
+            .appointments
+            .iter()
+            .filter(|a| a.date == date)
+            .cloned()
+            .collect(),
+        cycle_day,
+        phase: prediction::phase_info(date, data),
+    })
+}
+
+const DEFAULT_SIMULATION_RUNS: u32 = 5_000;
+const MAX_SIMULATION_RUNS: u32 = 50_000;
+
+/// Spread of the next period's start over `runs` simulated cycles.
+#[tauri::command]
+pub fn get_period_simulation(
+    runs: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<Option<StartDistribution>, CommandError> {
+    let runs = runs.unwrap_or(DEFAULT_SIMULATION_RUNS);
+    let mut v = Validator::new();
+    v.in_range("runs", runs, 100, MAX_SIMULATION_RUNS);
+    v.finish()?;
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(prediction::simulation::next_start(
+        data,
+        runs,
+        &mut rand::thread_rng(),
+    ))
+}
+
+/// Per-day chance of bleeding across a month, for a gradient calendar.
+/// Days with logged flow are certain; months beyond the prediction horizon
+/// come back as all zeros.
+#[tauri::command]
+pub fn get_period_probabilities(
+    year: i32,
+    month: u32,
+    state: State<'_, AppState>,
+) -> Result<Vec<DayProbability>, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let (first_day, last_day) = month_bounds(year, month)?;
+
+    let predictions = prediction::upcoming(data, MAX_PREDICTED_CYCLES);
+    Ok(prediction::period_probabilities(
+        &predictions,
+        &data.day_logs,
+        first_day,
+        last_day,
+    ))
+}
+
+fn month_bounds(year: i32, month: u32) -> Result<(NaiveDate, NaiveDate), CommandError> {
+    let first_day = NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| CommandError::invalid("month", "invalid year or month"))?;
+    let last_day = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    }
+    .ok_or_else(|| CommandError::invalid("month", "invalid year or month"))?
+        - chrono::Duration::days(1);
+    Ok((first_day, last_day))
+}
+
+/// How many upcoming periods `get_predictions` projects unless asked.
+const DEFAULT_PREDICTED_CYCLES: usize = 3;
+/// Beyond this the compounded uncertainty makes projections meaningless.
+const MAX_PREDICTED_CYCLES: usize = 6;
+
+#[tauri::command]
+pub fn get_predictions(
+    count: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Prediction>, CommandError> {
+    let count = count.unwrap_or(DEFAULT_PREDICTED_CYCLES);
+    let mut v = Validator::new();
+    v.in_range("count", count, 1, MAX_PREDICTED_CYCLES);
+    v.finish()?;
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(prediction::upcoming(data, count))
+}
+
+#[tauri::command]
+pub fn get_prediction_accuracy(
+    state: State<'_, AppState>,
+) -> Result<PredictionAccuracy, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(prediction::accuracy::report(&data.prediction_history))
+}
+
+#[tauri::command]
+pub fn get_insights(state: State<'_, AppState>) -> Result<Vec<Insight>, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let today = chrono::Local::now().date_naive();
+    Ok(insights::insights(data, today))
+}
+
+#[tauri::command]
+pub fn get_health_flags(state: State<'_, AppState>) -> Result<Vec<HealthFlag>, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    if !data.settings.show_health_flags {
+        return Ok(Vec::new());
+    }
+    let today = chrono::Local::now().date_naive();
+    Ok(insights::health_flags(data, today))
+}
+
+/// Cycles overlaid unless asked.
+const DEFAULT_OVERLAY_CYCLES: usize = 3;
+
+#[tauri::command]
+pub fn get_cycle_overlay(
+    cycles: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<OverlayCycle>, CommandError> {
+    let cycles = cycles.unwrap_or(DEFAULT_OVERLAY_CYCLES);
+    let mut v = Validator::new();
+    v.in_range("cycles", cycles, 1, 12);
+    v.finish()?;
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let today = chrono::Local::now().date_naive();
+    Ok(analytics::cycle_overlay(data, today, cycles))
+}
+
+#[tauri::command]
+pub fn get_bbt_chart(
+    date: String,
+    state: State<'_, AppState>,
+) -> Result<Option<BbtChart>, CommandError> {
+    let mut v = Validator::new();
+    let date = v.planned_date("date", &date);
+    v.finish()?;
+    let date = date.expect("validated above");
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(analytics::bbt_chart(data, date))
+}
+
+#[tauri::command]
+pub fn get_same_cycle_day(
+    date: String,
+    state: State<'_, AppState>,
+) -> Result<Option<SameCycleDay>, CommandError> {
+    let mut v = Validator::new();
+    let date = v.planned_date("date", &date);
+    v.finish()?;
+    let date = date.expect("validated above");
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let mut lookup = analytics::same_cycle_day(data, date);
+    if let Some(lookup) = &mut lookup {
+        localize_temperatures(
+            lookup
+                .previous
+                .iter_mut()
+                .filter_map(|p| p.day_log.as_mut()),
+            data.settings.temperature_unit,
+        );
+    }
+    Ok(lookup)
+}
+
+#[tauri::command]
+pub fn compare_current_cycle(
+    include_excluded: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Option<CycleComparison>, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let today = chrono::Local::now().date_naive();
+    Ok(analytics::compare_current_cycle(
+        &scoped(data, include_excluded),
+        today,
+    ))
+}
+
+/// Just enough for the tray tooltip and dashboard header, without the
+/// month payload.
+#[tauri::command]
+pub fn get_cycle_snapshot(state: State<'_, AppState>) -> Result<CycleSnapshot, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let derived = state.derived(data)?;
+    Ok(prediction::snapshot(
+        data,
+        derived.today,
+        derived.predictions.first(),
+    ))
+}
+
+#[tauri::command]
+pub fn get_stats(
+    include_excluded: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<CycleStats, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    if include_excluded == Some(true) {
+        return Ok(stats_for(&prediction::including_excluded(data)));
+    }
+    Ok(state.derived(data)?.stats)
+}
+
+/// The data analytics run on: as is, or with excluded cycles forced back
+/// in when the caller asks for everything.
+fn scoped(data: &AppData, include_excluded: Option<bool>) -> Cow<'_, AppData> {
+    if include_excluded == Some(true) {
+        Cow::Owned(prediction::including_excluded(data))
+    } else {
+        Cow::Borrowed(data)
+    }
+}
+
+/// Cycle stats with rolling averages, plus the perimenopause section when
+/// that mode is on.
+fn stats_for(data: &AppData) -> CycleStats {
+    let mut stats = prediction::cycle_stats(&data.cycles, &data.settings);
+    let today = chrono::Local::now().date_naive();
+    stats.rolling = analytics::rolling_trends(data, today);
+    stats.logging = Some(analytics::logging_stats(data, today));
+    stats.adherence = analytics::pill_adherence(data, today);
+    if data.settings.perimenopause_mode {
+        stats.perimenopause = Some(prediction::perimenopause_stats(
+            &data.cycles,
+            &data.symptoms,
+            today,
+        ));
+    }
+    stats
+}
+
+/// Deprecated: use [`update_settings`].
+#[tauri::command]
+pub fn toggle_fertility(enabled: bool, state: State<'_, AppState>) -> Result<(), CommandError> {
+    change_settings(&state, |s| s.show_fertility = enabled)
+}
+
+#[tauri::command]
+pub fn get_conception_likelihood(
+    date: String,
+    state: State<'_, AppState>,
+) -> Result<Option<ConceptionEstimate>, CommandError> {
+    let mut v = Validator::new();
+    let date = v.planned_date("date", &date);
+    v.finish()?;
+    let date = date.expect("validated above");
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    if !data.settings.trying_to_conceive {
+        return Ok(None);
+    }
+    Ok(prediction::conception_likelihood(date, data))
+}
+
+/// Deprecated: use [`update_settings`].
+#[tauri::command]
+pub fn toggle_trying_to_conceive(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    change_settings(&state, |s| s.trying_to_conceive = enabled)
+}
+
+/// Longest symptom forecast; further out the predicted cycle days drift too
+/// far to say anything useful.
+const MAX_FORECAST_DAYS: u32 = 60;
+
+/// Likely symptoms for each of the next `days_ahead` days.
+#[tauri::command]
+pub fn get_symptom_forecast(
+    days_ahead: u32,
+    state: State<'_, AppState>,
+) -> Result<Vec<SymptomForecast>, CommandError> {
+    let mut v = Validator::new();
+    v.in_range("days_ahead", days_ahead, 1, MAX_FORECAST_DAYS);
+    v.finish()?;
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let today = chrono::Local::now().date_naive();
+    Ok(prediction::symptom_forecast(data, today, days_ahead))
+}
+
+/// Cycle phase of any day, for coloring and explaining the calendar.
+#[tauri::command]
+pub fn get_cycle_phase(
+    date: String,
+    state: State<'_, AppState>,
+) -> Result<Option<PhaseInfo>, CommandError> {
+    let mut v = Validator::new();
+    let date = v.planned_date("date", &date);
+    v.finish()?;
+    let date = date.expect("validated above");
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(prediction::phase_info(date, data))
+}
+
+#[tauri::command]
+pub fn get_libido_by_phase(state: State<'_, AppState>) -> Result<Vec<PhaseAverage>, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    if !data.settings.track_libido {
+        return Ok(Vec::new());
+    }
+    Ok(prediction::libido_by_phase(data))
+}
+
+#[tauri::command]
+pub fn get_symptom_phase_stats(
+    include_excluded: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SymptomPhaseStats>, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(prediction::symptom_phase_stats(&scoped(
+        data,
+        include_excluded,
+    )))
+}
+
+#[tauri::command]
+pub fn get_symptom_heatmap(
+    include_excluded: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<SymptomHeatmap, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(analytics::symptom_heatmap(&scoped(data, include_excluded)))
+}
+
+/// Widest moving average a series can be smoothed with.
+const MAX_SERIES_SMOOTHING: usize = 30;
+
+#[tauri::command]
+pub fn get_series(
+    metric: SeriesMetric,
+    from: String,
+    to: String,
+    resolution: SeriesResolution,
+    smoothing: Option<usize>,
+    include_excluded: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SeriesPoint>, CommandError> {
+    let mut v = Validator::new();
+    let from = v.past_date("from", &from);
+    let to = v.past_date("to", &to);
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            v.error("to", "must not be before from");
+        }
+    }
+    if let Some(smoothing) = smoothing {
+        v.in_range("smoothing", smoothing, 1, MAX_SERIES_SMOOTHING);
+    }
+    v.finish()?;
+    let (from, to) = (from.expect("validated above"), to.expect("validated above"));
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(analytics::series(
+        &scoped(data, include_excluded),
+        &metric,
+        from,
+        to,
+        resolution,
+        smoothing,
+    ))
+}
+
+#[tauri::command]
+pub fn get_symptom_correlations(
+    state: State<'_, AppState>,
+) -> Result<CooccurrenceReport, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(analytics::cooccurrence(data))
+}
+
+/// Cycles averaged over in severity trends unless asked.
+const DEFAULT_SEVERITY_WINDOW: usize = 3;
+
+#[tauri::command]
+pub fn get_severity_trends(
+    window: Option<usize>,
+    include_excluded: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SeverityTrend>, CommandError> {
+    let window = window.unwrap_or(DEFAULT_SEVERITY_WINDOW);
+    let mut v = Validator::new();
+    v.in_range("window", window, 1, 12);
+    v.finish()?;
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(analytics::severity_trends(
+        &scoped(data, include_excluded),
+        window,
+    ))
+}
+
+#[tauri::command]
+pub fn get_flow_profiles(
+    include_excluded: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<CycleFlowProfile>, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(analytics::flow_profiles(&scoped(data, include_excluded)))
+}
+
+#[tauri::command]
+pub fn get_year_review(
+    year: i32,
+    include_excluded: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<YearReview, CommandError> {
+    let mut v = Validator::new();
+    v.in_range("year", year, 1950, chrono::Local::now().year());
+    v.finish()?;
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(analytics::year_review(
+        &scoped(data, include_excluded),
+        year,
+    ))
+}
+
+#[tauri::command]
+pub fn get_monthly_symptom_summary(
+    year: i32,
+    state: State<'_, AppState>,
+) -> Result<Vec<MonthlySymptoms>, CommandError> {
+    let mut v = Validator::new();
+    v.in_range("year", year, 1950, chrono::Local::now().year());
+    v.finish()?;
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(analytics::monthly_symptom_summary(data, year))
+}
+
+/// Deprecated: use [`update_settings`].
+#[tauri::command]
+pub fn toggle_health_flags(enabled: bool, state: State<'_, AppState>) -> Result<(), CommandError> {
+    change_settings(&state, |s| s.show_health_flags = enabled)
+}
+
+/// Deprecated: use [`update_settings`].
+#[tauri::command]
+pub fn toggle_pattern_screening(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    change_settings(&state, |s| s.pattern_screening = enabled)
+}
+
+/// Deprecated: use [`update_settings`].
+#[tauri::command]
+pub fn toggle_libido(enabled: bool, state: State<'_, AppState>) -> Result<(), CommandError> {
+    change_settings(&state, |s| s.track_libido = enabled)
+}
+
+/// Deprecated: use [`update_settings`].
+#[tauri::command]
+pub fn set_temperature_unit(
+    unit: TemperatureUnit,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    change_settings(&state, |s| s.temperature_unit = unit)
+}
+
+/// Deprecated: use [`update_settings`].
+#[tauri::command]
+pub fn set_prediction_model(
+    model: PredictionModel,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    change_settings(&state, |s| s.prediction_model = model)
+}
+
+/// Deprecated: use [`update_settings`].
+#[tauri::command]
+pub fn set_prediction_history_cycles(
+    cycles: u32,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    change_settings(&state, |s| s.prediction_history_cycles = cycles)
+}
+
+/// Deprecated: use [`update_settings`].
+#[tauri::command]
+pub fn set_cycle_gap_days(gap_days: u32, state: State<'_, AppState>) -> Result<(), CommandError> {
+    change_settings(&state, |s| s.cycle_gap_days = gap_days)
+}
+
+/// Deprecated: use [`update_settings`].
+#[tauri::command]
+pub fn set_cycle_length_bounds(
+    min_days: u32,
+    max_days: u32,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    change_settings(&state, |s| {
+        s.min_cycle_days = min_days;
+        s.max_cycle_days = max_days;
+    })
+}
+
+/// Deprecated: use [`update_settings`].
+#[tauri::command]
+pub fn set_late_period_sensitivity(
+    sensitivity: Sensitivity,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    change_settings(&state, |s| s.late_period_sensitivity = sensitivity)
+}
+
+/// Deprecated: use [`update_settings`].
+#[tauri::command]
+pub fn toggle_perimenopause(enabled: bool, state: State<'_, AppState>) -> Result<(), CommandError> {
+    change_settings(&state, |s| s.perimenopause_mode = enabled)
+}
+
+/// Deprecated: use [`update_settings`].
+#[tauri::command]
+pub fn set_contraception_mode(
+    mode: ContraceptionMode,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    change_settings(&state, |s| s.contraception_mode = mode)
+}
+
+/// Replace every setting at once. All fields are checked and every problem
+/// reported together; nothing changes unless all of them are valid.
+#[tauri::command]
+pub fn update_settings(
+    settings: AppSettings,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    change_settings(&state, |s| *s = settings)
+}
+
+/// Change only the settings named in `patch`, leaving the rest as they
+/// are. Unknown keys and values of the wrong shape are reported per key.
+#[tauri::command]
+pub fn patch_settings(
+    patch: serde_json::Map<String, serde_json::Value>,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let current = {
+        let data_lock = state.data.lock()?;
+        let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+        data.settings.clone()
+    };
+    let settings = patched(&current, patch)?;
+    change_settings(&state, |s| *s = settings)
+}
+
+#[tauri::command]
+pub fn add_note_template(
+    name: String,
+    notes: String,
+    flow_level: Option<FlowLevel>,
+    state: State<'_, AppState>,
+) -> Result<NoteTemplate, CommandError> {
+    let template = NoteTemplate {
+        id: Uuid::new_v4(),
+        name,
+        notes,
+        flow_level,
+    };
+    let added = template.clone();
+    change_settings(&state, |s| s.note_templates.push(added))?;
+    Ok(template)
+}
+
+#[tauri::command]
+pub fn update_note_template(
+    id: Uuid,
+    name: String,
+    notes: String,
+    flow_level: Option<FlowLevel>,
+    state: State<'_, AppState>,
+) -> Result<NoteTemplate, CommandError> {
+    find_note_template(&state, id)?;
+    let template = NoteTemplate {
+        id,
+        name,
+        notes,
+        flow_level,
+    };
+    let updated = template.clone();
+    change_settings(&state, |s| {
+        for t in s.note_templates.iter_mut().filter(|t| t.id == id) {
+            *t = updated.clone();
+        }
+    })?;
+    Ok(template)
+}
+
+#[tauri::command]
+pub fn delete_note_template(id: Uuid, state: State<'_, AppState>) -> Result<(), CommandError> {
+    find_note_template(&state, id)?;
+    change_settings(&state, |s| s.note_templates.retain(|t| t.id != id))
+}
+
+fn find_note_template(state: &AppState, id: Uuid) -> Result<NoteTemplate, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    data.settings
+        .note_templates
+        .iter()
+        .find(|t| t.id == id)
+        .cloned()
+        .ok_or(CommandError::NotFound {
+            entity: "note template",
+        })
+}
+
+/// `settings` with the fields in `patch` replaced.
+fn patched(
+    settings: &AppSettings,
+    patch: serde_json::Map<String, serde_json::Value>,
+) -> Result<AppSettings, CommandError> {
+    let serde_json::Value::Object(current) = serde_json::to_value(settings)? else {
+        unreachable!("settings serialize as an object");
+    };
+
+    let mut v = Validator::new();
+    let mut merged = current.clone();
+    for (key, value) in patch {
+        if !current.contains_key(&key) {
+            v.error(&key, "unknown setting");
+            continue;
+        }
+        // Try each key on its own so a bad value is reported against it
+        let mut single = current.clone();
+        single.insert(key.clone(), value.clone());
+        if let Err(e) = serde_json::from_value::<AppSettings>(serde_json::Value::Object(single)) {
+            v.error(&key, e.to_string());
+            continue;
+        }
+        merged.insert(key, value);
+    }
+    v.finish()?;
+    Ok(serde_json::from_value(serde_json::Value::Object(merged))?)
+}
+
+/// Apply `change` to a copy of the settings, validate the result and store
+/// it. Changing the gap between flow days changes which days belong
+/// together, so cycles are re-detected straight away.
+fn change_settings(
+    state: &AppState,
+    change: impl FnOnce(&mut AppSettings),
+) -> Result<(), CommandError> {
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+
+    let mut settings = data.settings.clone();
+    change(&mut settings);
+    let mut v = Validator::new();
+    validate_settings(&mut v, &settings);
+    v.finish()?;
+
+    let regroup = settings.cycle_gap_days != data.settings.cycle_gap_days;
+    data.settings = settings;
+    if regroup {
+        prediction::rebuild_cycles(data);
+        prediction::accuracy::record(data, chrono::Local::now().date_naive());
+    }
+
+    drop(data_lock);
+    state.save_data()?;
+    Ok(())
+}
+
+fn validate_settings(v: &mut Validator, settings: &AppSettings) {
+    v.in_range("auto_lock_minutes", settings.auto_lock_minutes, 1, 60);
+    if let Some(attempts) = settings.wipe_after_attempts {
+        // Any lower and a couple of typos would wipe the data
+        v.in_range("wipe_after_attempts", attempts, 3, 100);
+    }
+    if let ContraceptionMode::Pill {
+        active_days,
+        break_days,
+        ..
+    } = settings.contraception_mode
+    {
+        v.in_range("contraception_mode.active_days", active_days, 1, 365);
+        v.in_range("contraception_mode.break_days", break_days, 0, 14);
+    }
+    v.in_range(
+        "prediction_history_cycles",
+        settings.prediction_history_cycles,
+        2,
+        24,
+    );
+    v.in_range("cycle_gap_days", settings.cycle_gap_days, 1, 7);
+    v.in_range("min_cycle_days", settings.min_cycle_days, 7, 21);
+    v.in_range("max_cycle_days", settings.max_cycle_days, 35, 365);
+    for (i, template) in settings.note_templates.iter().enumerate() {
+        let field = |name| format!("note_templates[{i}].{name}");
+        v.required(&field("name"), &template.name);
+        v.max_chars(&field("name"), &template.name, validation::MAX_LABEL_CHARS);
+        v.max_chars(&field("notes"), &template.notes, validation::MAX_NOTE_CHARS);
+    }
+}
+
+/// Export everything as JSON, reporting progress under `operation_id`.
+#[tauri::command(async)]
+pub fn export_data(
+    options: Option<ExportOptions>,
+    operation_id: Option<Uuid>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+    let options = options.unwrap_or_default();
+    check_export_range(&options)?;
+
+    let progress = Progress::new(&app, &state.cancellations, operation_id, "export")?;
+    progress.stage("copying", 0)?;
+    let mut export = {
+        let data_lock = state.data.lock()?;
+        data_lock.as_ref().ok_or(CommandError::Locked)?.clone()
+    };
+    progress.stage("converting", 30)?;
+    filter_export(&mut export, &options);
+    localize_temperatures(
+        export.day_logs.values_mut(),
+        export.settings.temperature_unit,
+    );
+    progress.stage("serializing", 60)?;
+    let mut value = serde_json::to_value(&export)?;
+    if options.omit_settings {
+        if let Some(fields) = value.as_object_mut() {
+            fields.remove("settings");
+        }
+    }
+    let json = serde_json::to_string_pretty(&value)?;
+    progress.finish();
+    Ok(json)
+}
+
+/// The same records as [`export_data`] would include, as FHIR R4
+/// resources for patient portals and other health record systems.
+#[tauri::command]
+pub fn export_fhir(
+    options: Option<ExportOptions>,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+    let options = options.unwrap_or_default();
+    check_export_range(&options)?;
+
+    let mut export = {
+        let data_lock = state.data.lock()?;
+        data_lock.as_ref().ok_or(CommandError::Locked)?.clone()
+    };
+    filter_export(&mut export, &options);
+    Ok(serde_json::to_string_pretty(&export::fhir(
+        &export,
+        Utc::now(),
+    ))?)
+}
+
+/// Cycle lengths and symptom frequencies by phase, with no dates, notes or
+/// logs, for sharing patterns without the calendar behind them.
+#[tauri::command]
+pub fn export_aggregates(state: State<'_, AppState>) -> Result<String, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(serde_json::to_string_pretty(&export::aggregates(data))?)
+}
+
+fn check_export_range(options: &ExportOptions) -> Result<(), CommandError> {
+    let mut v = Validator::new();
+    if let (Some(from), Some(to)) = (options.from, options.to) {
+        if to < from {
+            v.error("to", "must not be before from");
+        }
+    }
+    Ok(v.finish()?)
+}
+
+/// Drop whatever `options` leaves out of an export. Settings are removed
+/// later, from the serialized form, as `AppData` always has them.
+fn filter_export(data: &mut AppData, options: &ExportOptions) {
+    let from = options.from.unwrap_or(NaiveDate::MIN);
+    let to = options.to.unwrap_or(NaiveDate::MAX);
+    let within = |date: &NaiveDate| (from..=to).contains(date);
+
+    data.cycles
+        .retain(|c| c.start_date <= to && c.end_date.is_none_or(|end| end >= from));
+    data.day_logs.retain(|date, _| within(date));
+    data.symptoms.retain(|date, _| within(date));
+    data.pain.retain(|p| within(&p.date));
+    data.pill_doses.retain(|d| within(&d.date));
+    data.appointments.retain(|a| within(&a.date));
+    data.lab_results.retain(|r| within(&r.date));
+    data.prediction_history.retain(|p| within(&p.made_on));
+
+    if options.omit_notes {
+        for log in data.day_logs.values_mut() {
+            log.notes.clear();
+        }
+        for appointment in &mut data.appointments {
+            appointment.notes.clear();
+        }
+    }
+    if options.omit_symptoms {
+        data.symptoms.clear();
+        data.pain.clear();
+    }
+}
+
+/// Day logs, symptoms and cycles as CSV files for spreadsheets.
+#[tauri::command]
+pub fn export_csv(
+    options: Option<CsvOptions>,
+    state: State<'_, AppState>,
+) -> Result<Vec<CsvFile>, CommandError> {
+    let options = options.unwrap_or_default();
+    let delimiter = options.delimiter.unwrap_or(',');
+    let date_format = options
+        .date_format
+        .as_deref()
+        .unwrap_or(export::DEFAULT_CSV_DATE_FORMAT);
+    let mut v = Validator::new();
+    if !export::CSV_DELIMITERS.contains(&delimiter) {
+        v.error("delimiter", "must be a comma, semicolon, tab or |");
+    }
+    v.date_format("date_format", date_format);
+    v.finish()?;
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(export::csv(data, delimiter, date_format))
+}
+
+/// Predicted periods, and optionally fertile windows, as an iCalendar file
+/// to overlay on another calendar.
+#[tauri::command]
+pub fn export_ical(
+    options: Option<IcalOptions>,
+    state: State<'_, AppState>,
+) -> Result<String, CommandError> {
+    let options = options.unwrap_or_default();
+    let count = options.count.unwrap_or(DEFAULT_PREDICTED_CYCLES);
+    let period_title = options
+        .period_title
+        .as_deref()
+        .unwrap_or(export::DEFAULT_ICAL_TITLE);
+    let fertile_title = options
+        .fertile_title
+        .as_deref()
+        .unwrap_or(export::DEFAULT_ICAL_TITLE);
+    let mut v = Validator::new();
+    v.in_range("count", count, 1, MAX_PREDICTED_CYCLES);
+    for (field, title) in [
+        ("period_title", period_title),
+        ("fertile_title", fertile_title),
+    ] {
+        v.required(field, title);
+        v.max_chars(field, title, validation::MAX_LABEL_CHARS);
+    }
+    v.finish()?;
+
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    let predictions = prediction::upcoming(data, count);
+    let fertile_windows = if options.include_fertile_window {
+        prediction::upcoming_fertility_windows(data, &predictions)
+    } else {
+        Vec::new()
+    };
+    Ok(export::ical(
+        &predictions,
+        &fertile_windows,
+        period_title.trim(),
+        fertile_title.trim(),
+        Utc::now(),
+    ))
+}
+
+/// Write a report of cycles starting between `from` and `to` to `path`,
+/// for taking to a clinician: a PDF unless another `format` is asked for.
+/// It is made entirely on this device.
+#[tauri::command]
+pub fn generate_report(
+    path: PathBuf,
+    from: String,
+    to: String,
+    sections: Vec<ReportSection>,
+    format: Option<ReportFormat>,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let mut v = Validator::new();
+    let from = v.past_date("from", &from);
+    let to = v.past_date("to", &to);
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            v.error("to", "must not be before from");
+        }
+    }
+    if sections.is_empty() {
+        v.error("sections", "must include at least one section");
+    }
+    v.finish()?;
+    let (from, to) = (from.expect("validated above"), to.expect("validated above"));
+
+    let report = {
+        let data_lock = state.data.lock()?;
+        let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+        let today = chrono::Local::now().date_naive();
+        report::build(data, from, to, &sections, today)
+    };
+    let bytes = match format.unwrap_or_default() {
+        ReportFormat::Pdf => report::pdf::render(&report),
+        ReportFormat::Markdown => report::markdown::render(&report).into_bytes(),
+        ReportFormat::Html => report::html::render(&report).into_bytes(),
+    };
+    std::fs::write(&path, bytes).map_err(|e| CommandError::Storage {
+        message: e.to_string(),
+    })
+}
+
+/// Days shown in an import preview.
+const IMPORT_PREVIEW_DAYS: usize = 50;
+/// Days merged between progress reports and chances to cancel.
+const IMPORT_BATCH_DAYS: usize = 500;
+
+/// Read days from a spreadsheet through `mapping`. Unless `dry_run` is
+/// false this only reports what would be imported.
+#[tauri::command(async)]
+pub fn import_csv(
+    source: ImportSource,
+    mapping: CsvMapping,
+    dry_run: Option<bool>,
+    operation_id: Option<Uuid>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ImportReport, CommandError> {
+    let content = read_source(source)?;
+    import_with(&state, &app, operation_id, dry_run, |data| {
+        Ok(importers::csv::import(
+            &content,
+            &mapping,
+            &data.symptom_registry,
+            data.settings.temperature_unit,
+        )?)
+    })
+}
+
+/// Import a Clue data export. Periods, pain, mood, energy, skin and
+/// digestion are mapped; other categories are listed as unmapped.
+#[tauri::command(async)]
+pub fn import_clue(
+    source: ImportSource,
+    dry_run: Option<bool>,
+    operation_id: Option<Uuid>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ImportReport, CommandError> {
+    let content = read_source(source)?;
+    import_with(&state, &app, operation_id, dry_run, |_| {
+        Ok(importers::clue::import(&content)?)
+    })
+}
+
+/// Import the data file from a Flo export archive, reporting events that
+/// have no equivalent here.
+#[tauri::command(async)]
+pub fn import_flo(
+    source: ImportSource,
+    dry_run: Option<bool>,
+    operation_id: Option<Uuid>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ImportReport, CommandError> {
+    let content = read_source(source)?;
+    import_with(&state, &app, operation_id, dry_run, |_| {
+        Ok(importers::flo::import(&content)?)
+    })
+}
+
+/// Import a CSV export from the drip. app. Cervical mucus has no
+/// equivalent here and is reported as unmapped.
+#[tauri::command(async)]
+pub fn import_drip(
+    source: ImportSource,
+    dry_run: Option<bool>,
+    operation_id: Option<Uuid>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ImportReport, CommandError> {
+    let content = read_source(source)?;
+    import_with(&state, &app, operation_id, dry_run, |_| {
+        Ok(importers::drip::import(&content)?)
+    })
+}
+
+/// Import menstrual flow, basal temperature, ovulation tests and cycle
+/// symptoms from the `export.xml` in an Apple Health export.
+#[tauri::command(async)]
+pub fn import_apple_health(
+    source: ImportSource,
+    dry_run: Option<bool>,
+    operation_id: Option<Uuid>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ImportReport, CommandError> {
+    let content = read_source(source)?;
+    import_with(&state, &app, operation_id, dry_run, |_| {
+        Ok(importers::apple_health::import(&content)?)
+    })
+}
+
+/// Import nightly temperatures from a wearable's or a Bluetooth
+/// thermometer app's CSV export. The columns are found by the device's
+/// usual header names unless given.
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+pub fn import_temperatures(
+    source: ImportSource,
+    device: TemperatureDevice,
+    date_column: Option<String>,
+    temperature_column: Option<String>,
+    dry_run: Option<bool>,
+    operation_id: Option<Uuid>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ImportReport, CommandError> {
+    let content = read_source(source)?;
+    import_with(&state, &app, operation_id, dry_run, |data| {
+        Ok(importers::temperature::import(
+            &content,
+            device,
+            date_column.as_deref(),
+            temperature_column.as_deref(),
+            importers::temperature::baseline(data),
+        )?)
+    })
+}
+
+fn read_source(source: ImportSource) -> Result<String, CommandError> {
+    source
+        .read()
+        .map_err(|e| CommandError::invalid("source", e.to_string()))
+}
+
+/// Parse with `parse`, then report and, unless it's a dry run (the
+/// default), merge the result in, reporting progress under `operation_id`.
+/// Parsing works on a copy of the data, so other commands aren't held up
+/// by a large file. An import cancelled part way leaves the data as it was.
+fn import_with(
+    state: &AppState,
+    app: &AppHandle,
+    operation_id: Option<Uuid>,
+    dry_run: Option<bool>,
+    parse: impl FnOnce(&AppData) -> Result<importers::Parsed, CommandError>,
+) -> Result<ImportReport, CommandError> {
+    let progress = Progress::new(app, &state.cancellations, operation_id, "import")?;
+    progress.stage("parsing", 0)?;
+    let snapshot = {
+        let data_lock = state.data.lock()?;
+        data_lock.as_ref().ok_or(CommandError::Locked)?.clone()
+    };
+    let parsed = parse(&snapshot)?;
+    let days = importers::merge_duplicates(parsed.days);
+    let report = ImportReport {
+        days: days.len(),
+        preview: days.iter().take(IMPORT_PREVIEW_DAYS).cloned().collect(),
+        errors: parsed.errors,
+        unmapped: parsed.unmapped,
+        already_logged: days.iter().filter(|d| already_logged(&snapshot, d)).count(),
+        committed: !dry_run.unwrap_or(true),
+    };
+    drop(snapshot);
+    if !report.committed {
+        progress.finish();
+        return Ok(report);
+    }
+
+    let now = Utc::now();
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let batches = days.len().div_ceil(IMPORT_BATCH_DAYS);
+    jobs::run(data, |data| {
+        for (i, batch) in days.chunks(IMPORT_BATCH_DAYS).enumerate() {
+            progress.stage("merging", (10 + 80 * i / batches) as u8)?;
+            merge_imported(data, batch.to_vec(), now);
+        }
+        prediction::accuracy::record(data, chrono::Local::now().date_naive());
+        progress.stage("saving", 90)
+    })?;
+    drop(data_lock);
+    state.save_data()?;
+    progress.finish();
+    Ok(report)
+}
+
+/// Whether merging `day` would change nothing, e.g. when importing the
+/// same file twice.
+fn already_logged(data: &AppData, day: &ImportedDay) -> bool {
+    let log = data.day_logs.get(&day.date);
+    let symptoms = data.symptoms.get(&day.date).map_or(&[][..], Vec::as_slice);
+    let flow = day
+        .flow_level
+        .as_ref()
+        .is_none_or(|f| log.is_some_and(|l| &l.flow_level == f));
+    let notes = day
+        .notes
+        .as_ref()
+        .is_none_or(|n| log.is_some_and(|l| &l.notes == n));
+    let bbt = day.bbt.is_none_or(|t| {
+        log.is_some_and(|l| l.bbt == Some(round_temperature(t)) && l.bbt_source == day.bbt_source)
+    });
+    let cervix = day
+        .cervix
+        .as_ref()
+        .is_none_or(|c| log.is_some_and(|l| l.cervix.as_ref() == Some(c)));
+    let libido = day
+        .libido
+        .is_none_or(|n| log.is_some_and(|l| l.libido == Some(n)));
+    let lh_test = day
+        .lh_test
+        .is_none_or(|r| log.is_some_and(|l| l.lh_test == Some(r)));
+    let symptoms = day.symptoms.iter().all(|(symptom_type, severity)| {
+        symptoms
+            .iter()
+            .any(|s| &s.symptom_type == symptom_type && s.severity == *severity)
+    });
+    flow && notes && bbt && cervix && libido && lh_test && symptoms
+}
+
+/// Merge imported days into the data: fields an import leaves out keep
+/// what's logged, and imported symptoms are laid over the day's others.
+fn merge_imported(data: &mut AppData, days: Vec<ImportedDay>, now: DateTime<Utc>) {
+    for day in days {
+        let date = day.date;
+        let logged = day.flow_level.is_some()
+            || day.notes.is_some()
+            || day.bbt.is_some()
+            || day.cervix.is_some()
+            || day.libido.is_some()
+            || day.lh_test.is_some();
+        if logged {
+            let log = data.day_logs.entry(date).or_insert_with(|| DayLog {
+                created_at: Some(now),
+                ..DayLog::new(date)
+            });
+            if let Some(flow_level) = day.flow_level {
+                log.flow_level = flow_level;
+            }
+            if let Some(notes) = day.notes {
+                log.notes = notes;
+            }
+            if let Some(bbt) = day.bbt {
+                log.bbt = Some(round_temperature(bbt));
+                log.bbt_source = day.bbt_source;
+            }
+            if let Some(cervix) = day.cervix {
+                log.cervix = Some(cervix);
+            }
+            if let Some(libido) = day.libido {
+                log.libido = Some(libido);
+            }
+            if let Some(lh_test) = day.lh_test {
+                log.lh_test = Some(lh_test);
+            }
+            log.updated_at = Some(now);
+        }
+        if !day.symptoms.is_empty() {
+            let previous = data.symptoms.get(&date).map_or(&[][..], Vec::as_slice);
+            let symptoms =
+                stamp_symptoms(previous, with_preset(previous, &day.symptoms), date, now);
+            data.symptoms.insert(date, symptoms);
+        }
+    }
+    prediction::rebuild_cycles(data);
+}
+
+/// Ask the long-running operation under `operation_id` (an export,
+/// import, restore, merge or storage conversion) to stop at its next
+/// stage. A cancelled import takes out the days it already merged, unless
+/// it has got as far as saving.
+#[tauri::command]
+pub fn cancel_operation(
+    operation_id: Uuid,
+    state: State<'_, AppState>,
+) -> Result<(), CommandError> {
+    state.cancellations.cancel(operation_id)
+}
+
+/// Write a `.cykelbak` backup of everything to `path`, encrypted with
+/// `bundle_passphrase` so it can be restored with no other secret.
+#[tauri::command]
+pub fn create_bundle(
+    path: PathBuf,
+    mut bundle_passphrase: String,
+    state: State<'_, AppState>,
+) -> Result<BundleManifest, CommandError> {
+    let mut v = Validator::new();
+    v.required("bundle_passphrase", &bundle_passphrase);
+    v.finish()?;
+
+    let (manifest, bundle) = {
+        let data_lock = state.data.lock()?;
+        let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+        bundle::create(data, &bundle_passphrase, Utc::now())?
+    };
+    bundle_passphrase.zeroize();
+    std::fs::write(&path, bundle).map_err(|e| CommandError::Storage {
+        message: e.to_string(),
+    })?;
+    Ok(manifest)
+}
+
+/// Replace everything with the contents of the `.cykelbak` backup at
+/// `path`, reporting progress under `operation_id`. The restored data is
+/// then kept under the current passphrase; undo goes back to what was
+/// there before.
+#[tauri::command(async)]
+pub fn restore_bundle(
+    path: PathBuf,
+    mut bundle_passphrase: String,
+    operation_id: Option<Uuid>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<BundleManifest, CommandError> {
+    let progress = Progress::new(&app, &state.cancellations, operation_id, "restore")?;
+    let opened = read_bundle(&path, &progress).and_then(|b| {
+        progress.stage("decrypting", 20)?;
+        Ok(bundle::open(&b, &bundle_passphrase)?)
+    });
+    bundle_passphrase.zeroize();
+    restore(&state, &progress, opened?)
+}
+
+/// Combine the `.cykelbak` backup at `path` with the data here instead of
+/// replacing it, e.g. after editing on two devices. Nothing logged on
+/// either side is lost; see [`merge::merge`].
+#[tauri::command(async)]
+pub fn merge_bundle(
+    path: PathBuf,
+    mut bundle_passphrase: String,
+    operation_id: Option<Uuid>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<MergeReport, CommandError> {
+    let progress = Progress::new(&app, &state.cancellations, operation_id, "merge")?;
+    let opened = read_bundle(&path, &progress).and_then(|b| {
+        progress.stage("decrypting", 20)?;
+        Ok(bundle::open(&b, &bundle_passphrase)?)
+    });
+    bundle_passphrase.zeroize();
+    let (_, mut theirs) = opened?;
+    progress.stage("merging", 50)?;
+    migrations::migrate(&mut theirs);
+
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    let (mut merged, report) = merge::merge(data, &theirs);
+    prediction::rebuild_cycles(&mut merged);
+    progress.stage("saving", 80)?;
+    *data = merged;
+    drop(data_lock);
+    state.save_data()?;
+    progress.finish();
+    Ok(report)
+}
+
+fn read_bundle(path: &Path, progress: &Progress) -> Result<Vec<u8>, CommandError> {
+    progress.stage("reading", 0)?;
+    std::fs::read(path).map_err(|e| CommandError::invalid("path", e.to_string()))
+}
+
+/// Split a backup encrypted with `bundle_passphrase` into QR codes for
+/// another device to scan.
+#[tauri::command]
+pub fn create_qr_transfer(
+    mut bundle_passphrase: String,
+    state: State<'_, AppState>,
+) -> Result<QrTransfer, CommandError> {
+    let mut v = Validator::new();
+    v.required("bundle_passphrase", &bundle_passphrase);
+    v.finish()?;
+
+    let (manifest, bundle) = {
+        let data_lock = state.data.lock()?;
+        let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+        bundle::create(data, &bundle_passphrase, Utc::now())?
+    };
+    bundle_passphrase.zeroize();
+    Ok(QrTransfer {
+        manifest,
+        chunks: transfer::split(&bundle),
+    })
+}
+
+/// How many of a transfer's codes have been scanned, to guide scanning.
+#[tauri::command]
+pub fn check_qr_transfer(chunks: Vec<String>) -> Result<QrTransferProgress, CommandError> {
+    Ok(transfer::progress(&chunks)?)
+}
+
+/// Restore from every code of a QR transfer, scanned in any order, like
+/// [`restore_bundle`].
+#[tauri::command(async)]
+pub fn import_qr_transfer(
+    chunks: Vec<String>,
+    mut bundle_passphrase: String,
+    operation_id: Option<Uuid>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<BundleManifest, CommandError> {
+    let progress = Progress::new(&app, &state.cancellations, operation_id, "restore")?;
+    let bundle = transfer::join(&chunks);
+    let opened = bundle.map_err(CommandError::from).and_then(|b| {
+        progress.stage("decrypting", 20)?;
+        Ok(bundle::open(&b, &bundle_passphrase)?)
+    });
+    bundle_passphrase.zeroize();
+    restore(&state, &progress, opened?)
+}
+
+/// Replace everything with an opened bundle's data, brought up to date.
+/// Cancelling before the last stage leaves the data as it was.
+fn restore(
+    state: &AppState,
+    progress: &Progress,
+    (manifest, mut restored): (BundleManifest, AppData),
+) -> Result<BundleManifest, CommandError> {
+    progress.stage("migrating", 50)?;
+    migrations::migrate(&mut restored);
+    prediction::rebuild_cycles(&mut restored);
+
+    progress.stage("saving", 80)?;
+    let mut data_lock = state.data.lock()?;
+    let data = data_lock.as_mut().ok_or(CommandError::Locked)?;
+    *data = restored;
+    drop(data_lock);
+    state.save_data()?;
+    progress.finish();
+    Ok(manifest)
+}
+
+/// Move the data into `layout`, e.g. monthly files for a Syncthing
+/// folder, re-encrypting all of it. Later saves keep to it. Progress is
+/// reported under `operation_id`.
+#[tauri::command(async)]
+pub fn set_storage_layout(
+    layout: StorageLayout,
+    operation_id: Option<Uuid>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<AppInfo, CommandError> {
+    let progress = Progress::new(&app, &state.cancellations, operation_id, "convert")?;
+    {
+        let pass = state.passphrase.lock()?;
+        let data = state.data.lock()?;
+        let (Some(pass), Some(data)) = (pass.as_ref(), data.as_ref()) else {
+            return Err(CommandError::Locked);
+        };
+        progress.stage("encrypting", 0)?;
+        storage::convert(pass, data, layout)?;
+    }
+    progress.finish();
+    drop(progress);
+    get_app_info(state)
+}
+
+#[tauri::command]
+pub fn wipe_all_data(state: State<'_, AppState>) -> Result<(), CommandError> {
+    state.lock();
+    Ok(storage::wipe()?)
+}
+
+#[tauri::command]
+pub fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, CommandError> {
+    let data_lock = state.data.lock()?;
+    let data = data_lock.as_ref().ok_or(CommandError::Locked)?;
+    Ok(data.settings.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchanged_symptoms_keep_timestamps() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let earlier = DateTime::parse_from_rfc3339("2026-03-01T08:00:00Z")
+            .unwrap()
+            .to_utc();
+        let now = DateTime::parse_from_rfc3339("2026-03-01T20:00:00Z")
+            .unwrap()
+            .to_utc();
+        let logged = |symptom_type: &str, severity| Symptom {
+            date,
+            symptom_type: symptom_type.to_string(),
+            severity,
+            created_at: Some(earlier),
+            updated_at: Some(earlier),
+        };
+        let previous = [logged(symptoms::CRAMPS, 2), logged(symptoms::ACNE, 1)];
+        let resolved = vec![
+            (symptoms::CRAMPS.to_string(), 2),
+            (symptoms::ACNE.to_string(), 3),
+            (symptoms::FATIGUE.to_string(), 1),
+        ];
+
+        let stamped = stamp_symptoms(&previous, resolved, date, now);
+        let times: Vec<_> = stamped
+            .iter()
+            .map(|s| (s.created_at.unwrap(), s.updated_at.unwrap()))
+            .collect();
+        assert_eq!(times, [(earlier, earlier), (earlier, now), (now, now)]);
+    }
+
+    #[test]
+    fn derived_results_cached_until_touched() {
+        let state = AppState::new();
+        let mut data = AppData::default();
+        assert_eq!(state.derived(&data).unwrap().stats.total_cycles, 0);
+
+        data.cycles.push(Cycle {
+            id: Uuid::new_v4(),
+            start_date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2026, 1, 5),
+            source: CycleSource::Manual,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: None,
+            updated_at: None,
+        });
+        assert_eq!(state.derived(&data).unwrap().stats.total_cycles, 0);
+        state.touch();
+        assert_eq!(state.derived(&data).unwrap().stats.total_cycles, 1);
+    }
+
+    #[test]
+    fn lock_drops_derived_results() {
+        let state = AppState::new();
+        *state.data.lock().unwrap() = Some(AppData::default());
+        state.derived(&AppData::default()).unwrap();
+        assert!(state.derived.lock().unwrap().is_some());
+
+        state.lock();
+        assert!(state.data.lock().unwrap().is_none());
+        assert!(state.derived.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn preset_laid_over_logged_symptoms() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 4).unwrap();
+        let logged = |symptom_type: &str, severity| Symptom {
+            date,
+            symptom_type: symptom_type.to_string(),
+            severity,
+            created_at: None,
+            updated_at: None,
+        };
+        let previous = [logged("acne", 1), logged("cramps", 1)];
+        let preset = [("cramps".to_string(), 3), ("fatigue".to_string(), 2)];
+        assert_eq!(
+            with_preset(&previous, &preset),
+            [
+                ("acne".to_string(), 1),
+                ("cramps".to_string(), 3),
+                ("fatigue".to_string(), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn auto_lock_counts_down_from_last_activity() {
+        assert_eq!(seconds_until_lock(Duration::from_secs(0), 5), 300);
+        assert_eq!(seconds_until_lock(Duration::from_secs(299), 5), 1);
+        assert_eq!(seconds_until_lock(Duration::from_secs(900), 5), 0);
+
+        let state = AppState::new();
+        assert!(!state.lock_status().unwrap().unlocked);
+        *state.data.lock().unwrap() = Some(AppData::default());
+        state.start_session().unwrap();
+        let status = state.lock_status().unwrap();
+        assert!(status.unlocked && status.session_started.is_some());
+        assert_eq!(status.seconds_until_lock, Some(300));
+    }
+
+    #[test]
+    fn reimported_days_already_logged() {
+        let day = ImportedDay {
+            date: NaiveDate::from_ymd_opt(2026, 1, 4).unwrap(),
+            flow_level: Some(FlowLevel::Medium),
+            bbt: Some(36.55),
+            symptoms: vec![("cramps".to_string(), 2)],
+            ..ImportedDay::default()
+        };
+        let mut data = AppData::default();
+        assert!(!already_logged(&data, &day));
+        merge_imported(&mut data, vec![day.clone()], Utc::now());
+        assert!(already_logged(&data, &day));
+        assert_eq!(data.cycles.len(), 1);
+
+        let heavier = ImportedDay {
+            flow_level: Some(FlowLevel::Heavy),
+            ..day
+        };
+        assert!(!already_logged(&data, &heavier));
+    }
+
+    #[test]
+    fn rejected_import_cells_not_stored() {
+        let content = format!(
+            "Date,Temp,Notes\n2026-01-04,98.1,\n2026-01-05,,{}\n",
+            "x".repeat(validation::MAX_NOTE_CHARS + 1)
+        );
+        let mapping = CsvMapping {
+            date_column: "Date".to_string(),
+            bbt_column: Some("Temp".to_string()),
+            notes_column: Some("Notes".to_string()),
+            ..CsvMapping::default()
+        };
+        let registry = symptoms::builtin_descriptors();
+        let parsed =
+            importers::csv::import(&content, &mapping, &registry, TemperatureUnit::Celsius)
+                .unwrap();
+        assert_eq!(parsed.errors.len(), 2);
+
+        let mut data = AppData::default();
+        merge_imported(&mut data, parsed.days, Utc::now());
+        assert!(data.day_logs.is_empty());
+    }
+
+    #[test]
+    fn logged_dates_skip_empty_logs() {
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        set_flow(
+            &mut data,
+            vec![(day("2026-01-04"), day("2026-01-05"), FlowLevel::Light)],
+            Utc::now(),
+        );
+        // Flow cleared again on the second day
+        data.day_logs
+            .get_mut(&day("2026-01-05"))
+            .unwrap()
+            .flow_level = FlowLevel::None;
+        data.pain.push(PainEntry {
+            date: day("2026-01-09"),
+            location: PainLocation::Head,
+            intensity: 2,
+            character: PainCharacter::Throbbing,
+        });
+
+        assert_eq!(
+            logged_dates(&data, day("2026-01-01"), day("2026-01-31")),
+            [day("2026-01-04"), day("2026-01-09")]
+        );
+    }
+
+    #[test]
+    fn cycle_changes_matched_by_id() {
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let cycle = |start: &str, end: &str| Cycle {
+            id: Uuid::new_v4(),
+            start_date: day(start),
+            end_date: Some(day(end)),
+            source: CycleSource::Detected,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let kept = cycle("2026-01-04", "2026-01-08");
+        let moved = cycle("2026-02-01", "2026-02-05");
+        let gone = cycle("2026-03-01", "2026-03-02");
+        let before = vec![kept.clone(), moved.clone(), gone.clone()];
+        let mut after = vec![kept, moved, cycle("2026-03-02", "2026-03-06")];
+        after[1].end_date = Some(day("2026-02-06"));
+
+        let (added, removed, changed) = cycle_changes(&before, &after);
+        assert_eq!(added, [after[2].clone()]);
+        assert_eq!(removed, [gone]);
+        assert_eq!(changed, [after[1].clone()]);
+    }
+
+    #[test]
+    fn export_filtered_by_range_and_content() {
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        set_flow(
+            &mut data,
+            vec![
+                (day("2025-06-01"), day("2025-06-04"), FlowLevel::Medium),
+                (day("2026-01-04"), day("2026-01-07"), FlowLevel::Heavy),
+            ],
+            Utc::now(),
+        );
+        data.day_logs.get_mut(&day("2026-01-05")).unwrap().notes = "clots".to_string();
+        data.symptoms.insert(
+            day("2026-01-05"),
+            vec![Symptom {
+                date: day("2026-01-05"),
+                symptom_type: "cramps".to_string(),
+                severity: 2,
+                created_at: None,
+                updated_at: None,
+            }],
+        );
+
+        filter_export(
+            &mut data,
+            &ExportOptions {
+                from: Some(day("2026-01-01")),
+                omit_notes: true,
+                omit_symptoms: true,
+                ..ExportOptions::default()
+            },
+        );
+        assert_eq!(data.cycles.len(), 1);
+        assert_eq!(data.cycles[0].start_date, day("2026-01-04"));
+        assert_eq!(data.day_logs.len(), 4);
+        assert!(data.day_logs.values().all(|l| l.notes.is_empty()));
+        assert!(data.symptoms.is_empty());
+    }
+
+    #[test]
+    fn range_paged_by_logged_days() {
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        set_flow(
+            &mut data,
+            vec![(day("2026-01-04"), day("2026-01-08"), FlowLevel::Medium)],
+            Utc::now(),
+        );
+        data.symptoms.insert(
+            day("2026-01-20"),
+            vec![Symptom {
+                date: day("2026-01-20"),
+                symptom_type: "acne".to_string(),
+                severity: 1,
+                created_at: None,
+                updated_at: None,
+            }],
+        );
+
+        let first = range_page(&data, day("2026-01-01"), day("2026-01-31"), 4);
+        assert_eq!(first.days.len(), 4);
+        assert_eq!(first.next_cursor, Some(day("2026-01-08")));
+        let rest = range_page(&data, first.next_cursor.unwrap(), day("2026-01-31"), 4);
+        let dates: Vec<NaiveDate> = rest.days.iter().map(|d| d.date).collect();
+        assert_eq!(dates, [day("2026-01-08"), day("2026-01-20")]);
+        assert!(rest.days[1].day_log.is_none());
+        assert_eq!(rest.next_cursor, None);
+    }
+
+    #[test]
+    fn settings_validated_field_by_field() {
+        let mut v = Validator::new();
+        validate_settings(&mut v, &AppSettings::default());
+        assert!(v.finish().is_ok());
+
+        let settings = AppSettings {
+            auto_lock_minutes: 0,
+            cycle_gap_days: 9,
+            contraception_mode: ContraceptionMode::Pill {
+                pack_start: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+                active_days: 21,
+                break_days: 30,
+            },
+            note_templates: vec![NoteTemplate {
+                id: Uuid::new_v4(),
+                name: " ".to_string(),
+                notes: "Sleep:\nMood:".to_string(),
+                flow_level: None,
+            }],
+            ..AppSettings::default()
+        };
+        let mut v = Validator::new();
+        validate_settings(&mut v, &settings);
+        let fields: Vec<String> = v
+            .finish()
+            .unwrap_err()
+            .0
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "auto_lock_minutes",
+                "contraception_mode.break_days",
+                "cycle_gap_days",
+                "note_templates[0].name"
+            ]
+        );
+    }
+
+    #[test]
+    fn settings_patch_checked_per_key() {
+        let patch = |json: &str| {
+            let map: serde_json::Map<String, serde_json::Value> =
+                serde_json::from_str(json).unwrap();
+            patched(&AppSettings::default(), map)
+        };
+
+        let settings = patch(r#"{"track_libido": true, "cycle_gap_days": 3}"#).unwrap();
+        assert!(settings.track_libido);
+        assert_eq!(settings.cycle_gap_days, 3);
+        assert_eq!(settings.auto_lock_minutes, 5);
+
+        for (json, key) in [
+            (r#"{"theme": "dark", "auto_lock_minutes": 10}"#, "theme"),
+            (r#"{"track_libido": "yes"}"#, "track_libido"),
+        ] {
+            match patch(json) {
+                Err(CommandError::InvalidInput { field, .. }) => assert_eq!(field, key),
+                other => panic!("expected {key} to be rejected, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn backfill_creates_cycles_and_keeps_notes() {
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        data.day_logs.insert(
+            day("2026-02-02"),
+            DayLog {
+                notes: "tired".to_string(),
+                ..DayLog::new(day("2026-02-02"))
+            },
+        );
+
+        set_flow(
+            &mut data,
+            vec![
+                (day("2026-01-04"), day("2026-01-08"), FlowLevel::Medium),
+                (day("2026-02-01"), day("2026-02-05"), FlowLevel::Heavy),
+                (day("2026-03-01"), day("2026-03-04"), FlowLevel::Light),
+            ],
+            Utc::now(),
+        );
+        assert_eq!(data.cycles.len(), 3);
+        assert_eq!(data.day_logs.len(), 14);
+        let kept = &data.day_logs[&day("2026-02-02")];
+        assert_eq!(kept.notes, "tired");
+        assert_eq!(kept.flow_level, FlowLevel::Heavy);
+    }
 }
@@ -1,12 +1,12 @@
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm, Nonce,
 };
 use argon2::{self, Argon2, Params};
 use rand::RngCore;
-use zeroize::Zeroize;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
-const SALT_LEN: usize = 32;
+pub const SALT_LEN: usize = 32;
 const NONCE_LEN: usize = 12;
 const KEY_LEN: usize = 32;
 /// Magic bytes prepended to plaintext before encryption.
@@ -38,16 +38,56 @@ fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; KEY_LEN], CryptoErro
     Ok(key)
 }
 
+/// A key derived once and used for many [`seal`]s, where deriving one per
+/// file would be too slow. Zeroed when dropped.
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct Key([u8; KEY_LEN]);
+
+/// A fresh random salt for [`Key::derive`].
+pub fn new_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    salt
+}
+
+impl Key {
+    pub fn derive(passphrase: &str, salt: &[u8]) -> Result<Self, CryptoError> {
+        Ok(Self(derive_key(passphrase, salt)?))
+    }
+}
+
 /// Encrypt plaintext data with a passphrase.
 /// Returns: salt (32) || nonce (12) || ciphertext
 pub fn encrypt(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
-    let mut salt = [0u8; SALT_LEN];
+    let salt = new_salt();
+    let key = Key::derive(passphrase, &salt)?;
+
+    // Output format: salt || nonce || ciphertext
+    let mut output = salt.to_vec();
+    output.extend(seal(&key, plaintext, &[])?);
+    Ok(output)
+}
+
+/// Decrypt data that was encrypted with `encrypt`.
+/// Returns the original plaintext, or an error if the passphrase is wrong.
+pub fn decrypt(passphrase: &str, encrypted: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if encrypted.len() < SALT_LEN + NONCE_LEN + MAGIC.len() {
+        return Err(CryptoError::InvalidFormat);
+    }
+
+    let (salt, sealed) = encrypted.split_at(SALT_LEN);
+    let key = Key::derive(passphrase, salt)?;
+    open(&key, sealed, &[])
+}
+
+/// Encrypt plaintext with an already derived key, authenticating `aad`
+/// along with it, e.g. the name of the file it is for.
+/// Returns: nonce (12) || ciphertext
+pub fn seal(key: &Key, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
     let mut nonce_bytes = [0u8; NONCE_LEN];
-    rand::thread_rng().fill_bytes(&mut salt);
     rand::thread_rng().fill_bytes(&mut nonce_bytes);
 
-    let mut key = derive_key(passphrase, &salt)?;
-    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| CryptoError::Encryption)?;
+    let cipher = Aes256Gcm::new_from_slice(&key.0).map_err(|_| CryptoError::Encryption)?;
     let nonce = Nonce::from_slice(&nonce_bytes);
 
     // Prepend magic bytes to plaintext for validation on decrypt
@@ -56,43 +96,40 @@ pub fn encrypt(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, CryptoErro
     payload.extend_from_slice(plaintext);
 
     let ciphertext = cipher
-        .encrypt(nonce, payload.as_slice())
+        .encrypt(nonce, Payload { msg: &payload, aad })
         .map_err(|_| CryptoError::Encryption)?;
 
     // Zeroize sensitive material
-    key.zeroize();
     payload.zeroize();
 
-    // Output format: salt || nonce || ciphertext
-    let mut output = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
-    output.extend_from_slice(&salt);
+    let mut output = Vec::with_capacity(NONCE_LEN + ciphertext.len());
     output.extend_from_slice(&nonce_bytes);
     output.extend_from_slice(&ciphertext);
 
     Ok(output)
 }
 
-/// Decrypt data that was encrypted with `encrypt`.
-/// Returns the original plaintext, or an error if the passphrase is wrong.
-pub fn decrypt(passphrase: &str, encrypted: &[u8]) -> Result<Vec<u8>, CryptoError> {
-    if encrypted.len() < SALT_LEN + NONCE_LEN + MAGIC.len() {
+/// Decrypt data that was encrypted with `seal` under the same key and
+/// `aad`.
+pub fn open(key: &Key, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if sealed.len() < NONCE_LEN + MAGIC.len() {
         return Err(CryptoError::InvalidFormat);
     }
 
-    let salt = &encrypted[..SALT_LEN];
-    let nonce_bytes = &encrypted[SALT_LEN..SALT_LEN + NONCE_LEN];
-    let ciphertext = &encrypted[SALT_LEN + NONCE_LEN..];
-
-    let mut key = derive_key(passphrase, salt)?;
-    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| CryptoError::Decryption)?;
+    let (nonce_bytes, ciphertext) = sealed.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new_from_slice(&key.0).map_err(|_| CryptoError::Decryption)?;
     let nonce = Nonce::from_slice(nonce_bytes);
 
     let mut decrypted = cipher
-        .decrypt(nonce, ciphertext)
+        .decrypt(
+            nonce,
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
         .map_err(|_| CryptoError::Decryption)?;
 
-    key.zeroize();
-
     // Verify magic bytes
     if decrypted.len() < MAGIC.len() || &decrypted[..MAGIC.len()] != MAGIC {
         decrypted.zeroize();
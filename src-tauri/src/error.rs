@@ -0,0 +1,108 @@
+use std::sync::PoisonError;
+
+use serde::Serialize;
+
+use crate::bundle::BundleError;
+use crate::crypto::CryptoError;
+use crate::storage::StorageError;
+use crate::validation::{FieldError, ValidationErrors};
+
+/// Error returned by every command. Serialized with a `kind` tag so the
+/// frontend can branch on the kind instead of parsing messages.
+#[derive(Debug, thiserror::Error, Serialize)]
+#[serde(tag = "kind")]
+pub enum CommandError {
+    #[error("app is locked")]
+    Locked,
+    /// `field` and `message` describe the first problem; `errors` lists
+    /// every one, so a form can highlight all the fields at once.
+    #[error("{field}: {message}")]
+    InvalidInput {
+        field: String,
+        message: String,
+        errors: Vec<FieldError>,
+    },
+    #[error("crypto error: {message}")]
+    Crypto { message: String },
+    #[error("storage error: {message}")]
+    Storage { message: String },
+    #[error("{entity} not found")]
+    NotFound { entity: &'static str },
+    #[error("{message}")]
+    Conflict { message: String },
+    #[error("cancelled")]
+    Cancelled,
+    #[error("internal error: {message}")]
+    Internal { message: String },
+}
+
+impl CommandError {
+    pub fn invalid(field: &str, message: impl Into<String>) -> Self {
+        let error = FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        };
+        CommandError::InvalidInput {
+            field: error.field.clone(),
+            message: error.message.clone(),
+            errors: vec![error],
+        }
+    }
+}
+
+impl From<StorageError> for CommandError {
+    fn from(err: StorageError) -> Self {
+        match err {
+            StorageError::Crypto(err) => err.into(),
+            err => CommandError::Storage {
+                message: err.to_string(),
+            },
+        }
+    }
+}
+
+impl From<BundleError> for CommandError {
+    fn from(err: BundleError) -> Self {
+        match err {
+            BundleError::Crypto(err) => err.into(),
+            err => CommandError::invalid("bundle", err.to_string()),
+        }
+    }
+}
+
+impl From<CryptoError> for CommandError {
+    fn from(err: CryptoError) -> Self {
+        CommandError::Crypto {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<ValidationErrors> for CommandError {
+    fn from(errors: ValidationErrors) -> Self {
+        match errors.0.first() {
+            Some(first) => CommandError::InvalidInput {
+                field: first.field.clone(),
+                message: first.message.clone(),
+                errors: errors.0,
+            },
+            None => CommandError::invalid("", "invalid input"),
+        }
+    }
+}
+
+impl From<serde_json::Error> for CommandError {
+    fn from(err: serde_json::Error) -> Self {
+        CommandError::Internal {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl<T> From<PoisonError<T>> for CommandError {
+    fn from(err: PoisonError<T>) -> Self {
+        CommandError::Internal {
+            message: err.to_string(),
+        }
+    }
+}
@@ -0,0 +1,547 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::models::*;
+use crate::prediction;
+
+/// Delimiters a CSV export may use.
+pub const CSV_DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+pub const DEFAULT_CSV_DATE_FORMAT: &str = "%Y-%m-%d";
+/// Calendar event title unless the user picks their own.
+pub const DEFAULT_ICAL_TITLE: &str = "•";
+const LOINC: &str = "http://loinc.org";
+const SNOMED: &str = "http://snomed.info/sct";
+const UCUM: &str = "http://unitsofmeasure.org";
+/// LOINC code for menstrual flow.
+const MENSTRUAL_FLOW_CODE: &str = "49033-4";
+/// Longest line an iCalendar file may have, in bytes, before folding.
+const ICAL_LINE_OCTETS: usize = 75;
+
+/// Day logs, symptoms (one row per symptom per day) and cycles as CSV
+/// files. Temperatures are in the user's unit.
+pub fn csv(data: &AppData, delimiter: char, date_format: &str) -> Vec<CsvFile> {
+    let table = Table {
+        delimiter,
+        date_format,
+    };
+    let unit = data.settings.temperature_unit;
+
+    let day_logs = table.render(
+        &[
+            "date",
+            "flow_level",
+            "bbt",
+            "lh_test",
+            "libido",
+            "cervix_height",
+            "cervix_firmness",
+            "cervix_openness",
+            "notes",
+        ],
+        data.day_logs.values().map(|l| {
+            vec![
+                table.date(l.date),
+                format!("{:?}", l.flow_level),
+                l.bbt
+                    .map(|t| format!("{:.2}", unit.convert_celsius(t)))
+                    .unwrap_or_default(),
+                optional(l.lh_test.map(|r| format!("{r:?}"))),
+                optional(l.libido),
+                optional(l.cervix.as_ref().map(|c| format!("{:?}", c.height))),
+                optional(l.cervix.as_ref().map(|c| format!("{:?}", c.firmness))),
+                optional(l.cervix.as_ref().map(|c| format!("{:?}", c.openness))),
+                l.notes.clone(),
+            ]
+        }),
+    );
+
+    let symptoms = table.render(
+        &["date", "symptom_type", "label", "severity"],
+        data.symptoms.values().flatten().map(|s| {
+            let label = data
+                .symptom_registry
+                .iter()
+                .find(|d| d.key == s.symptom_type)
+                .map_or(s.symptom_type.clone(), |d| d.label.clone());
+            vec![
+                table.date(s.date),
+                s.symptom_type.clone(),
+                label,
+                s.severity.to_string(),
+            ]
+        }),
+    );
+
+    let mut cycles: Vec<&Cycle> = data.cycles.iter().collect();
+    cycles.sort_by_key(|c| c.start_date);
+    let next_starts = cycles.iter().skip(1).map(|c| Some(c.start_date));
+    let cycles = table.render(
+        &[
+            "start_date",
+            "end_date",
+            "period_days",
+            "cycle_days",
+            "source",
+            "ovulation_day",
+        ],
+        cycles
+            .iter()
+            .zip(next_starts.chain([None]))
+            .map(|(c, next_start)| {
+                vec![
+                    table.date(c.start_date),
+                    optional(c.end_date.map(|d| table.date(d))),
+                    optional(c.end_date.map(|end| (end - c.start_date).num_days() + 1)),
+                    optional(next_start.map(|next| (next - c.start_date).num_days())),
+                    format!("{:?}", c.source),
+                    optional(c.ovulation.map(|o| table.date(o.day))),
+                ]
+            }),
+    );
+
+    vec![
+        CsvFile {
+            name: "day_logs.csv".to_string(),
+            content: day_logs,
+        },
+        CsvFile {
+            name: "symptoms.csv".to_string(),
+            content: symptoms,
+        },
+        CsvFile {
+            name: "cycles.csv".to_string(),
+            content: cycles,
+        },
+    ]
+}
+
+/// Cycle lengths and how often each symptom comes up in each phase, over
+/// the completed cycles that count towards stats.
+pub fn aggregates(data: &AppData) -> AggregateExport {
+    let mut starts: Vec<NaiveDate> = data.cycles.iter().map(|c| c.start_date).collect();
+    starts.sort();
+    let excluded = prediction::excluded_starts(&data.cycles, &data.settings);
+    let cycle_lengths = starts
+        .windows(2)
+        .filter(|w| !excluded.contains(&w[0]))
+        .map(|w| (w[1] - w[0]).num_days())
+        .collect();
+
+    let symptoms_by_phase = prediction::symptom_phase_stats(data)
+        .into_iter()
+        .map(|mut stats| {
+            if let Some(d) = data
+                .symptom_registry
+                .iter()
+                .find(|d| d.key == stats.symptom_type)
+            {
+                stats.symptom_type = d.label.clone();
+            }
+            stats
+        })
+        .collect();
+
+    AggregateExport {
+        cycle_lengths,
+        symptoms_by_phase,
+    }
+}
+
+/// A FHIR R4 collection bundle: an Observation for each day with flow and
+/// each completed cycle's length, and a Condition for each logged symptom.
+/// There is no Patient resource; whoever imports it attaches one.
+pub fn fhir(data: &AppData, generated: DateTime<Utc>) -> Value {
+    let mut resources = Vec::new();
+
+    for log in data.day_logs.values() {
+        if log.flow_level == FlowLevel::None {
+            continue;
+        }
+        resources.push(json!({
+            "resourceType": "Observation",
+            "status": "final",
+            "code": {
+                "coding": [{
+                    "system": LOINC,
+                    "code": MENSTRUAL_FLOW_CODE,
+                    "display": "Menstrual flow",
+                }],
+                "text": "Menstrual flow",
+            },
+            "effectiveDateTime": log.date.to_string(),
+            "valueCodeableConcept": { "text": format!("{:?}", log.flow_level) },
+        }));
+    }
+
+    let mut cycles: Vec<&Cycle> = data.cycles.iter().collect();
+    cycles.sort_by_key(|c| c.start_date);
+    for pair in cycles.windows(2) {
+        let (cycle, next) = (pair[0], pair[1]);
+        resources.push(json!({
+            "resourceType": "Observation",
+            "status": "final",
+            "code": { "text": "Menstrual cycle length" },
+            "effectivePeriod": {
+                "start": cycle.start_date.to_string(),
+                "end": (next.start_date - chrono::Duration::days(1)).to_string(),
+            },
+            "valueQuantity": {
+                "value": (next.start_date - cycle.start_date).num_days(),
+                "unit": "days",
+                "system": UCUM,
+                "code": "d",
+            },
+        }));
+    }
+
+    for symptom in data.symptoms.values().flatten() {
+        let descriptor = data
+            .symptom_registry
+            .iter()
+            .find(|d| d.key == symptom.symptom_type);
+        let label = descriptor.map_or(symptom.symptom_type.as_str(), |d| d.label.as_str());
+        let max_severity = descriptor.map_or(3, |d| d.max_severity.max(1));
+        resources.push(json!({
+            "resourceType": "Condition",
+            "clinicalStatus": {
+                "coding": [{
+                    "system": "http://terminology.hl7.org/CodeSystem/condition-clinical",
+                    "code": "resolved",
+                }],
+            },
+            "severity": severity_concept(symptom.severity, max_severity),
+            "code": { "text": label },
+            "onsetDateTime": symptom.date.to_string(),
+            "abatementDateTime": symptom.date.to_string(),
+        }));
+    }
+
+    json!({
+        "resourceType": "Bundle",
+        "type": "collection",
+        "timestamp": generated.to_rfc3339(),
+        "entry": resources
+            .into_iter()
+            .map(|resource| json!({
+                "fullUrl": format!("urn:uuid:{}", Uuid::new_v4()),
+                "resource": resource,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// FHIR's mild, moderate or severe, by where `severity` falls on the
+/// symptom's own scale.
+fn severity_concept(severity: u8, max_severity: u8) -> Value {
+    let share = f32::from(severity) / f32::from(max_severity);
+    let (code, display) = if share <= 1.0 / 3.0 {
+        ("255604002", "Mild")
+    } else if share <= 2.0 / 3.0 {
+        ("6736007", "Moderate")
+    } else {
+        ("24484000", "Severe")
+    };
+    json!({ "coding": [{ "system": SNOMED, "code": code, "display": display }] })
+}
+
+/// Predicted periods and fertile windows as all-day iCalendar events. The
+/// events carry nothing but the given titles, don't show as busy, and are
+/// numbered rather than named so re-importing updates them in place.
+pub fn ical(
+    predictions: &[Prediction],
+    fertile_windows: &[FertilityWindow],
+    period_title: &str,
+    fertile_title: &str,
+    stamp: DateTime<Utc>,
+) -> String {
+    let periods = predictions.iter().enumerate().map(|(i, p)| {
+        let uid = format!("{}a@local", i + 1);
+        (uid, p.predicted_start, p.predicted_end, period_title)
+    });
+    let fertile = fertile_windows.iter().enumerate().map(|(i, w)| {
+        let uid = format!("{}b@local", i + 1);
+        (uid, w.fertile_start, w.fertile_end, fertile_title)
+    });
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Calendar//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    for (uid, start, end, title) in periods.chain(fertile) {
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{uid}"),
+            format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")),
+            format!("DTSTART;VALUE=DATE:{}", start.format("%Y%m%d")),
+            // The end of an all-day event is exclusive
+            format!(
+                "DTEND;VALUE=DATE:{}",
+                (end + chrono::Duration::days(1)).format("%Y%m%d")
+            ),
+            format!("SUMMARY:{}", ical_text(title)),
+            "TRANSP:TRANSPARENT".to_string(),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|l| fold(l) + "\r\n").collect()
+}
+
+/// Escape `value` for an iCalendar text property.
+fn ical_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+/// Split `line` into continuation lines no longer than the limit, without
+/// splitting a character.
+fn fold(line: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > ICAL_LINE_OCTETS {
+            out.push_str("\r\n ");
+            // The leading space counts towards the next line
+            width = 1;
+        }
+        width += c.len_utf8();
+        out.push(c);
+    }
+    out
+}
+
+struct Table<'a> {
+    delimiter: char,
+    date_format: &'a str,
+}
+
+impl Table<'_> {
+    fn date(&self, date: NaiveDate) -> String {
+        date.format(self.date_format).to_string()
+    }
+
+    fn render(&self, header: &[&str], rows: impl Iterator<Item = Vec<String>>) -> String {
+        let header = header.iter().map(|h| h.to_string()).collect();
+        let mut out = String::new();
+        for row in std::iter::once(header).chain(rows) {
+            let fields: Vec<String> = row.iter().map(|f| self.field(f)).collect();
+            out.push_str(&fields.join(&self.delimiter.to_string()));
+            out.push_str("\r\n");
+        }
+        out
+    }
+
+    fn field(&self, value: &str) -> String {
+        // Spreadsheets run cells starting with these as formulas
+        let value = if value.starts_with(['=', '+', '-', '@']) {
+            format!("'{value}")
+        } else {
+            value.to_string()
+        };
+        if value.contains([self.delimiter, '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value
+        }
+    }
+}
+
+fn optional(value: Option<impl ToString>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(d: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn csv_quotes_and_formats() {
+        let mut data = AppData::default();
+        data.day_logs.insert(
+            date("2026-01-04"),
+            DayLog {
+                flow_level: FlowLevel::Heavy,
+                notes: "sore; \"bad\" day".to_string(),
+                bbt: Some(36.5),
+                ..DayLog::new(date("2026-01-04"))
+            },
+        );
+        for (start, end) in [("2026-01-04", "2026-01-08"), ("2026-02-01", "2026-02-04")] {
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: date(start),
+                end_date: Some(date(end)),
+                source: CycleSource::Detected,
+                include_in_stats: None,
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+        }
+
+        let files = csv(&data, ';', "%d/%m/%Y");
+        let day_logs: Vec<&str> = files[0].content.lines().collect();
+        assert_eq!(
+            day_logs[1],
+            "04/01/2026;Heavy;36.50;;;;;;\"sore; \"\"bad\"\" day\""
+        );
+        let cycles: Vec<&str> = files[2].content.lines().collect();
+        assert_eq!(cycles[1], "04/01/2026;08/01/2026;5;28;Detected;");
+        assert_eq!(cycles[2], "01/02/2026;04/02/2026;4;;Detected;");
+    }
+
+    #[test]
+    fn fhir_resources_per_record() {
+        let mut data = AppData::default();
+        for (start, flow_level) in [
+            ("2026-01-04", FlowLevel::Heavy),
+            ("2026-02-01", FlowLevel::Light),
+        ] {
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: date(start),
+                end_date: None,
+                source: CycleSource::Detected,
+                include_in_stats: None,
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+            data.day_logs.insert(
+                date(start),
+                DayLog {
+                    flow_level,
+                    ..DayLog::new(date(start))
+                },
+            );
+        }
+        data.symptoms.insert(
+            date("2026-01-05"),
+            vec![Symptom {
+                date: date("2026-01-05"),
+                symptom_type: "cramps".to_string(),
+                severity: 3,
+                created_at: None,
+                updated_at: None,
+            }],
+        );
+
+        let bundle = fhir(&data, DateTime::from_timestamp(0, 0).unwrap());
+        let resources: Vec<&Value> = bundle["entry"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| &e["resource"])
+            .collect();
+        assert_eq!(resources.len(), 4);
+        assert_eq!(
+            resources[0]["code"]["coding"][0]["code"],
+            MENSTRUAL_FLOW_CODE
+        );
+        assert_eq!(resources[0]["valueCodeableConcept"]["text"], "Heavy");
+        assert_eq!(resources[2]["valueQuantity"]["value"], 28);
+        assert_eq!(resources[3]["resourceType"], "Condition");
+        assert_eq!(resources[3]["severity"]["coding"][0]["display"], "Severe");
+    }
+
+    #[test]
+    fn aggregates_carry_no_dates() {
+        let mut data = AppData::default();
+        for (start, end) in [
+            ("2026-01-04", "2026-01-08"),
+            ("2026-02-01", "2026-02-05"),
+            ("2026-03-02", "2026-03-06"),
+        ] {
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: date(start),
+                end_date: Some(date(end)),
+                source: CycleSource::Detected,
+                include_in_stats: None,
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+        }
+        data.symptoms.insert(
+            date("2026-01-05"),
+            vec![Symptom {
+                date: date("2026-01-05"),
+                symptom_type: "cramps".to_string(),
+                severity: 2,
+                created_at: None,
+                updated_at: None,
+            }],
+        );
+        data.symptom_registry.push(SymptomDescriptor {
+            key: "cramps".to_string(),
+            label: "Cramps".to_string(),
+            category: SymptomCategory::Pain,
+            max_severity: 3,
+            builtin: true,
+        });
+
+        let export = aggregates(&data);
+        assert_eq!(export.cycle_lengths, [28, 29]);
+        assert_eq!(export.symptoms_by_phase[0].symptom_type, "Cramps");
+        assert_eq!(export.symptoms_by_phase[0].phases[0].days, 1);
+        let json = serde_json::to_string(&export).unwrap();
+        assert!(!json.contains("2026"));
+    }
+
+    #[test]
+    fn ical_events_neutral() {
+        let prediction = Prediction {
+            predicted_start: date("2026-03-01"),
+            predicted_end: date("2026-03-05"),
+            earliest_likely_start: date("2026-02-27"),
+            latest_likely_start: date("2026-03-03"),
+            confidence: 0.8,
+            bleed_kind: BleedKind::Period,
+            model: None,
+        };
+        let window = FertilityWindow {
+            fertile_start: date("2026-02-10"),
+            fertile_end: date("2026-02-15"),
+            ovulation_day: date("2026-02-15"),
+            peak_start: date("2026-02-13"),
+            peak_end: date("2026-02-15"),
+            basis: OvulationBasis::Calendar,
+        };
+        let stamp = DateTime::from_timestamp(0, 0).unwrap();
+
+        let ics = ical(&[prediction], &[window], "x, y", DEFAULT_ICAL_TITLE, stamp);
+        let lines: Vec<&str> = ics.split("\r\n").collect();
+        assert!(lines.contains(&"DTSTART;VALUE=DATE:20260301"));
+        assert!(lines.contains(&"DTEND;VALUE=DATE:20260306"));
+        assert!(lines.contains(&"SUMMARY:x\\, y"));
+        assert!(lines.contains(&"UID:1b@local"));
+        assert!(lines.contains(&"SUMMARY:•"));
+        assert!(!ics.to_lowercase().contains("period"));
+
+        let folded = fold(&"é".repeat(50));
+        assert!(folded.split("\r\n").all(|l| l.len() <= ICAL_LINE_OCTETS));
+    }
+
+    #[test]
+    fn formulas_neutralised() {
+        let table = Table {
+            delimiter: ',',
+            date_format: DEFAULT_CSV_DATE_FORMAT,
+        };
+        assert_eq!(table.field("=1+1"), "'=1+1");
+        assert_eq!(table.field("a,b"), "\"a,b\"");
+    }
+}
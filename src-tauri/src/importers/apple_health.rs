@@ -0,0 +1,223 @@
+use super::{iso_day, Parsed};
+use crate::models::*;
+use crate::symptoms;
+use crate::validation::{self, ValidationErrors, Validator};
+
+const TYPE_PREFIX: &str = "HKCategoryTypeIdentifier";
+
+/// Health symptom types that have a symptom here. Their values carry a
+/// severity.
+const SYMPTOMS: &[(&str, &str)] = &[
+    ("HKCategoryTypeIdentifierAbdominalCramps", symptoms::CRAMPS),
+    ("HKCategoryTypeIdentifierHeadache", symptoms::HEADACHE),
+    (
+        "HKCategoryTypeIdentifierBreastPain",
+        symptoms::BREAST_TENDERNESS,
+    ),
+    ("HKCategoryTypeIdentifierFatigue", symptoms::FATIGUE),
+    ("HKCategoryTypeIdentifierBloating", symptoms::BLOATING),
+    ("HKCategoryTypeIdentifierAcne", symptoms::ACNE),
+    ("HKCategoryTypeIdentifierHotFlashes", symptoms::HOT_FLASH),
+    ("HKCategoryTypeIdentifierMoodChanges", symptoms::MOOD_LOW),
+];
+
+/// Cycle tracking types there is no equivalent for here, reported so the
+/// user knows they weren't brought over. Everything else in the export
+/// (steps, heart rate, ...) is ignored without mention.
+const UNSUPPORTED: &[&str] = &[
+    "HKCategoryTypeIdentifierCervicalMucusQuality",
+    "HKCategoryTypeIdentifierSexualActivity",
+    "HKCategoryTypeIdentifierPregnancyTestResult",
+    "HKCategoryTypeIdentifierProgesteroneTestResult",
+    "HKCategoryTypeIdentifierContraceptive",
+    "HKCategoryTypeIdentifierLactation",
+    "HKCategoryTypeIdentifierPregnancy",
+];
+
+/// Days from an Apple Health `export.xml`. Every sample is a `<Record>`
+/// element whose attributes say what it is, when, and its value.
+pub fn import(content: &str) -> Result<Parsed, ValidationErrors> {
+    let mut v = Validator::new();
+    if !content.contains("<HealthData") {
+        v.error("content", "is not an Apple Health export.xml");
+    }
+    v.finish()?;
+
+    let mut parsed = Parsed::default();
+    let mut v = Validator::new();
+    for (i, record) in records(content).enumerate() {
+        let attribute = |name: &str| {
+            record
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map_or("", |(_, value)| *value)
+        };
+        let kind = attribute("type");
+        let value = attribute("value");
+        let relevant = kind == "HKCategoryTypeIdentifierMenstrualFlow"
+            || kind == "HKCategoryTypeIdentifierIntermenstrualBleeding"
+            || kind == "HKQuantityTypeIdentifierBasalBodyTemperature"
+            || kind == "HKCategoryTypeIdentifierOvulationTestResult"
+            || SYMPTOMS.iter().any(|(t, _)| *t == kind)
+            || UNSUPPORTED.contains(&kind);
+        if !relevant {
+            continue;
+        }
+        let field = |name: &str| format!("records[{i}].{name}");
+        let Some(date) = iso_day(&mut v, &field("startDate"), attribute("startDate")) else {
+            continue;
+        };
+        let mut day = ImportedDay {
+            date,
+            ..ImportedDay::default()
+        };
+
+        match kind {
+            "HKCategoryTypeIdentifierMenstrualFlow" => {
+                day.flow_level = match value.trim_start_matches("HKCategoryValueMenstrualFlow") {
+                    "None" => Some(FlowLevel::None),
+                    "Light" => Some(FlowLevel::Light),
+                    "Heavy" => Some(FlowLevel::Heavy),
+                    // Flow logged without saying how much
+                    "Medium" | "Unspecified" => Some(FlowLevel::Medium),
+                    _ => None,
+                };
+            }
+            "HKCategoryTypeIdentifierIntermenstrualBleeding" => {
+                day.flow_level = Some(FlowLevel::Light);
+            }
+            "HKQuantityTypeIdentifierBasalBodyTemperature" => {
+                let unit = match attribute("unit") {
+                    "degF" => TemperatureUnit::Fahrenheit,
+                    _ => TemperatureUnit::Celsius,
+                };
+                match value.parse::<f32>() {
+                    Ok(t) if t.is_finite() => {
+                        let celsius = unit.to_celsius(t);
+                        if v.in_range(
+                            &field("value"),
+                            celsius,
+                            validation::MIN_BBT_CELSIUS,
+                            validation::MAX_BBT_CELSIUS,
+                        ) {
+                            day.bbt = Some(celsius);
+                        }
+                    }
+                    _ => v.error(&field("value"), "must be a number"),
+                }
+            }
+            "HKCategoryTypeIdentifierOvulationTestResult" => {
+                day.lh_test = match value.trim_start_matches("HKCategoryValueOvulationTestResult") {
+                    "Negative" => Some(LhTestResult::Negative),
+                    "Positive" | "LuteinizingHormoneSurge" => Some(LhTestResult::Positive),
+                    _ => None,
+                };
+            }
+            _ => {
+                if let Some(&(_, key)) = SYMPTOMS.iter().find(|(t, _)| *t == kind) {
+                    if let Some(severity) = severity(value) {
+                        day.symptoms.push((key.to_string(), severity));
+                    }
+                }
+            }
+        }
+
+        let mapped = day.flow_level.is_some()
+            || day.bbt.is_some()
+            || day.lh_test.is_some()
+            || !day.symptoms.is_empty();
+        if mapped {
+            parsed.days.push(day);
+        } else if UNSUPPORTED.contains(&kind) || !value.contains("NotPresent") {
+            let kind = kind.trim_start_matches(TYPE_PREFIX);
+            let value = value
+                .trim_start_matches("HKCategoryValue")
+                .trim_start_matches(kind);
+            parsed.unmapped(kind, value);
+        }
+    }
+    parsed.errors = v.into_errors();
+    Ok(parsed)
+}
+
+/// 1 to 3 from a Health symptom severity; `None` when marked not present.
+fn severity(value: &str) -> Option<u8> {
+    match value.trim_start_matches("HKCategoryValueSeverity") {
+        "NotPresent" => None,
+        "Moderate" => Some(2),
+        "Severe" => Some(3),
+        // Mild, or present without a severity
+        _ => Some(1),
+    }
+}
+
+/// The attributes of every `<Record ...>` element, in document order.
+fn records(content: &str) -> impl Iterator<Item = Vec<(&str, &str)>> {
+    content.split("<Record ").skip(1).map(|rest| {
+        let end = rest.find('>').unwrap_or(rest.len());
+        attributes(&rest[..end])
+    })
+}
+
+/// `name="value"` pairs from the inside of a start tag.
+fn attributes(tag: &str) -> Vec<(&str, &str)> {
+    let mut attributes = Vec::new();
+    let mut rest = tag;
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq].trim();
+        let after = rest[eq + 1..].trim_start();
+        let Some(quote) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            break;
+        };
+        let Some(close) = after[1..].find(quote) else {
+            break;
+        };
+        attributes.push((name, &after[1..close + 1]));
+        rest = &after[close + 2..];
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn health_records_mapped() {
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<HealthData locale="en_GB">
+ <Record type="HKQuantityTypeIdentifierStepCount" unit="count" startDate="2026-01-04 08:00:00 +0000" value="120"/>
+ <Record type="HKCategoryTypeIdentifierMenstrualFlow" sourceName="Health" startDate="2026-01-04 00:00:00 +0000" endDate="2026-01-04 00:00:00 +0000" value="HKCategoryValueMenstrualFlowHeavy">
+  <MetadataEntry key="HKMenstrualCycleStart" value="1"/>
+ </Record>
+ <Record type="HKQuantityTypeIdentifierBasalBodyTemperature" unit="degF" startDate="2026-01-05 06:30:00 +0000" value="97.7"/>
+ <Record type="HKCategoryTypeIdentifierAbdominalCramps" startDate="2026-01-05 09:00:00 +0000" value="HKCategoryValueSeverityModerate"/>
+ <Record type="HKCategoryTypeIdentifierOvulationTestResult" startDate="2026-01-17 09:00:00 +0000" value="HKCategoryValueOvulationTestResultLuteinizingHormoneSurge"/>
+ <Record type="HKCategoryTypeIdentifierCervicalMucusQuality" startDate="2026-01-16 09:00:00 +0000" value="HKCategoryValueCervicalMucusQualityEggWhite"/>
+</HealthData>"#;
+        let parsed = import(content).unwrap();
+
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.days.len(), 4);
+        assert_eq!(parsed.days[0].flow_level, Some(FlowLevel::Heavy));
+        assert!((parsed.days[1].bbt.unwrap() - 36.5).abs() < 0.01);
+        assert_eq!(parsed.days[2].symptoms, [("cramps".to_string(), 2)]);
+        assert_eq!(parsed.days[3].lh_test, Some(LhTestResult::Positive));
+        let unmapped: Vec<&str> = parsed.unmapped.keys().map(String::as_str).collect();
+        assert_eq!(unmapped, ["CervicalMucusQuality: EggWhite"]);
+
+        assert!(import("<html></html>").is_err());
+    }
+
+    #[test]
+    fn rejected_temperatures_left_out() {
+        let content = r#"<HealthData>
+ <Record type="HKQuantityTypeIdentifierBasalBodyTemperature" unit="degC" startDate="2026-01-05 06:30:00 +0000" value="NaN"/>
+ <Record type="HKQuantityTypeIdentifierBasalBodyTemperature" unit="degF" startDate="2026-01-06 06:30:00 +0000" value="36.6"/>
+</HealthData>"#;
+        let parsed = import(content).unwrap();
+
+        assert_eq!(parsed.errors.len(), 2);
+        assert!(parsed.days.iter().all(|d| d.bbt.is_none()));
+    }
+}
@@ -0,0 +1,124 @@
+use serde_json::Value;
+
+use super::{iso_day, Parsed};
+use crate::models::*;
+use crate::symptoms;
+use crate::validation::{ValidationErrors, Validator};
+
+/// Clue `(category, value)` pairs that have a symptom here. Clue records
+/// no severity, so they are logged at the mildest.
+const SYMPTOMS: &[(&str, &str, &str)] = &[
+    ("pain", "cramps", symptoms::CRAMPS),
+    ("pain", "headache", symptoms::HEADACHE),
+    ("pain", "tender_breasts", symptoms::BREAST_TENDERNESS),
+    ("emotion", "sad", symptoms::MOOD_LOW),
+    ("emotion", "happy", symptoms::MOOD_HIGH),
+    ("energy", "exhausted", symptoms::FATIGUE),
+    ("energy", "low_energy", symptoms::FATIGUE),
+    ("skin", "acne", symptoms::ACNE),
+    ("digestion", "bloated", symptoms::BLOATING),
+];
+
+/// Days from a Clue export: a JSON object whose `data` holds one entry per
+/// day, each with an ISO `day` and its categories as a value or a list of
+/// values.
+pub fn import(content: &str) -> Result<Parsed, ValidationErrors> {
+    let mut v = Validator::new();
+    let export: Value = match serde_json::from_str(content) {
+        Ok(export) => export,
+        Err(e) => {
+            v.error("content", format!("is not a Clue export: {e}"));
+            Value::Null
+        }
+    };
+    let entries = export
+        .get("data")
+        .or(Some(&export))
+        .and_then(Value::as_array);
+    if entries.is_none() && !export.is_null() {
+        v.error("content", "has no list of days");
+    }
+    v.finish()?;
+
+    let mut parsed = Parsed::default();
+    let mut v = Validator::new();
+    for (i, entry) in entries.into_iter().flatten().enumerate() {
+        let day = entry.get("day").and_then(Value::as_str).unwrap_or("");
+        let Some(date) = iso_day(&mut v, &format!("data[{i}].day"), day) else {
+            continue;
+        };
+        let mut imported = ImportedDay {
+            date,
+            ..ImportedDay::default()
+        };
+        for (category, values) in entry.as_object().into_iter().flatten() {
+            if category == "day" {
+                continue;
+            }
+            for value in values
+                .as_array()
+                .map_or(vec![values], |a| a.iter().collect())
+            {
+                let value = value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), str::to_string);
+                if category == "period" {
+                    match flow_level(&value) {
+                        Some(level) => imported.flow_level = Some(level),
+                        None => parsed.unmapped(category, &value),
+                    }
+                    continue;
+                }
+                match SYMPTOMS
+                    .iter()
+                    .find(|(c, v, _)| c == category && *v == value)
+                {
+                    Some(&(_, _, key)) if !imported.symptoms.iter().any(|(k, _)| k == key) => {
+                        imported.symptoms.push((key.to_string(), 1));
+                    }
+                    Some(_) => {}
+                    None => parsed.unmapped(category, &value),
+                }
+            }
+        }
+        parsed.days.push(imported);
+    }
+    parsed.errors = v.into_errors();
+    Ok(parsed)
+}
+
+fn flow_level(value: &str) -> Option<FlowLevel> {
+    match value {
+        "spotting" | "light" => Some(FlowLevel::Light),
+        "medium" => Some(FlowLevel::Medium),
+        "heavy" => Some(FlowLevel::Heavy),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clue_days_mapped() {
+        let content = r#"{"data": [
+            {"day": "2026-01-04T00:00:00Z", "period": "heavy", "pain": ["cramps", "ovulation_pain"]},
+            {"day": "2026-01-05T00:00:00Z", "period": "spotting", "fluid": "creamy",
+             "energy": ["exhausted", "low_energy"]},
+            {"day": "someday", "period": "light"}
+        ]}"#;
+        let parsed = import(content).unwrap();
+
+        assert_eq!(parsed.days.len(), 2);
+        assert_eq!(parsed.days[0].flow_level, Some(FlowLevel::Heavy));
+        assert_eq!(parsed.days[0].symptoms, [("cramps".to_string(), 1)]);
+        assert_eq!(parsed.days[1].flow_level, Some(FlowLevel::Light));
+        assert_eq!(parsed.days[1].symptoms, [("fatigue".to_string(), 1)]);
+        let unmapped: Vec<&str> = parsed.unmapped.keys().map(String::as_str).collect();
+        assert_eq!(unmapped, ["fluid: creamy", "pain: ovulation_pain"]);
+        assert_eq!(parsed.errors[0].field, "data[2].day");
+
+        assert!(import("not json").is_err());
+    }
+}
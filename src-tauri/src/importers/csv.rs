@@ -0,0 +1,247 @@
+use super::Parsed;
+use crate::models::*;
+use crate::symptoms;
+use crate::validation::{self, ValidationErrors, Validator};
+
+/// Day rows from a spreadsheet, read through the caller's column mapping.
+/// Problems with single cells are returned alongside the rows that parsed;
+/// a mapping that doesn't fit the file fails the whole import.
+pub fn import(
+    content: &str,
+    mapping: &CsvMapping,
+    registry: &[SymptomDescriptor],
+    unit: TemperatureUnit,
+) -> Result<Parsed, ValidationErrors> {
+    let delimiter = mapping.delimiter.unwrap_or(',');
+    let date_format = mapping.date_format.as_deref().unwrap_or("%Y-%m-%d");
+
+    let mut v = Validator::new();
+    v.date_format("mapping.date_format", date_format);
+    let records = match records(content, delimiter) {
+        Ok(records) => records,
+        Err(message) => {
+            v.error("content", message);
+            Vec::new()
+        }
+    };
+    let header = records.first().cloned().unwrap_or_default();
+    let column = |v: &mut Validator, field: &str, name: &str| {
+        let index = header.iter().position(|h| h.trim() == name.trim());
+        if index.is_none() {
+            v.error(field, format!("no column named {name:?}"));
+        }
+        index
+    };
+    let date_column = column(&mut v, "mapping.date_column", &mapping.date_column);
+    let flow_column = mapping
+        .flow_column
+        .as_ref()
+        .and_then(|c| column(&mut v, "mapping.flow_column", c));
+    let notes_column = mapping
+        .notes_column
+        .as_ref()
+        .and_then(|c| column(&mut v, "mapping.notes_column", c));
+    let bbt_column = mapping
+        .bbt_column
+        .as_ref()
+        .and_then(|c| column(&mut v, "mapping.bbt_column", c));
+    let mut symptom_columns = Vec::new();
+    for (name, key) in &mapping.symptom_columns {
+        let field = format!("mapping.symptom_columns.{name}");
+        let index = column(&mut v, &field, name);
+        let descriptor = symptoms::resolve(registry, key);
+        if descriptor.is_none() {
+            v.error(&field, "unknown symptom");
+        }
+        if let (Some(index), Some(descriptor)) = (index, descriptor) {
+            symptom_columns.push((index, descriptor));
+        }
+    }
+    v.finish()?;
+    let date_column = date_column.expect("validated above");
+
+    let mut v = Validator::new();
+    let mut days = Vec::new();
+    for (i, record) in records.iter().enumerate().skip(1) {
+        if record.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        let cell = |index: usize| record.get(index).map_or("", |c| c.trim());
+        let field = |name: &str| format!("rows[{i}].{name}");
+
+        let Some(date) =
+            v.past_date_as(&field(&mapping.date_column), cell(date_column), date_format)
+        else {
+            continue;
+        };
+        let mut day = ImportedDay {
+            date,
+            ..ImportedDay::default()
+        };
+        if let Some(index) = flow_column {
+            let value = cell(index);
+            if !value.is_empty() {
+                day.flow_level = flow_level(value, mapping);
+                if day.flow_level.is_none() {
+                    v.error(&field(&header[index]), "unrecognised flow level");
+                }
+            }
+        }
+        if let Some(index) = notes_column {
+            let notes = cell(index);
+            let fits = v.max_chars(&field(&header[index]), notes, validation::MAX_NOTE_CHARS);
+            if fits && !notes.is_empty() {
+                day.notes = Some(notes.to_string());
+            }
+        }
+        if let Some(index) = bbt_column {
+            let value = cell(index).replace(',', ".");
+            if !value.is_empty() {
+                match value.parse::<f32>() {
+                    Ok(t) if t.is_finite() => {
+                        let celsius = unit.to_celsius(t);
+                        if v.in_range(
+                            &field(&header[index]),
+                            celsius,
+                            validation::MIN_BBT_CELSIUS,
+                            validation::MAX_BBT_CELSIUS,
+                        ) {
+                            day.bbt = Some(celsius);
+                        }
+                    }
+                    _ => v.error(&field(&header[index]), "must be a number"),
+                }
+            }
+        }
+        for &(index, descriptor) in &symptom_columns {
+            match severity(cell(index)) {
+                Ok(None) => {}
+                Ok(Some(severity)) if severity <= descriptor.max_severity => {
+                    day.symptoms.push((descriptor.key.clone(), severity));
+                }
+                _ => v.error(
+                    &field(&header[index]),
+                    format!("must be empty, yes/no or 1 to {}", descriptor.max_severity),
+                ),
+            }
+        }
+        days.push(day);
+    }
+    Ok(Parsed {
+        days,
+        errors: v.into_errors(),
+        ..Parsed::default()
+    })
+}
+
+/// The mapping's own value names first, then the usual words and 0-3.
+fn flow_level(value: &str, mapping: &CsvMapping) -> Option<FlowLevel> {
+    if let Some(level) = mapping.flow_values.get(value) {
+        return Some(level.clone());
+    }
+    match value.to_lowercase().as_str() {
+        "none" | "no" | "0" => Some(FlowLevel::None),
+        "light" | "spotting" | "1" => Some(FlowLevel::Light),
+        "medium" | "moderate" | "2" => Some(FlowLevel::Medium),
+        "heavy" | "3" => Some(FlowLevel::Heavy),
+        _ => None,
+    }
+}
+
+/// A symptom cell: empty or "no" for none, a tick for 1, or a number.
+fn severity(value: &str) -> Result<Option<u8>, ()> {
+    match value.to_lowercase().as_str() {
+        "" | "0" | "no" | "false" => Ok(None),
+        "yes" | "true" | "x" => Ok(Some(1)),
+        number => number.parse().map(Some).map_err(|_| ()),
+    }
+}
+
+/// Split CSV text into records, honouring quoted fields with embedded
+/// delimiters, quotes and line breaks.
+pub fn records(content: &str, delimiter: char) -> Result<Vec<Vec<String>>, String> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            c if c == delimiter => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("a quoted field is never closed".to_string());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn quoted_fields_split() {
+        let records = records("a,\"b,\"\"c\"\"\"\r\n\"multi\nline\",d\n", ',').unwrap();
+        assert_eq!(records, [vec!["a", "b,\"c\""], vec!["multi\nline", "d"]]);
+        assert!(super::records("a,\"open", ',').is_err());
+    }
+
+    #[test]
+    fn rows_mapped_with_cell_errors() {
+        let content = "Day;Bleeding;Cramps;Temp\n\
+                       03.01.2026;Heavy;2;36,6\n\
+                       04.01.2026;lots;x;\n\
+                       05.01.2026;;9;\n";
+        let mapping = CsvMapping {
+            delimiter: Some(';'),
+            date_column: "Day".to_string(),
+            date_format: Some("%d.%m.%Y".to_string()),
+            flow_column: Some("Bleeding".to_string()),
+            bbt_column: Some("Temp".to_string()),
+            symptom_columns: [("Cramps".to_string(), "cramps".to_string())].into(),
+            ..CsvMapping::default()
+        };
+        let registry = symptoms::builtin_descriptors();
+        let parsed = import(content, &mapping, &registry, TemperatureUnit::Celsius).unwrap();
+        let (days, errors) = (parsed.days, parsed.errors);
+
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[0].date, NaiveDate::from_ymd_opt(2026, 1, 3).unwrap());
+        assert_eq!(days[0].flow_level, Some(FlowLevel::Heavy));
+        assert_eq!(days[0].bbt, Some(36.6));
+        assert_eq!(days[1].symptoms, [("cramps".to_string(), 1)]);
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["rows[2].Bleeding", "rows[3].Cramps"]);
+
+        let unmapped = CsvMapping {
+            date_column: "Date".to_string(),
+            ..mapping
+        };
+        assert!(import(content, &unmapped, &registry, TemperatureUnit::Celsius).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use super::{csv, Parsed};
+use crate::models::*;
+use crate::symptoms;
+use crate::validation::{self, ValidationErrors, Validator};
+
+/// drip. `pain.*` and `mood.*` columns that have a symptom here, logged at
+/// the mildest severity when the column is `true`.
+const SYMPTOMS: &[(&str, &str)] = &[
+    ("pain.cramps", symptoms::CRAMPS),
+    ("pain.headache", symptoms::HEADACHE),
+    ("pain.migraine", symptoms::HEADACHE),
+    ("pain.tenderBreasts", symptoms::BREAST_TENDERNESS),
+    ("mood.sad", symptoms::MOOD_LOW),
+    ("mood.happy", symptoms::MOOD_HIGH),
+    ("mood.fatigue", symptoms::FATIGUE),
+];
+
+/// Days from a drip. CSV export: one row per date, with columns such as
+/// `bleeding.value` (0 spotting to 3 heavy), `temperature.value` in
+/// Celsius, `cervix.*`, `desire.value` (0 low to 2 high), `note.value` and
+/// true/false `pain.*` and `mood.*` flags. Temperatures the user excluded
+/// in drip. are left out.
+pub fn import(content: &str) -> Result<Parsed, ValidationErrors> {
+    let mut v = Validator::new();
+    let records = csv::records(content, ',').unwrap_or_else(|message| {
+        v.error("content", message);
+        Vec::new()
+    });
+    let header = records.first().cloned().unwrap_or_default();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    if column("date").is_none() && !records.is_empty() {
+        v.error("content", "has no date column; is this a drip. export?");
+    }
+    v.finish()?;
+
+    let mut parsed = Parsed::default();
+    let mut v = Validator::new();
+    for (i, record) in records.iter().enumerate().skip(1) {
+        let cell = |name: &str| {
+            column(name)
+                .and_then(|c| record.get(c))
+                .map_or("", |c| c.trim())
+        };
+        let field = |name: &str| format!("rows[{i}].{name}");
+        let Some(date) = v.past_date(&field("date"), cell("date")) else {
+            continue;
+        };
+        let mut day = ImportedDay {
+            date,
+            ..ImportedDay::default()
+        };
+
+        if cell("bleeding.exclude") != "true" {
+            day.flow_level = match cell("bleeding.value") {
+                "" => None,
+                "0" | "1" => Some(FlowLevel::Light),
+                "2" => Some(FlowLevel::Medium),
+                "3" => Some(FlowLevel::Heavy),
+                _ => {
+                    v.error(&field("bleeding.value"), "must be 0 to 3");
+                    None
+                }
+            };
+        }
+        match (cell("temperature.value"), cell("temperature.exclude")) {
+            ("", _) => {}
+            (_, "true") => parsed.unmapped("temperature", "excluded"),
+            (value, _) => match value.parse::<f32>() {
+                Ok(t) if t.is_finite() => {
+                    if v.in_range(
+                        &field("temperature.value"),
+                        t,
+                        validation::MIN_BBT_CELSIUS,
+                        validation::MAX_BBT_CELSIUS,
+                    ) {
+                        day.bbt = Some(t);
+                    }
+                }
+                _ => v.error(&field("temperature.value"), "must be a number"),
+            },
+        }
+        day.cervix = cervix(&cell);
+        day.libido = match cell("desire.value") {
+            "0" => Some(1),
+            "1" => Some(2),
+            "2" => Some(3),
+            _ => None,
+        };
+        let note = cell("note.value");
+        if !note.is_empty() && v.max_chars(&field("note.value"), note, validation::MAX_NOTE_CHARS) {
+            day.notes = Some(note.to_string());
+        }
+        if !cell("mucus.value").is_empty() {
+            parsed.unmapped("mucus", cell("mucus.value"));
+        }
+
+        for (name, value) in header.iter().zip(record) {
+            let flagged =
+                (name.starts_with("pain.") || name.starts_with("mood.")) && value.trim() == "true";
+            if !flagged {
+                continue;
+            }
+            match SYMPTOMS.iter().find(|(column, _)| column == name) {
+                Some(&(_, key)) if !day.symptoms.iter().any(|(k, _)| k == key) => {
+                    day.symptoms.push((key.to_string(), 1));
+                }
+                Some(_) => {}
+                None => {
+                    let (category, value) = name.split_once('.').unwrap_or((name, ""));
+                    parsed.unmapped(category, value);
+                }
+            }
+        }
+        parsed.days.push(day);
+    }
+    parsed.errors = v.into_errors();
+    Ok(parsed)
+}
+
+/// A cervix observation, when drip. has all three parts and the user
+/// didn't exclude it.
+fn cervix<'a>(cell: &impl Fn(&str) -> &'a str) -> Option<CervixObservation> {
+    if cell("cervix.exclude") == "true" {
+        return None;
+    }
+    let openness = match cell("cervix.opening") {
+        "0" => CervixOpenness::Closed,
+        "1" => CervixOpenness::PartlyOpen,
+        "2" => CervixOpenness::Open,
+        _ => return None,
+    };
+    let firmness = match cell("cervix.firmness") {
+        "0" => CervixFirmness::Firm,
+        "1" => CervixFirmness::Soft,
+        _ => return None,
+    };
+    let height = match cell("cervix.position") {
+        "0" => CervixHeight::Low,
+        "1" => CervixHeight::Medium,
+        "2" => CervixHeight::High,
+        _ => return None,
+    };
+    Some(CervixObservation {
+        height,
+        firmness,
+        openness,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drip_rows_mapped() {
+        let content = "date,temperature.value,temperature.exclude,bleeding.value,bleeding.exclude,\
+                       mucus.value,cervix.opening,cervix.firmness,cervix.position,cervix.exclude,\
+                       note.value,desire.value,pain.cramps,pain.nausea,mood.sad\n\
+                       2026-01-04,36.45,false,3,false,,,,,,\"tired, cold\",2,true,true,false\n\
+                       2026-01-18,36.9,true,,,2,2,1,2,false,,,false,false,true\n";
+        let parsed = import(content).unwrap();
+
+        assert!(parsed.errors.is_empty());
+        let [first, second] = &parsed.days[..] else {
+            panic!("expected two days");
+        };
+        assert_eq!(first.flow_level, Some(FlowLevel::Heavy));
+        assert_eq!(first.bbt, Some(36.45));
+        assert_eq!(first.notes.as_deref(), Some("tired, cold"));
+        assert_eq!(first.libido, Some(3));
+        assert_eq!(first.symptoms, [("cramps".to_string(), 1)]);
+        assert_eq!(second.bbt, None);
+        assert_eq!(
+            second.cervix.as_ref().map(|c| c.height),
+            Some(CervixHeight::High)
+        );
+        assert_eq!(second.symptoms, [("mood_low".to_string(), 1)]);
+        let unmapped: Vec<&str> = parsed.unmapped.keys().map(String::as_str).collect();
+        assert_eq!(
+            unmapped,
+            ["mucus: 2", "pain: nausea", "temperature: excluded"]
+        );
+    }
+
+    #[test]
+    fn rejected_cells_left_out() {
+        let content = format!(
+            "date,temperature.value,note.value\n2026-01-04,inf,\n2026-01-05,98.2,{}\n",
+            "x".repeat(validation::MAX_NOTE_CHARS + 1)
+        );
+        let parsed = import(&content).unwrap();
+
+        assert_eq!(parsed.errors.len(), 3);
+        assert!(parsed
+            .days
+            .iter()
+            .all(|d| d.bbt.is_none() && d.notes.is_none()));
+    }
+}
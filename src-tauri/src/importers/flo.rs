@@ -0,0 +1,140 @@
+use serde_json::Value;
+
+use super::{iso_day, Parsed};
+use crate::models::*;
+use crate::symptoms;
+use crate::validation::{ValidationErrors, Validator};
+
+/// Flo `(category, subcategory)` pairs that have a symptom here, logged at
+/// the mildest severity as Flo records none.
+const SYMPTOMS: &[(&str, &str, &str)] = &[
+    ("Symptom", "Cramps", symptoms::CRAMPS),
+    ("Symptom", "Headache", symptoms::HEADACHE),
+    ("Symptom", "TenderBreasts", symptoms::BREAST_TENDERNESS),
+    ("Symptom", "Fatigue", symptoms::FATIGUE),
+    ("Symptom", "Bloating", symptoms::BLOATING),
+    ("Symptom", "Acne", symptoms::ACNE),
+    ("Mood", "Sad", symptoms::MOOD_LOW),
+    ("Mood", "Depressed", symptoms::MOOD_LOW),
+    ("Mood", "Happy", symptoms::MOOD_HIGH),
+];
+
+/// Days from the JSON data file in a Flo export archive. Periods come from
+/// `operationalData.cycles`; logged events from
+/// `operationalData.point_events_manual_v2`. Period days without a logged
+/// intensity are imported as medium flow.
+pub fn import(content: &str) -> Result<Parsed, ValidationErrors> {
+    let mut v = Validator::new();
+    let export: Value = serde_json::from_str(content).unwrap_or_else(|e| {
+        v.error("content", format!("is not a Flo export: {e}"));
+        Value::Null
+    });
+    let data = export.get("operationalData");
+    if data.is_none() && !export.is_null() {
+        v.error("content", "has no operationalData");
+    }
+    v.finish()?;
+    let list = |name| {
+        data.and_then(|d| d.get(name))
+            .and_then(Value::as_array)
+            .map_or(&[][..], Vec::as_slice)
+    };
+
+    let mut parsed = Parsed::default();
+    let mut v = Validator::new();
+    for (i, cycle) in list("cycles").iter().enumerate() {
+        let field = |name| format!("cycles[{i}].{name}");
+        let start = iso_day(
+            &mut v,
+            &field("period_start_date"),
+            text(cycle, "period_start_date"),
+        );
+        let end = iso_day(
+            &mut v,
+            &field("period_end_date"),
+            text(cycle, "period_end_date"),
+        );
+        let (Some(start), Some(end)) = (start, end) else {
+            continue;
+        };
+        for date in start.iter_days().take_while(|&d| d <= end) {
+            parsed.days.push(ImportedDay {
+                date,
+                flow_level: Some(FlowLevel::Medium),
+                ..ImportedDay::default()
+            });
+        }
+    }
+
+    // Events come after the periods so a logged intensity wins when
+    // duplicates are merged
+    for (i, event) in list("point_events_manual_v2").iter().enumerate() {
+        let Some(date) = iso_day(
+            &mut v,
+            &format!("point_events_manual_v2[{i}].date"),
+            text(event, "date"),
+        ) else {
+            continue;
+        };
+        let (category, subcategory) = (text(event, "category"), text(event, "subcategory"));
+        let mut day = ImportedDay {
+            date,
+            ..ImportedDay::default()
+        };
+        if category == "Period" {
+            day.flow_level = flow_level(subcategory);
+        } else if let Some(&(_, _, key)) = SYMPTOMS
+            .iter()
+            .find(|(c, s, _)| *c == category && *s == subcategory)
+        {
+            day.symptoms.push((key.to_string(), 1));
+        }
+        if day.flow_level.is_none() && day.symptoms.is_empty() {
+            parsed.unmapped(category, subcategory);
+            continue;
+        }
+        parsed.days.push(day);
+    }
+    parsed.errors = v.into_errors();
+    Ok(parsed)
+}
+
+fn text<'a>(entry: &'a Value, name: &str) -> &'a str {
+    entry.get(name).and_then(Value::as_str).unwrap_or("")
+}
+
+fn flow_level(subcategory: &str) -> Option<FlowLevel> {
+    match subcategory {
+        "Spotting" | "Light" => Some(FlowLevel::Light),
+        "Medium" => Some(FlowLevel::Medium),
+        "Heavy" => Some(FlowLevel::Heavy),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importers::merge_duplicates;
+
+    #[test]
+    fn flo_periods_and_events_mapped() {
+        let content = r#"{"operationalData": {
+            "cycles": [{"period_start_date": "2026-01-04 00:00:00", "period_end_date": "2026-01-06 00:00:00"}],
+            "point_events_manual_v2": [
+                {"date": "2026-01-04 00:00:00", "category": "Period", "subcategory": "Heavy"},
+                {"date": "2026-01-05 00:00:00", "category": "Symptom", "subcategory": "Cramps"},
+                {"date": "2026-01-05 00:00:00", "category": "Sex", "subcategory": "Protected"}
+            ]
+        }}"#;
+        let parsed = import(content).unwrap();
+        let days = merge_duplicates(parsed.days);
+
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[0].flow_level, Some(FlowLevel::Heavy));
+        assert_eq!(days[1].flow_level, Some(FlowLevel::Medium));
+        assert_eq!(days[1].symptoms, [("cramps".to_string(), 1)]);
+        assert_eq!(parsed.unmapped.get("Sex: Protected"), Some(&1));
+        assert!(parsed.errors.is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+pub mod apple_health;
+pub mod clue;
+pub mod csv;
+pub mod drip;
+pub mod flo;
+pub mod temperature;
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use crate::models::*;
+use crate::validation::{FieldError, Validator};
+
+/// What a parser read from a file, before merging.
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub days: Vec<ImportedDay>,
+    /// Values that couldn't be read, by field.
+    pub errors: Vec<FieldError>,
+    /// Values with no equivalent here, as `category: value`, with how often
+    /// each came up.
+    pub unmapped: BTreeMap<String, usize>,
+}
+
+impl Parsed {
+    fn unmapped(&mut self, category: &str, value: &str) {
+        *self
+            .unmapped
+            .entry(format!("{category}: {value}"))
+            .or_default() += 1;
+    }
+}
+
+/// Where an import reads from: a file the backend opens, or text the
+/// frontend already has.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    Path(std::path::PathBuf),
+    Content(String),
+}
+
+impl ImportSource {
+    pub fn read(self) -> std::io::Result<String> {
+        match self {
+            ImportSource::Path(path) => std::fs::read_to_string(path),
+            ImportSource::Content(content) => Ok(content),
+        }
+    }
+}
+
+/// The date part of an ISO date or timestamp, e.g. `2019-02-12T00:00:00Z`
+/// or `2019-02-12 00:00:00`.
+fn iso_day(v: &mut Validator, field: &str, value: &str) -> Option<NaiveDate> {
+    v.past_date(field, value.get(..10).unwrap_or(value))
+}
+
+/// Combine days that appear more than once, later values winning field by
+/// field, and sort by date.
+pub fn merge_duplicates(days: Vec<ImportedDay>) -> Vec<ImportedDay> {
+    let mut merged: BTreeMap<NaiveDate, ImportedDay> = BTreeMap::new();
+    for day in days {
+        match merged.get_mut(&day.date) {
+            Some(existing) => {
+                existing.flow_level = day.flow_level.or(existing.flow_level.take());
+                existing.notes = day.notes.or(existing.notes.take());
+                if day.bbt.is_some() {
+                    existing.bbt = day.bbt;
+                    existing.bbt_source = day.bbt_source;
+                }
+                existing.cervix = day.cervix.or(existing.cervix.take());
+                existing.libido = day.libido.or(existing.libido);
+                existing.lh_test = day.lh_test.or(existing.lh_test);
+                for (symptom_type, severity) in day.symptoms {
+                    match existing
+                        .symptoms
+                        .iter_mut()
+                        .find(|(t, _)| *t == symptom_type)
+                    {
+                        Some(s) => s.1 = severity,
+                        None => existing.symptoms.push((symptom_type, severity)),
+                    }
+                }
+            }
+            None => {
+                merged.insert(day.date, day);
+            }
+        }
+    }
+    merged.into_values().collect()
+}
@@ -0,0 +1,203 @@
+use super::{csv, iso_day, Parsed};
+use crate::models::*;
+use crate::validation::{self, ValidationErrors, Validator};
+
+/// Where an Oura deviation is counted from when nothing's been logged with
+/// a thermometer yet, in Celsius.
+const DEFAULT_BASELINE_CELSIUS: f32 = 36.5;
+/// Readings above this are taken to be Fahrenheit.
+const FAHRENHEIT_ABOVE: f32 = 45.0;
+
+/// Header names each device's export uses, lowercase.
+fn date_columns(device: TemperatureDevice) -> &'static [&'static str] {
+    match device {
+        TemperatureDevice::Oura => &["day", "date", "summary_date"],
+        TemperatureDevice::OtherWearable | TemperatureDevice::BluetoothThermometer => {
+            &["date", "day", "night", "time", "timestamp"]
+        }
+    }
+}
+
+fn temperature_columns(device: TemperatureDevice) -> &'static [&'static str] {
+    match device {
+        TemperatureDevice::Oura => &[
+            "temperature_deviation",
+            "temperature deviation (°c)",
+            "temperature deviation",
+        ],
+        TemperatureDevice::OtherWearable | TemperatureDevice::BluetoothThermometer => &[
+            "temperature",
+            "temperature (°c)",
+            "temperature (°f)",
+            "temp",
+            "bbt",
+        ],
+    }
+}
+
+/// Where Oura's deviations are counted from: the middle of the user's own
+/// thermometer temperatures, so imported nights line up with them on the
+/// chart.
+pub fn baseline(data: &AppData) -> f32 {
+    let mut temps: Vec<f32> = data
+        .day_logs
+        .values()
+        .filter(|l| l.bbt_source == TemperatureSource::Thermometer)
+        .filter_map(|l| l.bbt)
+        .collect();
+    temps.sort_by(f32::total_cmp);
+    temps
+        .get(temps.len() / 2)
+        .copied()
+        .unwrap_or(DEFAULT_BASELINE_CELSIUS)
+}
+
+/// Nightly temperatures from a device's CSV export, one row per night.
+/// Columns are found by the device's usual header names unless given.
+/// Oura's deviations are added to `baseline`; other devices' readings are
+/// Celsius or Fahrenheit, told apart by size. Wearables are marked as such
+/// so the thermal shift is looked for in smoothed values.
+pub fn import(
+    content: &str,
+    device: TemperatureDevice,
+    date_column: Option<&str>,
+    temperature_column: Option<&str>,
+    baseline: f32,
+) -> Result<Parsed, ValidationErrors> {
+    let first_line = content.lines().next().unwrap_or("");
+    let delimiter = if first_line.contains(';') && !first_line.contains(',') {
+        ';'
+    } else {
+        ','
+    };
+
+    let mut v = Validator::new();
+    let records = csv::records(content, delimiter).unwrap_or_else(|message| {
+        v.error("content", message);
+        Vec::new()
+    });
+    let header: Vec<String> = records
+        .first()
+        .map(|h| h.iter().map(|c| c.trim().to_lowercase()).collect())
+        .unwrap_or_default();
+    let mut column = |field: &str, given: Option<&str>, usual: &[&str]| {
+        let index = match given {
+            Some(name) => header.iter().position(|h| *h == name.trim().to_lowercase()),
+            None => usual
+                .iter()
+                .find_map(|n| header.iter().position(|h| h == n)),
+        };
+        if index.is_none() && !records.is_empty() {
+            match given {
+                Some(name) => v.error(field, format!("no column named {name:?}")),
+                None => v.error(field, "not found; name the column to use"),
+            }
+        }
+        index
+    };
+    let date = column("date_column", date_column, date_columns(device));
+    let temperature = column(
+        "temperature_column",
+        temperature_column,
+        temperature_columns(device),
+    );
+    v.finish()?;
+    let (Some(date), Some(temperature)) = (date, temperature) else {
+        return Ok(Parsed::default());
+    };
+
+    let source = match device {
+        TemperatureDevice::Oura | TemperatureDevice::OtherWearable => TemperatureSource::Wearable,
+        TemperatureDevice::BluetoothThermometer => TemperatureSource::Thermometer,
+    };
+    let mut parsed = Parsed::default();
+    let mut v = Validator::new();
+    for (i, record) in records.iter().enumerate().skip(1) {
+        let cell = |c: usize| record.get(c).map_or("", |c| c.trim());
+        // Nights the device didn't record
+        if cell(temperature).is_empty() {
+            continue;
+        }
+        let Some(date) = iso_day(&mut v, &format!("rows[{i}].date"), cell(date)) else {
+            continue;
+        };
+        let field = format!("rows[{i}].temperature");
+        let value = match cell(temperature).replace(',', ".").parse::<f32>() {
+            Ok(value) if value.is_finite() => value,
+            _ => {
+                v.error(&field, "must be a number");
+                continue;
+            }
+        };
+        let celsius = match device {
+            TemperatureDevice::Oura => baseline + value,
+            _ if value > FAHRENHEIT_ABOVE => TemperatureUnit::Fahrenheit.to_celsius(value),
+            _ => value,
+        };
+        if !v.in_range(
+            &field,
+            celsius,
+            validation::MIN_BBT_CELSIUS,
+            validation::MAX_BBT_CELSIUS,
+        ) {
+            continue;
+        }
+        parsed.days.push(ImportedDay {
+            date,
+            bbt: Some(celsius),
+            bbt_source: source,
+            ..ImportedDay::default()
+        });
+    }
+    parsed.errors = v.into_errors();
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oura_deviations_and_thermometer_fahrenheit() {
+        let oura = "day,score,temperature_deviation\n\
+                    2026-01-04,80,-0.12\n\
+                    2026-01-05,78,\n\
+                    2026-01-06,81,0.31\n";
+        let parsed = import(oura, TemperatureDevice::Oura, None, None, 36.4).unwrap();
+        let temps: Vec<(String, f32, TemperatureSource)> = parsed
+            .days
+            .iter()
+            .map(|d| (d.date.to_string(), d.bbt.unwrap(), d.bbt_source))
+            .collect();
+        assert_eq!(temps.len(), 2);
+        assert!((temps[0].1 - 36.28).abs() < 0.001);
+        assert_eq!(temps[1].0, "2026-01-06");
+        assert_eq!(temps[1].2, TemperatureSource::Wearable);
+
+        let thermometer = "Measured;Reading\n2026-01-04 06:45;97,7\n2026-01-05 06:40;hot\n\
+                           2026-01-06 06:40;NaN\n2026-01-07 06:40;112\n";
+        let parsed = import(
+            thermometer,
+            TemperatureDevice::BluetoothThermometer,
+            Some("measured"),
+            Some("Reading"),
+            36.4,
+        )
+        .unwrap();
+        assert_eq!(parsed.days.len(), 1);
+        assert!((parsed.days[0].bbt.unwrap() - 36.5).abs() < 0.001);
+        assert_eq!(parsed.days[0].bbt_source, TemperatureSource::Thermometer);
+        let fields: Vec<&str> = parsed.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "rows[2].temperature",
+                "rows[3].temperature",
+                "rows[4].temperature"
+            ]
+        );
+
+        let err = import(oura, TemperatureDevice::OtherWearable, None, None, 36.4).unwrap_err();
+        assert_eq!(err.0[0].field, "temperature_column");
+    }
+}
@@ -0,0 +1,375 @@
+use chrono::NaiveDate;
+
+use crate::models::*;
+use crate::prediction;
+use crate::symptoms;
+
+/// Bleeding longer than this is flagged.
+const MAX_BLEEDING_DAYS: i64 = 7;
+/// Cycle lengths outside this range are flagged when they keep recurring.
+const MIN_TYPICAL_CYCLE_DAYS: i64 = 21;
+const MAX_TYPICAL_CYCLE_DAYS: i64 = 35;
+/// Only this many recent cycles are checked, so old patterns drop off.
+const RECENT_FLAG_CYCLES: usize = 3;
+/// How many of the recent cycles have to be short (or long) to flag it.
+const REPEATED_CYCLES: usize = 2;
+/// Light bleeding this short, well before the next period is due, is
+/// treated as spotting between periods rather than a period.
+const MAX_SPOTTING_DAYS: i64 = 2;
+/// Cycles the opt-in cycle-and-skin check looks at, and how many of them
+/// need to be complete before it says anything.
+const PATTERN_CYCLES: usize = 6;
+const MIN_PATTERN_CYCLES: usize = 4;
+
+/// Everything worth pointing out to the user today.
+pub fn insights(data: &AppData, today: NaiveDate) -> Vec<Insight> {
+    late_period(data, today).into_iter().collect()
+}
+
+/// Conservative, rule-based flags for patterns worth raising with a
+/// clinician. They only look at the last few cycles.
+pub fn health_flags(data: &AppData, today: NaiveDate) -> Vec<HealthFlag> {
+    let mut sorted: Vec<&Cycle> = data.cycles.iter().collect();
+    sorted.sort_by_key(|c| c.start_date);
+
+    // Split off spotting: short light bleeds that start well before the
+    // next period would be due
+    let mut periods: Vec<&Cycle> = Vec::new();
+    let mut spotting: Vec<NaiveDate> = Vec::new();
+    for cycle in sorted {
+        match periods.last() {
+            Some(last) if is_spotting(data, cycle, last) => spotting.push(cycle.start_date),
+            _ => periods.push(cycle),
+        }
+    }
+    let recent = &periods[periods.len().saturating_sub(RECENT_FLAG_CYCLES)..];
+    let mut flags = Vec::new();
+
+    let longest_bleed = recent
+        .iter()
+        .map(|c| (c.end_date.unwrap_or(today) - c.start_date).num_days() + 1)
+        .max();
+    if let Some(days) = longest_bleed.filter(|&d| d > MAX_BLEEDING_DAYS) {
+        flags.push(HealthFlag {
+            kind: HealthFlagKind::ProlongedBleeding,
+            message: format!(
+                "One of your recent periods lasted {days} days. Bleeding for more than \
+                 {MAX_BLEEDING_DAYS} days has many possible causes and may be worth \
+                 mentioning to a clinician."
+            ),
+        });
+    }
+
+    let lengths: Vec<i64> = periods
+        .windows(2)
+        .map(|w| (w[1].start_date - w[0].start_date).num_days())
+        .rev()
+        .take(RECENT_FLAG_CYCLES)
+        .collect();
+    let regimen = data.settings.contraception_mode != ContraceptionMode::None;
+    let short = lengths
+        .iter()
+        .filter(|&&l| l < MIN_TYPICAL_CYCLE_DAYS)
+        .count();
+    if !regimen && short >= REPEATED_CYCLES {
+        flags.push(HealthFlag {
+            kind: HealthFlagKind::ShortCycles,
+            message: format!(
+                "{short} of your last {} cycles were shorter than {MIN_TYPICAL_CYCLE_DAYS} \
+                 days. Cycle length varies for many reasons; if this continues, it may be \
+                 worth discussing with a clinician.",
+                lengths.len()
+            ),
+        });
+    }
+    let long = lengths
+        .iter()
+        .filter(|&&l| l > MAX_TYPICAL_CYCLE_DAYS)
+        .count();
+    // Long cycles are expected around menopause
+    if !regimen && !data.settings.perimenopause_mode && long >= REPEATED_CYCLES {
+        flags.push(HealthFlag {
+            kind: HealthFlagKind::LongCycles,
+            message: format!(
+                "{long} of your last {} cycles were longer than {MAX_TYPICAL_CYCLE_DAYS} \
+                 days. Cycle length varies for many reasons; if this continues, it may be \
+                 worth discussing with a clinician.",
+                lengths.len()
+            ),
+        });
+    }
+
+    // Spotting in each of the last two gaps between periods
+    let consecutive = periods.len() >= 3
+        && periods[periods.len() - 3..].windows(2).all(|w| {
+            spotting
+                .iter()
+                .any(|&s| s > w[0].start_date && s < w[1].start_date)
+        });
+    if consecutive {
+        flags.push(HealthFlag {
+            kind: HealthFlagKind::IntermenstrualBleeding,
+            message: "You logged bleeding between periods in two cycles in a row. Spotting \
+                      is often harmless, but if it keeps happening it may be worth \
+                      mentioning to a clinician."
+                .to_string(),
+        });
+    }
+
+    if data.settings.pattern_screening && !regimen {
+        flags.extend(cycle_and_skin_pattern(data, &periods));
+    }
+
+    flags
+}
+
+/// Persistently long or irregular cycles together with acne logged in most
+/// of them. The combination is one that clinicians sometimes look into
+/// further, so the wording stays informational and avoids naming anything
+/// as a finding.
+fn cycle_and_skin_pattern(data: &AppData, periods: &[&Cycle]) -> Option<HealthFlag> {
+    let recent = &periods[periods.len().saturating_sub(PATTERN_CYCLES + 1)..];
+    let cycles: Vec<(NaiveDate, NaiveDate)> = recent
+        .windows(2)
+        .map(|w| (w[0].start_date, w[1].start_date))
+        .collect();
+    if cycles.len() < MIN_PATTERN_CYCLES {
+        return None;
+    }
+
+    let long = cycles
+        .iter()
+        .filter(|(start, next)| (*next - *start).num_days() > MAX_TYPICAL_CYCLE_DAYS)
+        .count();
+    let irregular = prediction::cycle_stats(&data.cycles, &data.settings)
+        .irregularity
+        .is_some_and(|i| {
+            matches!(
+                i.band,
+                IrregularityBand::Irregular | IrregularityBand::VeryIrregular
+            )
+        });
+    let with_acne = cycles
+        .iter()
+        .filter(|(start, next)| {
+            data.symptoms
+                .range(*start..*next)
+                .flat_map(|(_, logged)| logged)
+                .any(|s| s.symptom_type == symptoms::ACNE)
+        })
+        .count();
+    if !(long * 2 >= cycles.len() || irregular) || with_acne * 2 < cycles.len() {
+        return None;
+    }
+
+    Some(HealthFlag {
+        kind: HealthFlagKind::CycleAndSkinPattern,
+        message: format!(
+            "Your recent cycles have often been long or irregular, and you logged acne \
+             in {with_acne} of the last {} of them. This is not a diagnosis, and the \
+             combination has many possible explanations, including hormonal conditions \
+             such as PCOS. If it continues, it may be worth a conversation with a \
+             clinician.",
+            cycles.len()
+        ),
+    })
+}
+
+/// Whether `cycle` is spotting rather than the period after `previous`: at
+/// most two days, only light flow, and before the usual minimum cycle
+/// length has passed.
+fn is_spotting(data: &AppData, cycle: &Cycle, previous: &Cycle) -> bool {
+    let Some(end) = cycle.end_date else {
+        return false;
+    };
+    (end - cycle.start_date).num_days() < MAX_SPOTTING_DAYS
+        && (cycle.start_date - previous.start_date).num_days() < MIN_TYPICAL_CYCLE_DAYS
+        && data
+            .day_logs
+            .range(cycle.start_date..=end)
+            .all(|(_, log)| matches!(log.flow_level, FlowLevel::None | FlowLevel::Light))
+}
+
+/// A period that hasn't started although today is further past the
+/// predicted start than the user's cycles usually vary. How far counts as
+/// "further" is set by the sensitivity.
+fn late_period(data: &AppData, today: NaiveDate) -> Option<Insight> {
+    let spreads = match data.settings.late_period_sensitivity {
+        Sensitivity::Off => return None,
+        Sensitivity::Low => 3,
+        Sensitivity::Normal => 2,
+        Sensitivity::High => 1,
+    };
+    if data.settings.contraception_mode != ContraceptionMode::None {
+        return None;
+    }
+
+    let prediction = prediction::upcoming(data, 1).into_iter().next()?;
+    // Started within the window, or early: not late
+    if data
+        .cycles
+        .iter()
+        .any(|c| c.start_date >= prediction.earliest_likely_start)
+    {
+        return None;
+    }
+
+    let spread = (prediction.latest_likely_start - prediction.predicted_start)
+        .num_days()
+        .max(1);
+    let days_late = (today - prediction.predicted_start).num_days();
+    (days_late > spread * spreads).then_some(Insight::LatePeriod {
+        days_late,
+        expected_start: prediction.predicted_start,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn date(d: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap()
+    }
+
+    fn data(sensitivity: Sensitivity) -> AppData {
+        let cycle = |start: &str, end: &str| Cycle {
+            id: Uuid::new_v4(),
+            start_date: date(start),
+            end_date: Some(date(end)),
+            source: CycleSource::Detected,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: None,
+            updated_at: None,
+        };
+        AppData {
+            // 28 and 30 days: next period expected Mar 29, ±1 day
+            cycles: vec![
+                cycle("2026-01-01", "2026-01-05"),
+                cycle("2026-01-29", "2026-02-02"),
+                cycle("2026-02-28", "2026-03-03"),
+            ],
+            settings: AppSettings {
+                late_period_sensitivity: sensitivity,
+                ..AppSettings::default()
+            },
+            ..AppData::default()
+        }
+    }
+
+    #[test]
+    fn late_period_depends_on_sensitivity() {
+        let today = date("2026-03-31");
+        assert!(insights(&data(Sensitivity::Normal), today).is_empty());
+        assert_eq!(
+            insights(&data(Sensitivity::High), today),
+            [Insight::LatePeriod {
+                days_late: 2,
+                expected_start: date("2026-03-29"),
+            }]
+        );
+        assert!(insights(&data(Sensitivity::Off), date("2026-05-01")).is_empty());
+    }
+
+    #[test]
+    fn flags_repeated_short_cycles_and_long_bleeding() {
+        let cycle = |start: &str, end: &str| Cycle {
+            id: Uuid::new_v4(),
+            start_date: date(start),
+            end_date: Some(date(end)),
+            source: CycleSource::Detected,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let mut data = AppData {
+            cycles: vec![
+                cycle("2026-01-01", "2026-01-05"),
+                cycle("2026-01-20", "2026-01-29"),
+                cycle("2026-02-08", "2026-02-12"),
+                cycle("2026-03-08", "2026-03-12"),
+            ],
+            ..AppData::default()
+        };
+        let kinds = |data: &AppData| -> Vec<HealthFlagKind> {
+            health_flags(data, date("2026-03-20"))
+                .iter()
+                .map(|f| f.kind)
+                .collect()
+        };
+        assert_eq!(
+            kinds(&data),
+            [
+                HealthFlagKind::ProlongedBleeding,
+                HealthFlagKind::ShortCycles
+            ]
+        );
+
+        data.cycles = vec![
+            cycle("2026-01-01", "2026-01-05"),
+            cycle("2026-01-29", "2026-02-02"),
+            cycle("2026-02-26", "2026-03-02"),
+        ];
+        assert!(kinds(&data).is_empty());
+    }
+
+    #[test]
+    fn cycle_and_skin_pattern_is_opt_in() {
+        let mut data = AppData::default();
+        let mut start = date("2025-01-01");
+        for length in [40, 38, 45, 29, 41, 0] {
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: start,
+                end_date: Some(start + chrono::Duration::days(4)),
+                source: CycleSource::Detected,
+                include_in_stats: None,
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+            let acne_day = start + chrono::Duration::days(10);
+            data.symptoms.insert(
+                acne_day,
+                vec![Symptom {
+                    date: acne_day,
+                    symptom_type: symptoms::ACNE.to_string(),
+                    severity: 1,
+                    created_at: None,
+                    updated_at: None,
+                }],
+            );
+            start += chrono::Duration::days(length);
+        }
+        let has_pattern = |data: &AppData| {
+            health_flags(data, date("2025-08-01"))
+                .iter()
+                .any(|f| f.kind == HealthFlagKind::CycleAndSkinPattern)
+        };
+        assert!(!has_pattern(&data));
+        data.settings.pattern_screening = true;
+        assert!(has_pattern(&data));
+
+        data.symptoms.clear();
+        assert!(!has_pattern(&data));
+    }
+
+    #[test]
+    fn no_insight_once_period_started() {
+        let mut data = data(Sensitivity::High);
+        data.cycles.push(Cycle {
+            id: Uuid::new_v4(),
+            start_date: date("2026-03-30"),
+            end_date: None,
+            source: CycleSource::Detected,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: None,
+            updated_at: None,
+        });
+        assert!(insights(&data, date("2026-04-03")).is_empty());
+    }
+}
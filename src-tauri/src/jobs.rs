@@ -0,0 +1,47 @@
+//! Changes to the data that run in stages and can be stopped part way, such
+//! as an import merging thousands of days. A job works on the data in place
+//! while holding its lock, and puts it back as it found it if it fails or is
+//! cancelled (see [`crate::progress`]) before the end, so it is applied
+//! whole or not at all.
+
+use crate::error::CommandError;
+use crate::models::AppData;
+
+/// Run `work` on `data`, restoring the snapshot taken beforehand if it
+/// returns an error.
+pub fn run<T>(
+    data: &mut AppData,
+    work: impl FnOnce(&mut AppData) -> Result<T, CommandError>,
+) -> Result<T, CommandError> {
+    let snapshot = data.clone();
+    work(data).inspect_err(|_| *data = snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancelled_job_rolled_back() {
+        let mut data = AppData::default();
+        let result = run(&mut data, |data| {
+            data.settings.cycle_gap_days += 1;
+            Err::<(), _>(CommandError::Cancelled)
+        });
+        assert!(matches!(result, Err(CommandError::Cancelled)));
+        assert_eq!(
+            data.settings.cycle_gap_days,
+            AppData::default().settings.cycle_gap_days
+        );
+
+        run(&mut data, |data| {
+            data.settings.cycle_gap_days += 1;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            data.settings.cycle_gap_days,
+            AppData::default().settings.cycle_gap_days + 1
+        );
+    }
+}
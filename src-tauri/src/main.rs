@@ -1,30 +1,144 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod analytics;
+mod bundle;
 mod commands;
 mod crypto;
+mod error;
+mod export;
+mod importers;
+mod insights;
+mod jobs;
+mod merge;
+mod migrations;
 mod models;
 mod prediction;
+mod progress;
+mod report;
+mod search;
 mod storage;
+mod symptoms;
+mod timeline;
+mod transfer;
+mod undo;
+mod validation;
 
 use commands::AppState;
+use tauri::Manager;
 
 fn main() {
     tauri::Builder::default()
         .manage(AppState::new())
+        .setup(|app| {
+            app.state::<AppState>().attach(app.handle().clone());
+            commands::spawn_auto_lock(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::is_setup,
+            commands::get_app_info,
             commands::setup,
             commands::unlock,
             commands::lock,
+            commands::get_lock_status,
+            commands::keep_unlocked,
             commands::log_day,
+            commands::undo_last_change,
+            commands::quick_log_today,
+            commands::log_day_from_template,
+            commands::backfill_periods,
+            commands::log_pain,
+            commands::log_pill_dose,
+            commands::get_symptom_registry,
+            commands::get_symptom_catalogue,
+            commands::add_custom_symptom,
+            commands::list_symptom_presets,
+            commands::add_symptom_preset,
+            commands::update_symptom_preset,
+            commands::delete_symptom_preset,
+            commands::apply_symptom_preset,
+            commands::list_appointments,
+            commands::add_appointment,
+            commands::update_appointment,
+            commands::delete_appointment,
+            commands::list_lab_results,
+            commands::add_lab_result,
+            commands::update_lab_result,
+            commands::delete_lab_result,
+            commands::confirm_cycle,
+            commands::add_cycle,
+            commands::set_cycle_in_stats,
+            commands::recalculate,
             commands::get_month,
+            commands::get_day,
+            commands::get_range,
+            commands::get_timeline,
+            commands::get_logged_dates,
+            commands::search,
+            commands::get_cycle_snapshot,
+            commands::compare_current_cycle,
+            commands::get_same_cycle_day,
+            commands::get_bbt_chart,
+            commands::get_cycle_overlay,
             commands::get_predictions,
+            commands::get_period_probabilities,
+            commands::get_period_simulation,
             commands::get_stats,
+            commands::get_prediction_accuracy,
+            commands::get_insights,
+            commands::get_health_flags,
+            commands::get_cycle_phase,
+            commands::get_symptom_forecast,
+            commands::get_libido_by_phase,
+            commands::get_symptom_phase_stats,
+            commands::get_symptom_heatmap,
+            commands::get_symptom_correlations,
+            commands::get_flow_profiles,
+            commands::get_severity_trends,
+            commands::get_year_review,
+            commands::get_monthly_symptom_summary,
+            commands::get_series,
             commands::get_settings,
             commands::toggle_fertility,
+            commands::get_conception_likelihood,
+            commands::toggle_trying_to_conceive,
+            commands::toggle_perimenopause,
+            commands::toggle_libido,
+            commands::toggle_health_flags,
+            commands::toggle_pattern_screening,
+            commands::set_contraception_mode,
+            commands::set_temperature_unit,
+            commands::set_prediction_model,
+            commands::set_prediction_history_cycles,
+            commands::set_cycle_gap_days,
+            commands::set_cycle_length_bounds,
+            commands::set_late_period_sensitivity,
             commands::update_settings,
+            commands::patch_settings,
+            commands::add_note_template,
+            commands::update_note_template,
+            commands::delete_note_template,
             commands::export_data,
+            commands::export_csv,
+            commands::export_fhir,
+            commands::export_aggregates,
+            commands::export_ical,
+            commands::generate_report,
+            commands::import_csv,
+            commands::import_clue,
+            commands::import_flo,
+            commands::import_drip,
+            commands::import_apple_health,
+            commands::import_temperatures,
+            commands::create_bundle,
+            commands::restore_bundle,
+            commands::merge_bundle,
+            commands::create_qr_transfer,
+            commands::check_qr_transfer,
+            commands::import_qr_transfer,
+            commands::cancel_operation,
+            commands::set_storage_layout,
             commands::wipe_all_data,
         ])
         .run(tauri::generate_context!())
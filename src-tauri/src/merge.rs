@@ -0,0 +1,273 @@
+//! Combining two copies of the data that were edited apart, e.g. on two
+//! devices sharing a sync folder. The merge is deterministic, so both sides
+//! reach the same records whichever merges first:
+//!
+//! - Records are matched by date, id or symptom type and unioned, so a day
+//!   logged on either side is never lost. Deleting on one side doesn't
+//!   carry over; the record comes back from the other.
+//! - Where both sides have a record, the later `updated_at` wins, falling
+//!   back to comparing the records themselves when that can't decide.
+//! - A day log keeps the losing side's notes and anything the winner left
+//!   empty, rather than dropping them.
+//! - Settings stay as they are on this device.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::models::*;
+
+/// `theirs` merged into `ours`.
+pub fn merge(ours: &AppData, theirs: &AppData) -> (AppData, MergeReport) {
+    let mut merged = ours.clone();
+    let mut conflicting_days = Vec::new();
+
+    for (date, log) in &theirs.day_logs {
+        match merged.day_logs.get(date) {
+            None => {
+                merged.day_logs.insert(*date, log.clone());
+            }
+            Some(mine) if mine != log => {
+                conflicting_days.push(*date);
+                merged.day_logs.insert(*date, merge_day_log(mine, log));
+            }
+            Some(_) => {}
+        }
+    }
+
+    for (date, symptoms) in &theirs.symptoms {
+        let day = merged.symptoms.entry(*date).or_default();
+        for symptom in symptoms {
+            match day
+                .iter_mut()
+                .find(|s| s.symptom_type == symptom.symptom_type)
+            {
+                None => day.push(symptom.clone()),
+                Some(mine) if mine != symptom => {
+                    if !conflicting_days.contains(date) {
+                        conflicting_days.push(*date);
+                    }
+                    *mine = winner(mine, symptom, |s| s.updated_at).clone();
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    for entry in &theirs.pain {
+        if !merged.pain.contains(entry) {
+            merged.pain.push(entry.clone());
+        }
+    }
+    union_by(&mut merged.pill_doses, &theirs.pill_doses, |d| d.date);
+    union_by(&mut merged.appointments, &theirs.appointments, |a| a.id);
+    union_by(&mut merged.lab_results, &theirs.lab_results, |r| r.id);
+    union_by(
+        &mut merged.symptom_registry,
+        &theirs.symptom_registry,
+        |d| d.key.clone(),
+    );
+    union_by(&mut merged.symptom_presets, &theirs.symptom_presets, |p| {
+        p.id
+    });
+    union_by(
+        &mut merged.prediction_history,
+        &theirs.prediction_history,
+        |p| (p.made_on, p.cycle_start),
+    );
+
+    // Detected cycles are re-derived from the merged logs; only the ones
+    // the user entered or confirmed need carrying over, and they take the
+    // place of a detected cycle starting the same day
+    for cycle in theirs
+        .cycles
+        .iter()
+        .filter(|c| c.source != CycleSource::Detected)
+    {
+        match merged
+            .cycles
+            .iter_mut()
+            .find(|c| c.start_date == cycle.start_date)
+        {
+            Some(mine) if mine.source == CycleSource::Detected => *mine = cycle.clone(),
+            Some(mine) => *mine = winner(mine, cycle, |c| c.updated_at).clone(),
+            None => merged.cycles.push(cycle.clone()),
+        }
+    }
+    merged.cycles.sort_by_key(|c| c.start_date);
+    merged.schema_version = ours.schema_version.max(theirs.schema_version);
+
+    conflicting_days.sort();
+    let added = added(&RecordCounts::of(ours), &RecordCounts::of(&merged));
+    (
+        merged,
+        MergeReport {
+            added,
+            conflicting_days,
+        },
+    )
+}
+
+/// The later of two versions of a record by `stamp`, or the greater when
+/// that doesn't tell them apart, so the choice is the same on every device.
+fn winner<'a, T: Serialize>(
+    a: &'a T,
+    b: &'a T,
+    stamp: impl Fn(&T) -> Option<DateTime<Utc>>,
+) -> &'a T {
+    let key = |r: &T| (stamp(r), serde_json::to_string(r).unwrap_or_default());
+    if key(b) > key(a) {
+        b
+    } else {
+        a
+    }
+}
+
+fn merge_day_log(a: &DayLog, b: &DayLog) -> DayLog {
+    let won = winner(a, b, |l| l.updated_at);
+    let lost = if std::ptr::eq(won, a) { b } else { a };
+    let mut log = won.clone();
+
+    if !lost.notes.trim().is_empty() && !log.notes.contains(lost.notes.trim()) {
+        log.notes = if log.notes.trim().is_empty() {
+            lost.notes.clone()
+        } else {
+            format!("{}\n\n{}", log.notes, lost.notes)
+        };
+    }
+    if log.flow_level == FlowLevel::None {
+        log.flow_level = lost.flow_level.clone();
+    }
+    log.cervix = log.cervix.clone().or_else(|| lost.cervix.clone());
+    log.libido = log.libido.or(lost.libido);
+    if log.bbt.is_none() {
+        log.bbt = lost.bbt;
+        log.bbt_source = lost.bbt_source;
+    }
+    log.lh_test = log.lh_test.or(lost.lh_test);
+    for disturbance in &lost.bbt_disturbances {
+        if !log.bbt_disturbances.contains(disturbance) {
+            log.bbt_disturbances.push(*disturbance);
+        }
+    }
+    log.created_at = match (a.created_at, b.created_at) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    log
+}
+
+/// Add `theirs` to `ours` by `key`; where both have one, the greater
+/// version (these records have no timestamps) replaces ours.
+fn union_by<T: Clone + Serialize, K: Ord>(ours: &mut Vec<T>, theirs: &[T], key: impl Fn(&T) -> K) {
+    let mut index: BTreeMap<K, usize> = ours.iter().enumerate().map(|(i, r)| (key(r), i)).collect();
+    for record in theirs {
+        match index.get(&key(record)) {
+            Some(&i) => ours[i] = winner(&ours[i], record, |_| None).clone(),
+            None => {
+                index.insert(key(record), ours.len());
+                ours.push(record.clone());
+            }
+        }
+    }
+}
+
+fn added(before: &RecordCounts, after: &RecordCounts) -> RecordCounts {
+    RecordCounts {
+        cycles: after.cycles.saturating_sub(before.cycles),
+        day_logs: after.day_logs.saturating_sub(before.day_logs),
+        symptoms: after.symptoms.saturating_sub(before.symptoms),
+        pain_entries: after.pain_entries.saturating_sub(before.pain_entries),
+        pill_doses: after.pill_doses.saturating_sub(before.pill_doses),
+        lab_results: after.lab_results.saturating_sub(before.lab_results),
+        appointments: after.appointments.saturating_sub(before.appointments),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn date(d: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap()
+    }
+
+    fn at(time: &str) -> Option<DateTime<Utc>> {
+        Some(DateTime::parse_from_rfc3339(time).unwrap().to_utc())
+    }
+
+    fn log(day: &str, flow_level: FlowLevel, notes: &str, updated: &str) -> DayLog {
+        DayLog {
+            flow_level,
+            notes: notes.to_string(),
+            created_at: at(updated),
+            updated_at: at(updated),
+            ..DayLog::new(date(day))
+        }
+    }
+
+    #[test]
+    fn merge_is_symmetric_and_drops_no_day() {
+        let mut laptop = AppData::default();
+        let mut desktop = AppData::default();
+        let only_laptop = log("2026-01-03", FlowLevel::Light, "", "2026-01-03T08:00:00Z");
+        laptop.day_logs.insert(only_laptop.date, only_laptop);
+        let mut earlier = log(
+            "2026-01-04",
+            FlowLevel::Heavy,
+            "cramps",
+            "2026-01-04T08:00:00Z",
+        );
+        earlier.bbt = Some(36.4);
+        laptop.day_logs.insert(earlier.date, earlier);
+        let later = log(
+            "2026-01-04",
+            FlowLevel::Medium,
+            "better",
+            "2026-01-04T20:00:00Z",
+        );
+        desktop.day_logs.insert(later.date, later);
+
+        let (merged, report) = merge(&laptop, &desktop);
+        let (reversed, _) = merge(&desktop, &laptop);
+        assert_eq!(merged.day_logs, reversed.day_logs);
+
+        assert_eq!(merged.day_logs.len(), 2);
+        let day = &merged.day_logs[&date("2026-01-04")];
+        assert_eq!(day.flow_level, FlowLevel::Medium);
+        assert_eq!(day.notes, "better\n\ncramps");
+        assert_eq!(day.bbt, Some(36.4));
+        assert_eq!(report.conflicting_days, [date("2026-01-04")]);
+        assert_eq!(report.added.day_logs, 0);
+        assert_eq!(merge(&desktop, &laptop).1.added.day_logs, 1);
+    }
+
+    #[test]
+    fn later_edited_cycle_wins() {
+        let cycle = |end: &str, updated: &str| Cycle {
+            id: uuid::Uuid::new_v4(),
+            start_date: date("2026-01-04"),
+            end_date: Some(date(end)),
+            source: CycleSource::Manual,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: at("2026-01-09T08:00:00Z"),
+            updated_at: at(updated),
+        };
+        let laptop = AppData {
+            cycles: vec![cycle("2026-01-09", "2026-01-12T08:00:00Z")],
+            ..AppData::default()
+        };
+        // Shortened on the desktop afterwards
+        let desktop = AppData {
+            cycles: vec![cycle("2026-01-08", "2026-01-14T08:00:00Z")],
+            ..AppData::default()
+        };
+        for merged in [merge(&laptop, &desktop).0, merge(&desktop, &laptop).0] {
+            assert_eq!(merged.cycles.len(), 1);
+            assert_eq!(merged.cycles[0].end_date, Some(date("2026-01-08")));
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::models::AppData;
+use crate::symptoms;
+
+/// Schema version written by this build.
+/// 0: symptoms stored as enum names, no registry.
+/// 1: symptoms stored as registry keys.
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// Bring data loaded from an older file up to the current schema.
+pub fn migrate(data: &mut AppData) {
+    if data.schema_version < 1 {
+        for symptom in data.symptoms.values_mut().flatten() {
+            if let Some(key) = symptoms::key_for_legacy(&symptom.symptom_type) {
+                symptom.symptom_type = key.to_string();
+            }
+        }
+    }
+
+    // New built-ins appear for existing users without touching their own
+    for builtin in symptoms::builtin_descriptors() {
+        if !data.symptom_registry.iter().any(|d| d.key == builtin.key) {
+            data.symptom_registry.push(builtin);
+        }
+    }
+
+    data.schema_version = CURRENT_SCHEMA_VERSION;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Symptom;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn legacy_symptoms_mapped_to_keys() {
+        let json = r#"{
+            "cycles": [],
+            "day_logs": [],
+            "symptoms": [{"date": "2026-01-02", "symptom_type": "MoodLow", "severity": 2}],
+            "settings": {"auto_lock_minutes": 5, "wipe_after_attempts": null}
+        }"#;
+        let mut data: AppData = serde_json::from_str(json).unwrap();
+        migrate(&mut data);
+
+        assert_eq!(data.schema_version, CURRENT_SCHEMA_VERSION);
+        let date = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        assert_eq!(data.symptoms[&date][0].symptom_type, symptoms::MOOD_LOW);
+        assert!(symptoms::resolve(&data.symptom_registry, symptoms::CRAMPS).is_some());
+    }
+
+    #[test]
+    fn migration_is_idempotent() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        let acne = Symptom {
+            date,
+            symptom_type: symptoms::ACNE.to_string(),
+            severity: 1,
+            created_at: None,
+            updated_at: None,
+        };
+        let mut data = AppData {
+            symptoms: [(date, vec![acne])].into(),
+            ..AppData::default()
+        };
+        migrate(&mut data);
+        let registry_len = data.symptom_registry.len();
+        migrate(&mut data);
+
+        assert_eq!(data.symptom_registry.len(), registry_len);
+        assert_eq!(data.symptoms[&date][0].symptom_type, symptoms::ACNE);
+    }
+}
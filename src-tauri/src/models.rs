@@ -1,7 +1,11 @@
-use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::validation::FieldError;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum FlowLevel {
     None,
@@ -10,44 +14,809 @@ pub enum FlowLevel {
     Heavy,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum SymptomCategory {
+    Pain,
+    Mood,
+    Energy,
+    Digestion,
+    Skin,
+    Vasomotor,
+    Other,
+}
+
+/// An entry in the symptom registry. Symptoms are stored by `key`, which
+/// never changes once assigned, so labels and ordering can.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SymptomDescriptor {
+    pub key: String,
+    pub label: String,
+    pub category: SymptomCategory,
+    /// Severity is logged on a 1..=max_severity scale.
+    pub max_severity: u8,
+    pub builtin: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum CervixHeight {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum CervixFirmness {
+    Firm,
+    Medium,
+    Soft,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum CervixOpenness {
+    Closed,
+    PartlyOpen,
+    Open,
+}
+
+/// Cervix self-check, as recorded in the sympto-thermal method.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CervixObservation {
+    pub height: CervixHeight,
+    pub firmness: CervixFirmness,
+    pub openness: CervixOpenness,
+}
+
+/// Result of an ovulation (LH) test strip.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum LhTestResult {
+    Negative,
+    Positive,
+}
+
+/// Body map regions for pain entries.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum PainLocation {
+    LowerAbdomenLeft,
+    LowerAbdomenRight,
+    LowerBack,
+    Thighs,
+    Head,
+    Breasts,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum PainCharacter {
+    Cramping,
+    Aching,
+    Sharp,
+    Throbbing,
+    Burning,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    /// Convert a stored Celsius value into this unit.
+    pub fn convert_celsius(self, celsius: f32) -> f32 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    /// Convert a value in this unit into Celsius for storage.
+    pub fn to_celsius(self, value: f32) -> f32 {
+        match self {
+            TemperatureUnit::Celsius => value,
+            TemperatureUnit::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+        }
+    }
+}
+
+/// Hormonal contraception in use, which changes what bleeding means and
+/// whether calendar-based predictions make sense at all.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub enum ContraceptionMode {
+    #[default]
+    None,
+    /// Combined pill: `active_days` hormone pills followed by `break_days`
+    /// pill-free (or placebo) days, repeating from `pack_start`.
+    Pill {
+        pack_start: NaiveDate,
+        active_days: u32,
+        break_days: u32,
+    },
+    /// Implant, hormonal IUD, injection or progestin-only pill — bleeding
+    /// is not tied to a regimen.
+    Continuous,
+}
+
+/// How the next period is predicted.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum PredictionModel {
+    /// Sympto-thermal when ovulation has been confirmed this cycle;
+    /// otherwise robust when recent cycle lengths vary a lot, mean if not.
+    #[default]
+    Auto,
+    /// Plain mean of the recent cycles.
+    Mean,
+    /// Exponentially weighted toward the most recent cycles, so genuine
+    /// shifts in cycle length show up sooner.
+    Weighted,
+    /// Median, with confidence from the median absolute deviation, so one
+    /// unusually long or short cycle barely moves the prediction.
+    Robust,
+    /// A luteal phase after ovulation confirmed by BBT or LH tests. Falls
+    /// back to the calendar models (as in `Auto`) until ovulation is seen.
+    SymptoThermal,
+}
+
+/// How readily an insight is raised.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum Sensitivity {
+    Off,
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// Something noteworthy in the user's data, phrased by the frontend.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum SymptomType {
-    Cramps,
-    Headache,
-    MoodLow,
-    MoodHigh,
-    Fatigue,
-    Bloating,
-    BreastTenderness,
-    Acne,
+#[serde(tag = "kind")]
+pub enum Insight {
+    /// The period is later than the user's cycles usually vary.
+    LatePeriod {
+        days_late: i64,
+        expected_start: NaiveDate,
+    },
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum HealthFlagKind {
+    ProlongedBleeding,
+    ShortCycles,
+    LongCycles,
+    IntermenstrualBleeding,
+    /// Long or irregular cycles together with frequent acne. Opt-in only.
+    CycleAndSkinPattern,
+}
+
+/// A pattern worth mentioning to a clinician. Never a diagnosis: `message`
+/// is worded for the user as an observation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthFlag {
+    pub kind: HealthFlagKind,
+    pub message: String,
+}
+
+/// What kind of bleeding the stats and predictions describe.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum BleedKind {
+    Period,
+    WithdrawalBleed,
+    Bleeding,
+}
+
+/// Where a cycle's boundaries came from.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum CycleSource {
+    /// Inferred from flow logs; replaced whenever the logs change.
+    #[default]
+    Detected,
+    /// A detected cycle the user has checked and accepted.
+    Confirmed,
+    /// Entered directly by the user, e.g. a period before they started
+    /// logging daily.
+    Manual,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Cycle {
     pub id: Uuid,
     pub start_date: NaiveDate,
     pub end_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub source: CycleSource,
+    /// User override for whether this cycle's length counts towards
+    /// averages; `None` leaves it to outlier detection.
+    #[serde(default)]
+    pub include_in_stats: Option<bool>,
+    /// Likely ovulation day, filled in once the cycle is complete.
+    #[serde(default)]
+    pub ovulation: Option<RecordedOvulation>,
+    /// When the user entered, confirmed or overrode this cycle; `None` for
+    /// cycles only ever detected, and those saved before timestamps were
+    /// tracked.
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Ovulation day recorded on a past cycle, and what it was worked out from.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct RecordedOvulation {
+    pub day: NaiveDate,
+    pub basis: OvulationBasis,
+}
+
+impl Cycle {
+    /// Whether the two cycles share at least one day. An open cycle runs
+    /// indefinitely.
+    pub fn overlaps(&self, other: &Cycle) -> bool {
+        let self_end = self.end_date.unwrap_or(NaiveDate::MAX);
+        let other_end = other.end_date.unwrap_or(NaiveDate::MAX);
+        self.start_date <= other_end && other.start_date <= self_end
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct DayLog {
     pub date: NaiveDate,
     pub flow_level: FlowLevel,
     pub notes: String,
+    #[serde(default)]
+    pub cervix: Option<CervixObservation>,
+    #[serde(default)]
+    pub libido: Option<u8>, // 1-3
+    /// Basal body temperature, always stored in Celsius.
+    #[serde(default)]
+    pub bbt: Option<f32>,
+    #[serde(default)]
+    pub lh_test: Option<LhTestResult>,
+    /// Anything that may have thrown off the day's temperature. Disturbed
+    /// temperatures are charted but left out of the shift rule.
+    #[serde(default)]
+    pub bbt_disturbances: Vec<BbtDisturbance>,
+    #[serde(default)]
+    pub bbt_source: TemperatureSource,
+    /// `None` for records saved before timestamps were tracked.
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl DayLog {
+    /// A log for `date` with nothing recorded yet.
+    pub fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            flow_level: FlowLevel::None,
+            notes: String::new(),
+            cervix: None,
+            libido: None,
+            bbt: None,
+            lh_test: None,
+            bbt_disturbances: Vec::new(),
+            bbt_source: TemperatureSource::Thermometer,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+/// What a temperature was measured with.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum TemperatureSource {
+    /// A reading on waking, typed in or from a thermometer app.
+    #[default]
+    Thermometer,
+    /// Derived from a night of wear by a ring, watch or patch. Noisier from
+    /// day to day, so smoothed before shift detection.
+    Wearable,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum BbtDisturbance {
+    /// Taken well after the usual time.
+    LateMeasurement,
+    Illness,
+    Alcohol,
+    ShortSleep,
+    Travel,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Symptom {
     pub date: NaiveDate,
-    pub symptom_type: SymptomType,
-    pub severity: u8, // 1-3
+    /// Key into the symptom registry.
+    pub symptom_type: String,
+    pub severity: u8,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    /// Only moves when the severity changes, not on every save of the day.
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PainEntry {
+    pub date: NaiveDate,
+    pub location: PainLocation,
+    pub intensity: u8, // 1-3
+    pub character: PainCharacter,
+}
+
+/// Whether the day's pill was taken, logged under a pill regimen.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PillDose {
+    pub date: NaiveDate,
+    pub taken: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum AppointmentType {
+    Checkup,
+    CervicalScreening,
+    Ultrasound,
+    Consultation,
+    FollowUp,
+    Other,
+}
+
+/// A gynecological visit, kept alongside the cycle data it relates to.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Appointment {
+    pub id: Uuid,
+    pub date: NaiveDate,
+    pub appointment_type: AppointmentType,
+    pub notes: String,
+    pub follow_up_date: Option<NaiveDate>,
+}
+
+/// A single lab measurement, e.g. TSH, ferritin or estradiol.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LabResult {
+    pub id: Uuid,
+    pub date: NaiveDate,
+    pub analyte: String,
+    pub value: f64,
+    pub unit: String,
+    pub reference_low: Option<f64>,
+    pub reference_high: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Prediction {
     pub predicted_start: NaiveDate,
     pub predicted_end: NaiveDate,
+    /// Range the start realistically falls in, for shading the calendar.
+    pub earliest_likely_start: NaiveDate,
+    pub latest_likely_start: NaiveDate,
     pub confidence: f32,
+    pub bleed_kind: BleedKind,
+    /// The model that produced this prediction, after any automatic choice;
+    /// `None` when it follows a contraceptive regimen instead.
+    pub model: Option<PredictionModel>,
+}
+
+/// A next-period prediction as it stood when first made, kept to measure
+/// how accurate predictions turn out to be.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PredictionRecord {
+    pub made_on: NaiveDate,
+    /// Start of the cycle that was current when the prediction was made.
+    pub cycle_start: NaiveDate,
+    pub predicted_start: NaiveDate,
+    pub earliest_likely_start: NaiveDate,
+    pub latest_likely_start: NaiveDate,
+    pub confidence: f32,
+    /// Filled in once the next period has started.
+    pub actual_start: Option<NaiveDate>,
+}
+
+impl PredictionRecord {
+    /// Whether the period started inside the likely window.
+    pub fn hit(&self) -> bool {
+        self.actual_start.is_some_and(|actual| {
+            actual >= self.earliest_likely_start && actual <= self.latest_likely_start
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PredictionAccuracy {
+    /// Predictions whose period has arrived.
+    pub samples: usize,
+    pub mean_absolute_error: Option<f32>,
+    /// Mean signed error in days; positive means periods came later than
+    /// predicted.
+    pub bias: Option<f32>,
+    pub calibration: Vec<CalibrationBucket>,
+}
+
+/// Predictions with stated confidence in `[confidence_low, confidence_high)`
+/// and how often their period started inside the likely window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationBucket {
+    pub confidence_low: f32,
+    pub confidence_high: f32,
+    pub predictions: usize,
+    pub hit_rate: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum CyclePhase {
+    Menstrual,
+    Follicular,
+    Ovulatory,
+    Luteal,
+}
+
+/// Where today sits in the cycle, for the tray tooltip and dashboard header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleSnapshot {
+    pub today: NaiveDate,
+    /// 1 on the first day of the current period; `None` before any cycle.
+    pub cycle_day: Option<u32>,
+    /// Negative once the predicted start has passed.
+    pub days_until_next: Option<i64>,
+    pub phase: Option<CyclePhase>,
+}
+
+/// Chance that a period is under way on a given day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayProbability {
+    pub date: NaiveDate,
+    pub probability: f32,
+}
+
+/// Share of simulated runs in which the next period started on a day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartProbability {
+    pub date: NaiveDate,
+    pub probability: f32,
+}
+
+/// Distribution of the next period start from resampling past cycle
+/// lengths. Unlike a prediction's range it needn't be symmetric.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartDistribution {
+    pub runs: u32,
+    pub median_start: NaiveDate,
+    /// The central 80% of simulated starts.
+    pub earliest_likely_start: NaiveDate,
+    pub latest_likely_start: NaiveDate,
+    /// Days with at least one simulated start, in date order.
+    pub days: Vec<StartProbability>,
+}
+
+/// Where a day sits in its cycle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseInfo {
+    pub date: NaiveDate,
+    pub phase: CyclePhase,
+    /// 1 on the first day of the period.
+    pub cycle_day: u32,
+    /// 1 on the first day of `phase`.
+    pub phase_day: u32,
+    /// The ovulation estimate the phases are placed around, when there is one.
+    pub ovulation_day: Option<NaiveDate>,
+    pub ovulation_basis: Option<OvulationBasis>,
+}
+
+/// Estimated chance of conception from intercourse on one day. This is a
+/// population average placed around the ovulation estimate, not a
+/// measurement, and the frontend should always present it as an estimate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConceptionEstimate {
+    pub date: NaiveDate,
+    /// Days from the estimated ovulation day; negative before it.
+    pub days_from_ovulation: i64,
+    pub probability: f32,
+    /// What the ovulation estimate is based on. Calendar estimates are much
+    /// less certain than observed signs.
+    pub basis: OvulationBasis,
+}
+
+/// Symptoms expected on an upcoming day, from how often they were logged
+/// on the same cycle day in past cycles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymptomForecast {
+    pub date: NaiveDate,
+    pub cycle_day: u32,
+    /// Most likely first.
+    pub symptoms: Vec<SymptomLikelihood>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymptomLikelihood {
+    /// Key into the symptom registry.
+    pub symptom_type: String,
+    pub probability: f32,
+}
+
+/// Average of a daily rating over all days that fell in one cycle phase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseAverage {
+    pub phase: CyclePhase,
+    pub average: f32,
+    pub samples: usize,
+}
+
+/// One cycle's days lined up by cycle day, for overlaying cycles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayCycle {
+    pub cycle_start: NaiveDate,
+    /// The cycle still under way, which runs up to today.
+    pub current: bool,
+    /// Every day of the cycle, day 1 first, logged or not.
+    pub days: Vec<OverlayDay>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayDay {
+    pub cycle_day: u32,
+    pub date: NaiveDate,
+    pub flow_level: FlowLevel,
+    /// Symptom keys with their severity.
+    pub symptoms: Vec<(String, u8)>,
+    /// In the user's unit.
+    pub bbt: Option<f32>,
+}
+
+/// One cycle's temperatures, ready to chart, in the user's unit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BbtChart {
+    pub cycle_start: NaiveDate,
+    /// Oldest first, one per day with a temperature.
+    pub points: Vec<BbtPoint>,
+    /// Highest of the six temperatures before the shift, once there is one.
+    pub coverline: Option<f32>,
+    pub ovulation_day: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BbtPoint {
+    pub date: NaiveDate,
+    pub cycle_day: u32,
+    pub temperature: f32,
+    /// Centered three-reading average of the undisturbed temperatures;
+    /// `None` on disturbed days.
+    pub smoothed: Option<f32>,
+    pub disturbances: Vec<BbtDisturbance>,
+}
+
+/// What was logged on the same cycle day as `date` in earlier cycles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SameCycleDay {
+    pub date: NaiveDate,
+    pub cycle_day: u32,
+    /// Newest first; only cycles that lasted long enough to reach the day.
+    pub previous: Vec<PastCycleDay>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PastCycleDay {
+    pub cycle_start: NaiveDate,
+    pub date: NaiveDate,
+    pub day_log: Option<DayLog>,
+    pub symptoms: Vec<Symptom>,
+}
+
+/// The current cycle so far next to past cycles up to the same cycle day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleComparison {
+    pub cycle_start: NaiveDate,
+    pub cycle_day: u32,
+    /// Past cycles that reached this cycle day; the typical values average
+    /// over them.
+    pub past_cycles: usize,
+    pub flow_days: usize,
+    pub typical_flow_days: Option<f32>,
+    /// Flow days weighted 1 (light), 2 (medium) and 3 (heavy).
+    pub flow_intensity: u32,
+    pub typical_flow_intensity: Option<f32>,
+    /// Symptoms logged so far this cycle or typical by now, most logged
+    /// first.
+    pub symptoms: Vec<SymptomComparison>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymptomComparison {
+    pub symptom_type: String,
+    pub days_so_far: usize,
+    pub typical_days: f32,
+}
+
+/// One symptom's severity cycle by cycle, with a moving average.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeverityTrend {
+    pub symptom_type: String,
+    /// Cycles the symptom was logged in, oldest first.
+    pub points: Vec<CycleSeverity>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleSeverity {
+    pub cycle_start: NaiveDate,
+    /// Mean severity over the days it was logged in this cycle.
+    pub mean_severity: f32,
+    pub days: usize,
+    /// Mean of this and up to `window - 1` earlier points.
+    pub moving_average: f32,
+}
+
+/// Bleeding profile of one cycle's period, compared with the others.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleFlowProfile {
+    pub cycle_id: Uuid,
+    pub start_date: NaiveDate,
+    pub light_days: usize,
+    pub medium_days: usize,
+    pub heavy_days: usize,
+    /// Flow days weighted 1 (light), 2 (medium) and 3 (heavy).
+    pub intensity: u32,
+    /// Intensity relative to the median of the user's other cycles, once
+    /// there are enough to compare with.
+    pub relative_intensity: Option<f32>,
+    pub heavier_than_usual: bool,
+}
+
+/// How strongly symptoms go together, over days with a log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CooccurrenceReport {
+    pub logged_days: usize,
+    /// Strongest first.
+    pub symptoms: Vec<SymptomCorrelation>,
+    pub with_flow: Vec<FlowCorrelation>,
+}
+
+/// Correlation between `first` on a day and `second` `lag_days` later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymptomCorrelation {
+    pub first: String,
+    pub second: String,
+    /// 0 for the same day, 1 when `second` follows the next day.
+    pub lag_days: i64,
+    /// Day pairs where both were logged.
+    pub together: usize,
+    /// Pearson correlation of the two yes/no series, -1 to 1.
+    pub correlation: f32,
+}
+
+/// Correlation between having a symptom and the day's flow level.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowCorrelation {
+    pub symptom_type: String,
+    /// Days with both the symptom and some flow.
+    pub together: usize,
+    pub correlation: f32,
+}
+
+/// A value the charts can plot over time.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum SeriesMetric {
+    /// Days to the next period, dated by cycle start.
+    CycleLength,
+    /// Days of bleeding, dated by cycle start.
+    PeriodLength,
+    /// Basal body temperature, in the user's unit.
+    Bbt,
+    /// High mood severity minus low mood severity, on days with either.
+    Mood,
+    Libido,
+    /// Severity of any symptom, built-in or custom, on days it was logged.
+    Symptom {
+        symptom_type: String,
+    },
+}
+
+/// How points are bucketed: each bucket's values are averaged.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum SeriesResolution {
+    Day,
+    Week,
+    Month,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SeriesPoint {
+    /// First day of the bucket.
+    pub date: NaiveDate,
+    pub value: f32,
+}
+
+/// Everything the annual recap screen shows for one calendar year.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YearReview {
+    pub year: i32,
+    /// Cycles that started during the year.
+    pub cycles: usize,
+    pub avg_cycle_length: Option<f32>,
+    pub regularity: Option<Irregularity>,
+    /// Up to five symptoms, most logged first.
+    pub top_symptoms: Vec<SymptomCount>,
+    /// Months (1–12) with the most and least total flow, among months with
+    /// any flow logged.
+    pub heaviest_month: Option<u32>,
+    pub lightest_month: Option<u32>,
+    /// Days with anything logged.
+    pub logged_days: usize,
+    pub longest_logging_streak: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymptomCount {
+    pub symptom_type: String,
+    pub days: usize,
+}
+
+/// Symptoms logged during one calendar month.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonthlySymptoms {
+    pub month: u32,
+    /// Most logged first.
+    pub symptoms: Vec<SymptomMonthStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymptomMonthStats {
+    pub symptom_type: String,
+    pub days: usize,
+    pub avg_severity: f32,
+}
+
+/// Symptom occurrence rates by cycle day across past cycles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymptomHeatmap {
+    /// Number of columns: cycle days 1 to `cycle_days`.
+    pub cycle_days: u32,
+    /// How many past cycles each column is based on.
+    pub cycles_reached: Vec<usize>,
+    pub rows: Vec<HeatmapRow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapRow {
+    pub symptom_type: String,
+    /// Share of cycles with the symptom logged, one per cycle day.
+    pub rates: Vec<f32>,
+}
+
+/// How often one symptom came up in each cycle phase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymptomPhaseStats {
+    pub symptom_type: String,
+    /// One entry per phase, in cycle order.
+    pub phases: Vec<PhaseFrequency>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseFrequency {
+    pub phase: CyclePhase,
+    /// Days the symptom was logged in this phase.
+    pub days: usize,
+    /// Those days averaged over completed cycles.
+    pub per_cycle: f32,
+}
+
+/// Patterns only, for sharing with a partner or a forum without giving
+/// away the calendar: no dates, notes or individual logs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateExport {
+    /// Days in each completed cycle that counts towards stats, oldest first.
+    pub cycle_lengths: Vec<i64>,
+    /// Symptoms by label rather than key.
+    pub symptoms_by_phase: Vec<SymptomPhaseStats>,
+}
+
+/// What the ovulation estimate in a fertility window is based on.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum OvulationBasis {
+    Calendar,
+    Cervix,
+    /// The day after a positive LH test peak.
+    LhTest,
+    /// Confirmed by a basal body temperature shift.
+    Temperature,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +826,7 @@ pub struct FertilityWindow {
     pub ovulation_day: NaiveDate,
     pub peak_start: NaiveDate,
     pub peak_end: NaiveDate,
+    pub basis: OvulationBasis,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,24 +836,334 @@ pub struct CycleStats {
     pub avg_period_length: Option<f32>,
     pub shortest_cycle: Option<i64>,
     pub longest_cycle: Option<i64>,
+    /// Over the same cycles as the average.
+    pub cycle_length_spread: Option<Spread>,
+    pub period_length_spread: Option<Spread>,
     pub last_period_start: Option<NaiveDate>,
     pub last_period_end: Option<NaiveDate>,
+    pub bleed_kind: BleedKind,
+    pub perimenopause: Option<PerimenopauseStats>,
+    /// Cycles flagged as outliers or overridden by the user.
+    pub outliers: Vec<OutlierCycle>,
+    pub irregularity: Option<Irregularity>,
+    /// The irregularity score as it stood after each cycle, oldest first.
+    pub irregularity_history: Vec<IrregularityPoint>,
+    pub trend: Option<LengthTrend>,
+    /// Month-by-month averages over 3, 6 and 12 month windows.
+    pub rolling: Vec<RollingSeries>,
+    pub logging: Option<LoggingStats>,
+    /// Pill-taking record, under a pill regimen only.
+    pub adherence: Option<PillAdherence>,
+}
+
+/// How reliably active pills have been taken, over the days with a dose
+/// logged. Break days are not counted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PillAdherence {
+    /// Active-pill days since the pack start.
+    pub scheduled_days: usize,
+    pub taken: usize,
+    pub missed: usize,
+    /// Share of logged active days the pill was taken.
+    pub adherence: Option<f32>,
+    /// Consecutive missed pills up to the latest logged dose.
+    pub current_missed_streak: u32,
+    pub longest_missed_streak: u32,
+    /// Share of active days with bleeding within a few days after a missed
+    /// pill, and on the other active days.
+    pub bleeding_after_missed: Option<f32>,
+    pub bleeding_otherwise: Option<f32>,
+}
+
+/// How consistently days are being logged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingStats {
+    /// Consecutive logged days up to today, or up to yesterday while
+    /// today is still open.
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    /// Oldest first, ending with the current month.
+    pub months: Vec<MonthCompleteness>,
+}
+
+/// Share of days in a calendar month with any entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonthCompleteness {
+    pub year: i32,
+    pub month: u32,
+    pub logged_days: usize,
+    /// Days in the month so far.
+    pub days: usize,
+    pub completeness: f32,
+}
+
+/// How widely a set of lengths varies, in days.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Spread {
+    pub median: f32,
+    /// First and third quartiles.
+    pub lower_quartile: f32,
+    pub upper_quartile: f32,
+    pub interquartile_range: f32,
+    /// Sample standard deviation; 0 with a single value.
+    pub std_dev: f32,
+}
+
+/// Averages over a rolling window, one point per month.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollingSeries {
+    pub window_months: u32,
+    /// Oldest first; the last point ends today.
+    pub points: Vec<RollingPoint>,
+}
+
+/// Averages over the window ending on `end` (inclusive).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollingPoint {
+    pub end: NaiveDate,
+    /// Over cycles that started in the window and have since ended.
+    pub avg_cycle_length: Option<f32>,
+    pub avg_period_length: Option<f32>,
+    /// Mean total symptom severity logged per day in the window.
+    pub symptom_burden: f32,
+}
+
+/// Straight-line fit of recent cycle lengths against time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LengthTrend {
+    /// Change in cycle length per year, in days; positive when lengthening.
+    pub days_per_year: f32,
+    /// Change in cycle length across the fitted cycles, in days.
+    pub total_change: f32,
+    pub cycles: usize,
+    /// Whether the slope is clearly distinguishable from noise (|t| > 2).
+    pub significant: bool,
+}
+
+/// How much recent cycle lengths vary: 0 for identical cycles, 100 for a
+/// coefficient of variation of 30% or more.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Irregularity {
+    pub score: u8,
+    pub band: IrregularityBand,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IrregularityPoint {
+    /// Start of the latest cycle the score covers.
+    pub cycle_start: NaiveDate,
+    pub irregularity: Irregularity,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum IrregularityBand {
+    /// Score below 25.
+    Regular,
+    /// 25 to 49.
+    SomewhatIrregular,
+    /// 50 to 74.
+    Irregular,
+    /// 75 and up; worth bringing up with a clinician.
+    VeryIrregular,
+}
+
+/// A cycle whose length is far outside the user's usual range, or outside
+/// the plausible bounds altogether.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutlierCycle {
+    pub cycle_id: Uuid,
+    pub start_date: NaiveDate,
+    pub length: i64,
+    /// Outside the plausible cycle length bounds, most likely a detection
+    /// mistake such as spotting or a gap in logging.
+    pub implausible: bool,
+    /// Whether it is left out of averages, after any user override.
+    pub excluded: bool,
+}
+
+/// Stats that matter more than averages once cycles become erratic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerimenopauseStats {
+    /// Cycles long enough that at least one period was skipped.
+    pub skipped_cycles: usize,
+    pub months_since_last_period: Option<u32>,
+    /// Days with a logged hot flash per week, over the last four weeks.
+    pub hot_flashes_per_week: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppData {
+    #[serde(default)]
+    pub schema_version: u32,
     pub cycles: Vec<Cycle>,
-    pub day_logs: Vec<DayLog>,
-    pub symptoms: Vec<Symptom>,
+    #[serde(with = "by_date::day_logs")]
+    pub day_logs: BTreeMap<NaiveDate, DayLog>,
+    #[serde(with = "by_date::symptoms")]
+    pub symptoms: BTreeMap<NaiveDate, Vec<Symptom>>,
+    #[serde(default)]
+    pub pain: Vec<PainEntry>,
+    #[serde(default)]
+    pub pill_doses: Vec<PillDose>,
+    #[serde(default)]
+    pub appointments: Vec<Appointment>,
+    #[serde(default)]
+    pub lab_results: Vec<LabResult>,
+    #[serde(default)]
+    pub symptom_registry: Vec<SymptomDescriptor>,
+    #[serde(default)]
+    pub symptom_presets: Vec<SymptomPreset>,
+    #[serde(default)]
+    pub prediction_history: Vec<PredictionRecord>,
     pub settings: AppSettings,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Date-indexed collections are stored as flat arrays, so the data file and
+/// exports look the same as when they were plain `Vec`s.
+mod by_date {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub mod day_logs {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            logs: &BTreeMap<NaiveDate, DayLog>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(logs.values())
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<BTreeMap<NaiveDate, DayLog>, D::Error> {
+            let logs = Vec::<DayLog>::deserialize(deserializer)?;
+            Ok(logs.into_iter().map(|l| (l.date, l)).collect())
+        }
+    }
+
+    pub mod symptoms {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            symptoms: &BTreeMap<NaiveDate, Vec<Symptom>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(symptoms.values().flatten())
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<BTreeMap<NaiveDate, Vec<Symptom>>, D::Error> {
+            let mut by_date: BTreeMap<NaiveDate, Vec<Symptom>> = BTreeMap::new();
+            for symptom in Vec::<Symptom>::deserialize(deserializer)? {
+                by_date.entry(symptom.date).or_default().push(symptom);
+            }
+            Ok(by_date)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct AppSettings {
     pub auto_lock_minutes: u32,
     pub wipe_after_attempts: Option<u32>,
     #[serde(default)]
     pub show_fertility: bool,
+    #[serde(default)]
+    pub contraception_mode: ContraceptionMode,
+    #[serde(default)]
+    pub perimenopause_mode: bool,
+    #[serde(default)]
+    pub track_libido: bool,
+    #[serde(default)]
+    pub temperature_unit: TemperatureUnit,
+    #[serde(default)]
+    pub prediction_model: PredictionModel,
+    #[serde(default)]
+    pub late_period_sensitivity: Sensitivity,
+    #[serde(default = "default_true")]
+    pub show_health_flags: bool,
+    /// How many recent cycles the prediction averages are taken over.
+    #[serde(default = "default_history_cycles")]
+    pub prediction_history_cycles: u32,
+    /// Flow days at most this many days apart count as the same period.
+    #[serde(default = "default_cycle_gap_days")]
+    pub cycle_gap_days: u32,
+    /// Detected cycles shorter or longer than this are flagged as
+    /// implausible and kept out of predictions.
+    #[serde(default = "default_min_cycle_days")]
+    pub min_cycle_days: u32,
+    #[serde(default = "default_max_cycle_days")]
+    pub max_cycle_days: u32,
+    #[serde(default)]
+    pub trying_to_conceive: bool,
+    /// Opt-in check for long, irregular cycles alongside acne.
+    #[serde(default)]
+    pub pattern_screening: bool,
+    #[serde(default)]
+    pub note_templates: Vec<NoteTemplate>,
+    /// Symptom keys in the order the user wants them listed; any not named
+    /// follow in registry order.
+    #[serde(default)]
+    pub symptom_order: Vec<String>,
+    /// Symptom keys left out of the logging UI. Already logged entries are
+    /// unaffected.
+    #[serde(default)]
+    pub hidden_symptoms: Vec<String>,
+}
+
+/// A registry entry as the user arranged it for logging.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogueSymptom {
+    pub descriptor: SymptomDescriptor,
+    pub hidden: bool,
+}
+
+/// A named bundle of symptoms logged together, e.g. a usual first day.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SymptomPreset {
+    pub id: Uuid,
+    pub name: String,
+    /// `(symptom_type, severity)` pairs, keys already resolved.
+    pub symptoms: Vec<(String, u8)>,
+}
+
+/// Reusable starting point for a day's notes, such as a daily check-in.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NoteTemplate {
+    pub id: Uuid,
+    pub name: String,
+    pub notes: String,
+    /// Flow to log along with the notes, if any.
+    pub flow_level: Option<FlowLevel>,
+}
+
+/// Replaces parts of a template when logging a day from it.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct TemplateOverrides {
+    pub notes: Option<String>,
+    pub flow_level: Option<FlowLevel>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_history_cycles() -> u32 {
+    6
+}
+
+fn default_cycle_gap_days() -> u32 {
+    2
+}
+
+fn default_min_cycle_days() -> u32 {
+    15
+}
+
+fn default_max_cycle_days() -> u32 {
+    90
 }
 
 impl Default for AppSettings {
@@ -92,6 +1172,22 @@ impl Default for AppSettings {
             auto_lock_minutes: 5,
             wipe_after_attempts: None,
             show_fertility: false,
+            contraception_mode: ContraceptionMode::None,
+            perimenopause_mode: false,
+            track_libido: false,
+            temperature_unit: TemperatureUnit::Celsius,
+            prediction_model: PredictionModel::Auto,
+            late_period_sensitivity: Sensitivity::Normal,
+            show_health_flags: true,
+            prediction_history_cycles: default_history_cycles(),
+            cycle_gap_days: default_cycle_gap_days(),
+            min_cycle_days: default_min_cycle_days(),
+            max_cycle_days: default_max_cycle_days(),
+            trying_to_conceive: false,
+            pattern_screening: false,
+            note_templates: Vec::new(),
+            symptom_order: Vec::new(),
+            hidden_symptoms: Vec::new(),
         }
     }
 }
@@ -103,8 +1199,368 @@ pub struct MonthData {
     pub month: u32,
     pub day_logs: Vec<DayLog>,
     pub symptoms: Vec<Symptom>,
+    pub pain: Vec<PainEntry>,
     pub predictions: Vec<Prediction>,
     pub fertility: Option<FertilityWindow>,
     pub current_cycle: Option<Cycle>,
     pub stats: CycleStats,
 }
+
+/// Structured conditions a search result has to meet, all optional.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SearchFilters {
+    /// A symptom that has to be logged on the day.
+    pub symptom_type: Option<String>,
+    /// Lowest severity of that symptom, or of any symptom without one.
+    pub min_severity: Option<u8>,
+    pub flow_level: Option<FlowLevel>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// How a CSV export is laid out.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct CsvOptions {
+    /// `,` unless given; `;`, tab and `|` are also accepted.
+    pub delimiter: Option<char>,
+    /// strftime-style, `%Y-%m-%d` unless given.
+    pub date_format: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvFile {
+    pub name: String,
+    pub content: String,
+}
+
+/// Which spreadsheet columns hold what, for a CSV import.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct CsvMapping {
+    /// `,` unless given.
+    pub delimiter: Option<char>,
+    pub date_column: String,
+    /// strftime-style, `%Y-%m-%d` unless given.
+    pub date_format: Option<String>,
+    pub flow_column: Option<String>,
+    /// The file's own words for flow levels, e.g. `"S"` for light. Common
+    /// English words and 0-3 are understood without this.
+    pub flow_values: BTreeMap<String, FlowLevel>,
+    pub notes_column: Option<String>,
+    /// Temperatures in the user's unit.
+    pub bbt_column: Option<String>,
+    /// Column name to symptom key. Cells hold a severity, a tick such as
+    /// `x` or `yes`, or nothing.
+    pub symptom_columns: BTreeMap<String, String>,
+}
+
+/// A device whose nightly temperature export can be imported.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TemperatureDevice {
+    /// Oura ring: each night's deviation from the ring's own baseline.
+    Oura,
+    /// Another ring, band or patch worn overnight, e.g. Tempdrop or Ava.
+    OtherWearable,
+    /// A thermometer app's readings on waking, e.g. Femometer.
+    BluetoothThermometer,
+}
+
+/// One day as read from an import, before it is merged into the data.
+/// Fields left `None` keep whatever is already logged.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ImportedDay {
+    pub date: NaiveDate,
+    pub flow_level: Option<FlowLevel>,
+    pub notes: Option<String>,
+    /// Celsius.
+    pub bbt: Option<f32>,
+    pub bbt_source: TemperatureSource,
+    pub cervix: Option<CervixObservation>,
+    pub libido: Option<u8>,
+    pub lh_test: Option<LhTestResult>,
+    /// `(symptom_type, severity)`, keys already resolved.
+    pub symptoms: Vec<(String, u8)>,
+}
+
+/// What an import found, and whether it was merged in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// Distinct days read.
+    pub days: usize,
+    /// The first of those days, for a preview before committing.
+    pub preview: Vec<ImportedDay>,
+    /// Cells that couldn't be read, as `rows[i].column` with the header as
+    /// row 0. Their rows are imported without them, or skipped when the
+    /// date is unreadable.
+    pub errors: Vec<FieldError>,
+    /// Values with no equivalent here, as `category: value`, with how often
+    /// each came up. They are left out of the import.
+    pub unmapped: BTreeMap<String, usize>,
+    /// Days that would add nothing to what's already logged.
+    pub already_logged: usize,
+    pub committed: bool,
+}
+
+/// What to leave out of an export, e.g. to share only recent cycle
+/// timing with a clinician. Everything is included by default.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ExportOptions {
+    /// Only records dated from here on; cycles that end on or after it.
+    pub from: Option<NaiveDate>,
+    /// Only records dated up to here; cycles that start on or before it.
+    pub to: Option<NaiveDate>,
+    /// Blank out free-text notes on days and appointments.
+    pub omit_notes: bool,
+    /// Leave out symptoms and pain entries.
+    pub omit_symptoms: bool,
+    pub omit_settings: bool,
+}
+
+/// A part of a clinician report.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ReportSection {
+    /// Each cycle's start, end, period and cycle length.
+    CycleSummary,
+    Stats,
+    SymptomFrequency,
+    /// Flow over the first days of each cycle.
+    FlowChart,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Pdf,
+    Markdown,
+    /// A standalone page with its styles inline.
+    Html,
+}
+
+/// What goes into a calendar export. Titles are the only text shown, so
+/// they default to a plain dot rather than saying what the event is.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct IcalOptions {
+    /// Predicted periods to include, 3 unless given.
+    pub count: Option<usize>,
+    pub period_title: Option<String>,
+    pub fertile_title: Option<String>,
+    pub include_fertile_window: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub date: NaiveDate,
+    /// The matching part of the notes, when searching text.
+    pub snippet: Option<String>,
+    pub flow_level: FlowLevel,
+    pub symptom_types: Vec<String>,
+}
+
+/// One page of logged days from a date range, oldest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RangePage {
+    pub days: Vec<LoggedDay>,
+    /// Pass back as `cursor` for the next page; `None` on the last page.
+    pub next_cursor: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedDay {
+    pub date: NaiveDate,
+    pub day_log: Option<DayLog>,
+    pub symptoms: Vec<Symptom>,
+}
+
+/// One record in the journal-style timeline, tagged with its `kind`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum TimelineEntry {
+    CycleStart(Cycle),
+    /// Last day of the cycle's period.
+    PeriodEnd(Cycle),
+    DayLog(DayLog),
+    Symptom(Symptom),
+    Pain(PainEntry),
+    PillDose(PillDose),
+    LabResult(LabResult),
+    Appointment(Appointment),
+}
+
+impl TimelineEntry {
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            TimelineEntry::CycleStart(c) => c.start_date,
+            TimelineEntry::PeriodEnd(c) => c.end_date.unwrap_or(c.start_date),
+            TimelineEntry::DayLog(l) => l.date,
+            TimelineEntry::Symptom(s) => s.date,
+            TimelineEntry::Pain(p) => p.date,
+            TimelineEntry::PillDose(d) => d.date,
+            TimelineEntry::LabResult(r) => r.date,
+            TimelineEntry::Appointment(a) => a.date,
+        }
+    }
+}
+
+/// One page of the timeline, oldest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelinePage {
+    pub entries: Vec<TimelineEntry>,
+    /// Pass back as `cursor` for the next page; `None` on the last page.
+    pub next_cursor: Option<NaiveDate>,
+}
+
+/// Where the app stands with locking, for a countdown in the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LockStatus {
+    pub unlocked: bool,
+    pub session_started: Option<DateTime<Utc>>,
+    /// Until the backend locks on its own if nothing happens; `None` while
+    /// locked.
+    pub seconds_until_lock: Option<u64>,
+}
+
+/// Version and size details for the about screen and support requests.
+/// Holds counts only, never content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppInfo {
+    pub app_version: String,
+    /// Schema this build writes.
+    pub schema_version: u32,
+    pub storage_layout: Option<StorageLayout>,
+    pub data_file_bytes: Option<u64>,
+    /// `None` while locked.
+    pub records: Option<RecordCounts>,
+}
+
+/// How the encrypted data is kept on disk.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum StorageLayout {
+    /// One file, rewritten on every save.
+    SingleFile,
+    /// A folder of small files, one per month of records, for file-sync
+    /// tools to replicate.
+    MonthlyChunks,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecordCounts {
+    pub cycles: usize,
+    pub day_logs: usize,
+    /// Individual symptom entries, several per day possible.
+    pub symptoms: usize,
+    pub pain_entries: usize,
+    pub pill_doses: usize,
+    pub lab_results: usize,
+    pub appointments: usize,
+}
+
+impl RecordCounts {
+    pub fn of(data: &AppData) -> Self {
+        Self {
+            cycles: data.cycles.len(),
+            day_logs: data.day_logs.len(),
+            symptoms: data.symptoms.values().map(Vec::len).sum(),
+            pain_entries: data.pain.len(),
+            pill_doses: data.pill_doses.len(),
+            lab_results: data.lab_results.len(),
+            appointments: data.appointments.len(),
+        }
+    }
+}
+
+/// A backup split into QR code texts, shown one after another.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QrTransfer {
+    pub manifest: BundleManifest,
+    pub chunks: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QrTransferProgress {
+    pub received: usize,
+    pub total: usize,
+    /// 1-based positions of the codes not scanned yet.
+    pub missing: Vec<usize>,
+}
+
+/// What merging another copy of the data brought in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeReport {
+    /// Records that were only in the other copy.
+    pub added: RecordCounts,
+    /// Days edited differently on both sides, worth checking.
+    pub conflicting_days: Vec<NaiveDate>,
+}
+
+/// What a `.cykelbak` backup holds, readable once it's decrypted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u8,
+    pub schema_version: u32,
+    /// The version of cykel that wrote it.
+    pub app_version: String,
+    pub created_at: DateTime<Utc>,
+    pub records: RecordCounts,
+}
+
+/// What re-detecting cycles from scratch changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recalculation {
+    pub added: Vec<Cycle>,
+    pub removed: Vec<Cycle>,
+    /// Cycles that kept their id but whose dates or ovulation day moved,
+    /// as they are now.
+    pub changed: Vec<Cycle>,
+    pub previous_prediction: Option<Prediction>,
+    pub prediction: Option<Prediction>,
+}
+
+/// Everything recorded for one date, for the day detail view.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayData {
+    pub date: NaiveDate,
+    pub day_log: Option<DayLog>,
+    pub symptoms: Vec<Symptom>,
+    pub pain: Vec<PainEntry>,
+    /// Whether the pill was taken, when logged.
+    pub pill_taken: Option<bool>,
+    pub lab_results: Vec<LabResult>,
+    pub appointments: Vec<Appointment>,
+    /// `None` before the first cycle.
+    pub cycle_day: Option<u32>,
+    pub phase: Option<PhaseInfo>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexed_collections_stored_as_arrays() {
+        let json = r#"{
+            "cycles": [],
+            "day_logs": [
+                {"date": "2026-01-03", "flow_level": "Light", "notes": ""},
+                {"date": "2026-01-02", "flow_level": "Heavy", "notes": "x"}
+            ],
+            "symptoms": [
+                {"date": "2026-01-02", "symptom_type": "cramps", "severity": 2},
+                {"date": "2026-01-02", "symptom_type": "acne", "severity": 1}
+            ],
+            "settings": {"auto_lock_minutes": 5, "wipe_after_attempts": null}
+        }"#;
+        let data: AppData = serde_json::from_str(json).unwrap();
+        let jan_2 = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        assert_eq!(data.day_logs.keys().next(), Some(&jan_2));
+        assert_eq!(data.symptoms[&jan_2].len(), 2);
+
+        let value = serde_json::to_value(&data).unwrap();
+        assert_eq!(value["day_logs"].as_array().unwrap().len(), 2);
+        assert_eq!(value["day_logs"][0]["date"], "2026-01-02");
+        assert_eq!(value["symptoms"].as_array().unwrap().len(), 2);
+    }
+}
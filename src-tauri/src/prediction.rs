@@ -1,53 +1,372 @@
-use crate::models::{Cycle, CycleStats, FertilityWindow, Prediction};
+use std::collections::{BTreeMap, BTreeSet};
 
-/// Generate period predictions based on completed cycles.
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use crate::analytics;
+use crate::models::*;
+use crate::symptoms;
+
+pub mod accuracy;
+pub mod ovulation;
+pub mod simulation;
+
+/// Days into the pill-free break before a withdrawal bleed usually starts.
+const WITHDRAWAL_ONSET_DAYS: u32 = 2;
+/// In perimenopause mode, cycles longer than this count as skipped periods
+/// rather than as very long cycles.
+const SKIPPED_CYCLE_DAYS: i64 = 60;
+/// Half-width of the likely start window when there is only one cycle
+/// length to go on and so no spread to measure.
+const DEFAULT_START_SPREAD_DAYS: f64 = 3.0;
+/// Smoothing factor for the weighted model: each older cycle counts
+/// `1 - SMOOTHING_ALPHA` times as much as the one after it.
+const SMOOTHING_ALPHA: f64 = 0.4;
+/// Above this coefficient of variation in cycle length, the auto model
+/// switches from the mean to the median.
+const HIGH_VARIANCE_CV: f64 = 0.15;
+/// Scales the median absolute deviation to estimate a standard deviation
+/// for normally distributed data.
+const MAD_TO_STD_DEV: f64 = 1.4826;
+/// Cycles more than this many (scaled) MADs from the median length are
+/// left out of averages unless the user says otherwise.
+const OUTLIER_MADS: f64 = 2.5;
+/// Cycles within this many days of the median are never outliers, which
+/// keeps very regular users from having normal variation flagged.
+const MIN_OUTLIER_DEVIATION_DAYS: f64 = 7.0;
+/// Fewer cycle lengths than this say too little about what's usual.
+const MIN_LENGTHS_FOR_OUTLIERS: usize = 4;
+/// Days from ovulation to the next period, until the user's own luteal
+/// phase has been measured.
+const DEFAULT_LUTEAL_PHASE_DAYS: i64 = 14;
+/// Confirmed ovulations needed before the personal luteal phase is used.
+const MIN_LUTEAL_SAMPLES: usize = 2;
+/// Only the most recent luteal phases count, as with cycle lengths.
+const RECENT_LUTEAL_SAMPLES: usize = 6;
+/// Past cycles needed before a cycle day's symptoms are forecast.
+const MIN_FORECAST_CYCLES: usize = 2;
+/// Coefficient of variation that maps to the top irregularity score.
+const MAX_IRREGULARITY_CV: f64 = 0.3;
+/// Most recent cycle lengths the irregularity score is taken over.
+const IRREGULARITY_WINDOW: usize = 5;
+/// Cycle lengths needed before an irregularity score is given.
+const MIN_IRREGULARITY_LENGTHS: usize = 3;
+/// Cycle lengths needed before a trend is fitted, and the most it uses.
+const MIN_TREND_LENGTHS: usize = 12;
+const MAX_TREND_LENGTHS: usize = 24;
+/// t statistic above which a trend is reported as significant, roughly a 5%
+/// two-sided test at these sample sizes.
+const TREND_T_THRESHOLD: f64 = 2.0;
+/// How much perimenopause mode scales down prediction confidence.
+const PERIMENOPAUSE_CONFIDENCE_FACTOR: f32 = 0.5;
+/// How much perimenopause mode widens the likely start window, since past
+/// regularity says little about the next cycle.
+const PERIMENOPAUSE_MARGIN_FACTOR: f64 = 2.0;
+/// The narrowest half-width of the likely start window in perimenopause mode.
+const PERIMENOPAUSE_MIN_MARGIN_DAYS: i64 = 7;
+/// Day-specific probability of conception from intercourse on a single day,
+/// for the five days before ovulation and ovulation day itself (Wilcox et
+/// al., NEJM 1995).
+const CONCEPTION_BY_OVULATION_DAY: [f32; 6] = [0.10, 0.16, 0.14, 0.27, 0.31, 0.33];
+
+/// Generate the next period prediction based on completed cycles.
 /// Requires at least 2 completed cycles.
-pub fn predict(cycles: &[Cycle]) -> Option<Prediction> {
-    let stats = calc_internals(cycles)?;
+/// On the pill, predicts the withdrawal bleed from the pack regimen instead;
+/// with continuous hormonal contraception there is nothing to predict.
+pub fn predict(cycles: &[Cycle], settings: &AppSettings) -> Option<Prediction> {
+    predict_n(cycles, settings, 1).pop()
+}
 
-    let predicted_start =
-        stats.last_start + chrono::Duration::days(stats.avg_cycle.round() as i64);
-    let predicted_end =
-        predicted_start + chrono::Duration::days((stats.avg_period.round() - 1.0).max(0.0) as i64);
+/// Project the next `n` periods. Each further cycle compounds the average
+/// length, and confidence drops as the spread accumulates (by √k for the
+/// k-th cycle ahead).
+pub fn predict_n(cycles: &[Cycle], settings: &AppSettings, n: usize) -> Vec<Prediction> {
+    match settings.contraception_mode {
+        ContraceptionMode::None => {}
+        ContraceptionMode::Pill {
+            pack_start,
+            active_days,
+            break_days,
+        } => {
+            let today = chrono::Local::now().date_naive();
+            return pill_predictions(pack_start, active_days, break_days, today, n);
+        }
+        ContraceptionMode::Continuous => return Vec::new(),
+    }
 
-    let confidence = if stats.cycle_lengths.len() < 2 {
-        0.5
+    let Some(stats) = calc_internals(cycles, settings) else {
+        return Vec::new();
+    };
+
+    // Relative spread of cycle lengths; a single cycle gives a flat 50%
+    let (spread, std_dev) = if stats.cycle_lengths.len() < 2 {
+        (0.5, DEFAULT_START_SPREAD_DAYS)
     } else {
-        let std_dev = std_deviation(&stats.cycle_lengths);
-        (1.0 - (std_dev / stats.avg_cycle) as f32).clamp(0.1, 0.95)
+        ((stats.std_dev / stats.avg_cycle) as f32, stats.std_dev)
     };
+    let period_days = (stats.avg_period.round() - 1.0).max(0.0) as i64;
 
-    Some(Prediction {
-        predicted_start,
-        predicted_end,
-        confidence,
-    })
+    (1..=n)
+        .map(|k| {
+            let offset = (stats.avg_cycle * k as f64).round() as i64;
+            let predicted_start = stats.last_start + chrono::Duration::days(offset);
+            // ±1 standard deviation, accumulated over k cycles
+            let mut margin = (std_dev * (k as f64).sqrt()).round() as i64;
+            let mut confidence = (1.0 - spread * (k as f32).sqrt()).clamp(0.1, 0.95);
+            if settings.perimenopause_mode {
+                margin = ((margin as f64 * PERIMENOPAUSE_MARGIN_FACTOR).round() as i64)
+                    .max(PERIMENOPAUSE_MIN_MARGIN_DAYS);
+                confidence = (confidence * PERIMENOPAUSE_CONFIDENCE_FACTOR).max(0.1);
+            }
+            let margin = chrono::Duration::days(margin);
+            Prediction {
+                predicted_start,
+                predicted_end: predicted_start + chrono::Duration::days(period_days),
+                earliest_likely_start: predicted_start - margin,
+                latest_likely_start: predicted_start + margin,
+                confidence,
+                bleed_kind: BleedKind::Period,
+                model: Some(stats.model),
+            }
+        })
+        .collect()
+}
+
+/// Predict the next `n` withdrawal bleeds from a pill pack regimen: bleeding
+/// usually starts a couple of days into the break and stops when it ends.
+fn pill_predictions(
+    pack_start: NaiveDate,
+    active_days: u32,
+    break_days: u32,
+    today: NaiveDate,
+    n: usize,
+) -> Vec<Prediction> {
+    // Packs taken back-to-back have no scheduled bleed
+    if active_days == 0 || break_days == 0 {
+        return Vec::new();
+    }
+
+    let pack_len = (active_days + break_days) as i64;
+    let mut first_pack = (today - pack_start).num_days().max(0) / pack_len;
+    let break_end = |pack: i64| pack_start + chrono::Duration::days(pack * pack_len + pack_len - 1);
+    if break_end(first_pack) < today {
+        first_pack += 1;
+    }
+
+    (first_pack..first_pack + n as i64)
+        .map(|pack| {
+            let break_start =
+                pack_start + chrono::Duration::days(pack * pack_len + active_days as i64);
+            let onset = WITHDRAWAL_ONSET_DAYS.min(break_days - 1) as i64;
+            let predicted_start = break_start + chrono::Duration::days(onset);
+            Prediction {
+                predicted_start,
+                predicted_end: break_end(pack),
+                // Can come as soon as the break starts, or a day late
+                earliest_likely_start: break_start,
+                latest_likely_start: (predicted_start + chrono::Duration::days(1))
+                    .min(break_end(pack)),
+                confidence: 0.9,
+                bleed_kind: BleedKind::WithdrawalBleed,
+                model: None,
+            }
+        })
+        .collect()
+}
+
+/// Probability of bleeding on each day from `first_day` to `last_day`.
+/// Each predicted start is treated as normally distributed, with the likely
+/// start window as ±1 standard deviation; a day is a period day if the start
+/// falls within a period length before it. Logged flow counts as certain.
+pub fn period_probabilities(
+    predictions: &[Prediction],
+    day_logs: &BTreeMap<NaiveDate, DayLog>,
+    first_day: NaiveDate,
+    last_day: NaiveDate,
+) -> Vec<DayProbability> {
+    first_day
+        .iter_days()
+        .take_while(|&date| date <= last_day)
+        .map(|date| {
+            let logged_flow = day_logs
+                .get(&date)
+                .is_some_and(|log| log.flow_level != FlowLevel::None);
+            let probability = if logged_flow {
+                1.0
+            } else {
+                predictions
+                    .iter()
+                    .map(|p| bleeding_probability(p, date))
+                    .sum::<f64>()
+                    .min(1.0)
+            };
+            DayProbability {
+                date,
+                probability: probability as f32,
+            }
+        })
+        .collect()
+}
+
+fn bleeding_probability(prediction: &Prediction, date: NaiveDate) -> f64 {
+    let window = (prediction.latest_likely_start - prediction.earliest_likely_start).num_days();
+    // Never fully certain of the day, even with perfectly regular cycles
+    let std_dev = (window as f64 / 2.0).max(0.5);
+    let period_days = (prediction.predicted_end - prediction.predicted_start).num_days() + 1;
+    let offset = (date - prediction.predicted_start).num_days() as f64;
+    // P(date - period_days < start <= date), with a continuity correction
+    normal_cdf((offset + 0.5) / std_dev) - normal_cdf((offset - period_days as f64 + 0.5) / std_dev)
+}
+
+/// Standard normal CDF via the Abramowitz–Stegun erf approximation
+/// (error below 1.5e-7).
+fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+/// Predictions for the next `n` periods. With the sympto-thermal (or auto)
+/// model, once ovulation in the current cycle is confirmed by a thermal
+/// shift or LH peak, the next period is expected a luteal phase after it,
+/// and later ones move with it. Once enough past predictions have been
+/// settled, confidence comes from how often they landed in the window.
+pub fn upcoming(data: &AppData, n: usize) -> Vec<Prediction> {
+    let mut predictions = predict_n(&data.cycles, &data.settings, n);
+    if data.settings.contraception_mode != ContraceptionMode::None {
+        return predictions;
+    }
+    anchor_to_ovulation(data, &mut predictions);
+    for (k, prediction) in predictions.iter_mut().enumerate() {
+        let margin = (prediction.latest_likely_start - prediction.predicted_start).num_days();
+        if let Some(confidence) =
+            accuracy::calibrated_confidence(&data.prediction_history, margin, k + 1)
+        {
+            prediction.confidence = confidence;
+        }
+    }
+    predictions
+}
+
+fn anchor_to_ovulation(data: &AppData, predictions: &mut [Prediction]) {
+    if !matches!(
+        data.settings.prediction_model,
+        PredictionModel::Auto | PredictionModel::SymptoThermal
+    ) {
+        return;
+    }
+    let Some(current_start) = data.cycles.iter().map(|c| c.start_date).max() else {
+        return;
+    };
+    let Some(ovulation_day) = ovulation::confirmed(&data.day_logs, current_start..NaiveDate::MAX)
+    else {
+        return;
+    };
+    let Some(first) = predictions.first() else {
+        return;
+    };
+
+    let luteal_days = luteal_phase_days(data);
+    let shift = ovulation_day + chrono::Duration::days(luteal_days) - first.predicted_start;
+    for prediction in predictions {
+        prediction.predicted_start += shift;
+        prediction.predicted_end += shift;
+        prediction.earliest_likely_start += shift;
+        prediction.latest_likely_start += shift;
+        prediction.model = Some(PredictionModel::SymptoThermal);
+    }
+}
+
+/// The user's typical luteal phase: the median of recent cycles with
+/// confirmed ovulation, or 14 days until there are enough of them.
+pub fn luteal_phase_days(data: &AppData) -> i64 {
+    let lengths = ovulation::luteal_lengths(&data.cycles, &data.day_logs);
+    if lengths.len() < MIN_LUTEAL_SAMPLES {
+        return DEFAULT_LUTEAL_PHASE_DAYS;
+    }
+    let recent: Vec<f64> = lengths
+        .iter()
+        .rev()
+        .take(RECENT_LUTEAL_SAMPLES)
+        .map(|&days| days as f64)
+        .collect();
+    median(&recent).round() as i64
 }
 
 /// Estimate the fertility window based on predicted next period.
 /// Ovulation ~14 days before next period. Fertile window = ovulation - 5 to ovulation day.
 /// Peak fertility = ovulation - 2 to ovulation day.
-pub fn fertility_window(cycles: &[Cycle]) -> Option<FertilityWindow> {
-    let prediction = predict(cycles)?;
-
-    // Ovulation estimated at 14 days before predicted period start
-    let ovulation_day = prediction.predicted_start - chrono::Duration::days(14);
-    let fertile_start = ovulation_day - chrono::Duration::days(5);
-    let fertile_end = ovulation_day;
-    let peak_start = ovulation_day - chrono::Duration::days(2);
-    let peak_end = ovulation_day;
-
-    Some(FertilityWindow {
-        fertile_start,
-        fertile_end,
+/// A thermal shift or cervix peak observed in the current cycle overrides
+/// the calendar estimate.
+/// Hormonal contraception suppresses ovulation, so no window is given then.
+pub fn fertility_window(data: &AppData) -> Option<FertilityWindow> {
+    if data.settings.contraception_mode != ContraceptionMode::None {
+        return None;
+    }
+
+    let current_start = data.cycles.iter().map(|c| c.start_date).max();
+    if let Some((day, basis)) =
+        current_start.and_then(|start| ovulation::observed(&data.day_logs, start..NaiveDate::MAX))
+    {
+        return Some(window_around(day, basis));
+    }
+
+    let prediction = predict(&data.cycles, &data.settings)?;
+
+    // Ovulation estimated at a luteal phase before predicted period start
+    let ovulation_day =
+        prediction.predicted_start - chrono::Duration::days(luteal_phase_days(data));
+    Some(window_around(ovulation_day, OvulationBasis::Calendar))
+}
+
+/// The fertile window ahead of each of `predictions` (from
+/// [`upcoming`]): the current one, then calendar estimates for the cycles
+/// after it. Empty whenever [`fertility_window`] gives none.
+pub fn upcoming_fertility_windows(
+    data: &AppData,
+    predictions: &[Prediction],
+) -> Vec<FertilityWindow> {
+    let Some(current) = fertility_window(data) else {
+        return Vec::new();
+    };
+    let luteal = chrono::Duration::days(luteal_phase_days(data));
+    std::iter::once(current)
+        .chain(
+            predictions
+                .iter()
+                .skip(1)
+                .map(|p| window_around(p.predicted_start - luteal, OvulationBasis::Calendar)),
+        )
+        .collect()
+}
+
+fn window_around(ovulation_day: NaiveDate, basis: OvulationBasis) -> FertilityWindow {
+    FertilityWindow {
+        fertile_start: ovulation_day - chrono::Duration::days(5),
+        fertile_end: ovulation_day,
         ovulation_day,
-        peak_start,
-        peak_end,
-    })
+        peak_start: ovulation_day - chrono::Duration::days(2),
+        peak_end: ovulation_day,
+        basis,
+    }
 }
 
 /// Compute cycle statistics for the stats view.
-pub fn cycle_stats(cycles: &[Cycle]) -> CycleStats {
+pub fn cycle_stats(cycles: &[Cycle], settings: &AppSettings) -> CycleStats {
+    let bleed_kind = match settings.contraception_mode {
+        ContraceptionMode::None => BleedKind::Period,
+        ContraceptionMode::Pill { .. } => BleedKind::WithdrawalBleed,
+        ContraceptionMode::Continuous => BleedKind::Bleeding,
+    };
+
     let mut completed: Vec<&Cycle> = cycles.iter().filter(|c| c.end_date.is_some()).collect();
     completed.sort_by_key(|c| c.start_date);
 
@@ -58,8 +377,19 @@ pub fn cycle_stats(cycles: &[Cycle]) -> CycleStats {
             avg_period_length: None,
             shortest_cycle: None,
             longest_cycle: None,
+            cycle_length_spread: None,
+            period_length_spread: None,
             last_period_start: None,
             last_period_end: None,
+            bleed_kind,
+            perimenopause: None,
+            outliers: Vec::new(),
+            irregularity: None,
+            irregularity_history: Vec::new(),
+            trend: None,
+            rolling: Vec::new(),
+            logging: None,
+            adherence: None,
         };
     }
 
@@ -68,19 +398,34 @@ pub fn cycle_stats(cycles: &[Cycle]) -> CycleStats {
         .filter_map(|c| c.end_date.map(|end| (end - c.start_date).num_days() as f64 + 1.0))
         .collect();
 
-    let cycle_lengths: Vec<i64> = completed
-        .windows(2)
-        .map(|w| (w[1].start_date - w[0].start_date).num_days())
+    let lengths = measure_lengths(&completed, settings);
+    let cycle_lengths: Vec<i64> = lengths.iter().map(|l| l.days).collect();
+    let averaged_by_start: Vec<(NaiveDate, i64)> = lengths
+        .iter()
+        .filter(|l| counts_towards_average(l, settings))
+        .map(|l| (l.cycle.start_date, l.days))
+        .collect();
+    let averaged: Vec<i64> = averaged_by_start.iter().map(|&(_, days)| days).collect();
+    let outliers = lengths
+        .iter()
+        .filter(|l| l.outlier || l.implausible || l.cycle.include_in_stats.is_some())
+        .map(|l| OutlierCycle {
+            cycle_id: l.cycle.id,
+            start_date: l.cycle.start_date,
+            length: l.days,
+            implausible: l.implausible,
+            excluded: l.excluded(),
+        })
         .collect();
 
     let last = completed.last().unwrap();
 
     CycleStats {
         total_cycles: completed.len(),
-        avg_cycle_length: if cycle_lengths.is_empty() {
+        avg_cycle_length: if averaged.is_empty() {
             None
         } else {
-            Some(cycle_lengths.iter().sum::<i64>() as f32 / cycle_lengths.len() as f32)
+            Some(averaged.iter().sum::<i64>() as f32 / averaged.len() as f32)
         },
         avg_period_length: if period_lengths.is_empty() {
             None
@@ -89,19 +434,459 @@ pub fn cycle_stats(cycles: &[Cycle]) -> CycleStats {
         },
         shortest_cycle: cycle_lengths.iter().copied().min(),
         longest_cycle: cycle_lengths.iter().copied().max(),
+        cycle_length_spread: spread(&averaged.iter().map(|&d| d as f64).collect::<Vec<_>>()),
+        period_length_spread: spread(&period_lengths),
         last_period_start: Some(last.start_date),
         last_period_end: last.end_date,
+        bleed_kind,
+        perimenopause: None,
+        outliers,
+        // The most recent lengths, as in the prediction window
+        irregularity: irregularity(&averaged[averaged.len().saturating_sub(IRREGULARITY_WINDOW)..]),
+        irregularity_history: (0..averaged.len())
+            .filter_map(|i| {
+                let window = &averaged[(i + 1).saturating_sub(IRREGULARITY_WINDOW)..=i];
+                Some(IrregularityPoint {
+                    cycle_start: averaged_by_start[i].0,
+                    irregularity: irregularity(window)?,
+                })
+            })
+            .collect(),
+        trend: trend(&averaged_by_start),
+        rolling: Vec::new(),
+        logging: None,
+        adherence: None,
+    }
+}
+
+/// Start dates of the cycles left out of averages, as outliers, as
+/// implausible or by the user. Cycle-based analytics skip the same cycles.
+pub fn excluded_starts(cycles: &[Cycle], settings: &AppSettings) -> BTreeSet<NaiveDate> {
+    let mut completed: Vec<&Cycle> = cycles.iter().filter(|c| c.end_date.is_some()).collect();
+    completed.sort_by_key(|c| c.start_date);
+    measure_lengths(&completed, settings)
+        .iter()
+        .filter(|l| l.excluded())
+        .map(|l| l.cycle.start_date)
+        .collect()
+}
+
+/// A copy of `data` with every excluded cycle forced back in, for the
+/// "including excluded" variant of stats and analytics.
+pub fn including_excluded(data: &AppData) -> AppData {
+    let excluded = excluded_starts(&data.cycles, &data.settings);
+    let mut data = data.clone();
+    for cycle in &mut data.cycles {
+        if excluded.contains(&cycle.start_date) {
+            cycle.include_in_stats = Some(true);
+        }
     }
+    data
+}
+
+/// Least-squares trend of the most recent cycle lengths against their start
+/// dates (oldest first).
+fn trend(lengths: &[(NaiveDate, i64)]) -> Option<LengthTrend> {
+    let recent = &lengths[lengths.len().saturating_sub(MAX_TREND_LENGTHS)..];
+    if recent.len() < MIN_TREND_LENGTHS {
+        return None;
+    }
+    let first = recent[0].0;
+    let xs: Vec<f64> = recent
+        .iter()
+        .map(|(start, _)| (*start - first).num_days() as f64 / 365.25)
+        .collect();
+    let ys: Vec<f64> = recent.iter().map(|&(_, days)| days as f64).collect();
+    let (x_mean, y_mean) = (mean(&xs), mean(&ys));
+    let sxx: f64 = xs.iter().map(|x| (x - x_mean).powi(2)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+    let sxy: f64 = xs
+        .iter()
+        .zip(&ys)
+        .map(|(x, y)| (x - x_mean) * (y - y_mean))
+        .sum();
+    let slope = sxy / sxx;
+    let residuals: f64 = xs
+        .iter()
+        .zip(&ys)
+        .map(|(x, y)| (y - y_mean - slope * (x - x_mean)).powi(2))
+        .sum();
+    let standard_error = (residuals / (recent.len() - 2) as f64 / sxx).sqrt();
+    let significant = if standard_error == 0.0 {
+        slope != 0.0
+    } else {
+        (slope / standard_error).abs() > TREND_T_THRESHOLD
+    };
+
+    Some(LengthTrend {
+        days_per_year: slope as f32,
+        total_change: (slope * xs.last().copied().unwrap_or(0.0)) as f32,
+        cycles: recent.len(),
+        significant,
+    })
+}
+
+/// How much the given cycle lengths vary, once there are enough of them.
+pub fn irregularity(lengths: &[i64]) -> Option<Irregularity> {
+    let lengths: Vec<f64> = lengths.iter().map(|&l| l as f64).collect();
+    if lengths.len() < MIN_IRREGULARITY_LENGTHS {
+        return None;
+    }
+    let cv = std_deviation(&lengths) / mean(&lengths);
+    let score = ((cv / MAX_IRREGULARITY_CV).min(1.0) * 100.0).round() as u8;
+    let band = match score {
+        0..=24 => IrregularityBand::Regular,
+        25..=49 => IrregularityBand::SomewhatIrregular,
+        50..=74 => IrregularityBand::Irregular,
+        _ => IrregularityBand::VeryIrregular,
+    };
+    Some(Irregularity { score, band })
+}
+
+/// A completed cycle's length, measured to the next cycle's start.
+struct CycleLength<'a> {
+    cycle: &'a Cycle,
+    days: i64,
+    outlier: bool,
+    implausible: bool,
+}
+
+impl CycleLength<'_> {
+    fn excluded(&self) -> bool {
+        self.cycle
+            .include_in_stats
+            .map_or(self.outlier || self.implausible, |include| !include)
+    }
+}
+
+/// Length of each completed cycle (sorted by start) except the last, with
+/// lengths outside the plausible bounds flagged, and outliers flagged by
+/// their distance from the median of the plausible lengths.
+fn measure_lengths<'a>(completed: &[&'a Cycle], settings: &AppSettings) -> Vec<CycleLength<'a>> {
+    let min = settings.min_cycle_days as f64;
+    let max = settings.max_cycle_days as f64;
+    let days: Vec<f64> = completed
+        .windows(2)
+        .map(|w| (w[1].start_date - w[0].start_date).num_days() as f64)
+        .collect();
+    let plausible: Vec<f64> = days
+        .iter()
+        .copied()
+        .filter(|d| (min..=max).contains(d))
+        .collect();
+    let center = median(&plausible);
+    let deviations: Vec<f64> = plausible.iter().map(|d| (d - center).abs()).collect();
+    let limit =
+        (OUTLIER_MADS * MAD_TO_STD_DEV * median(&deviations)).max(MIN_OUTLIER_DEVIATION_DAYS);
+
+    completed
+        .iter()
+        .zip(&days)
+        .map(|(&cycle, &d)| {
+            let implausible = !(min..=max).contains(&d);
+            CycleLength {
+                cycle,
+                days: d as i64,
+                outlier: !implausible
+                    && plausible.len() >= MIN_LENGTHS_FOR_OUTLIERS
+                    && (d - center).abs() > limit,
+                implausible,
+            }
+        })
+        .collect()
+}
+
+/// Whether a length goes into averages: not excluded as an outlier and, in
+/// perimenopause mode, not a skipped period, which would drag the average
+/// far past any real cycle.
+fn counts_towards_average(length: &CycleLength, settings: &AppSettings) -> bool {
+    !length.excluded() && (!settings.perimenopause_mode || length.days <= SKIPPED_CYCLE_DAYS)
+}
+
+/// Perimenopause-specific stats: skipped periods, time since the last
+/// period and hot flash frequency over the last four weeks.
+pub fn perimenopause_stats(
+    cycles: &[Cycle],
+    symptoms: &BTreeMap<NaiveDate, Vec<Symptom>>,
+    today: NaiveDate,
+) -> PerimenopauseStats {
+    let mut starts: Vec<NaiveDate> = cycles.iter().map(|c| c.start_date).collect();
+    starts.sort();
+
+    let skipped_cycles = starts
+        .windows(2)
+        .filter(|w| (w[1] - w[0]).num_days() > SKIPPED_CYCLE_DAYS)
+        .count();
+
+    let last_bleed = cycles
+        .iter()
+        .map(|c| c.end_date.unwrap_or(c.start_date))
+        .max();
+    let months_since_last_period = last_bleed.map(|last| months_between(last, today));
+
+    let window_start = today - chrono::Duration::days(27);
+    let hot_flash_days = symptoms
+        .range(window_start..=today)
+        .filter(|(_, day)| day.iter().any(|s| s.symptom_type == symptoms::HOT_FLASH))
+        .count();
+
+    PerimenopauseStats {
+        skipped_cycles,
+        months_since_last_period,
+        hot_flashes_per_week: hot_flash_days as f32 / 4.0,
+    }
+}
+
+/// Whole calendar months from `from` to `to`.
+fn months_between(from: NaiveDate, to: NaiveDate) -> u32 {
+    use chrono::Datelike;
+
+    let months = (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32;
+    let months = if to.day() < from.day() {
+        months - 1
+    } else {
+        months
+    };
+    months.max(0) as u32
+}
+
+/// Which phase of its cycle `date` falls in, and how far into the cycle and
+/// the phase it is. Ovulation is taken from observations in that cycle when
+/// there are any, otherwise placed a luteal phase before the next period
+/// (predicted while that period is still ahead); the ovulatory phase spans
+/// the day either side of it. Returns `None` when the date can't be placed,
+/// or under hormonal contraception outside of bleeding days.
+pub fn phase_info(date: NaiveDate, data: &AppData) -> Option<PhaseInfo> {
+    phase_info_with(date, data, luteal_phase_days(data))
+}
+
+fn phase_info_with(date: NaiveDate, data: &AppData, luteal_days: i64) -> Option<PhaseInfo> {
+    let mut sorted: Vec<&Cycle> = data.cycles.iter().collect();
+    sorted.sort_by_key(|c| c.start_date);
+
+    let index = sorted.iter().rposition(|c| c.start_date <= date)?;
+    let cycle = sorted[index];
+    let cycle_day = (date - cycle.start_date).num_days() as u32 + 1;
+    let next_start = sorted.get(index + 1).map(|next| next.start_date);
+    let contracepting = data.settings.contraception_mode != ContraceptionMode::None;
+
+    let ovulation = if contracepting {
+        None
+    } else if let Some(recorded) = cycle.ovulation {
+        Some((recorded.day, recorded.basis))
+    } else {
+        let cycle_days = cycle.start_date..next_start.unwrap_or(NaiveDate::MAX);
+        ovulation::observed(&data.day_logs, cycle_days).or_else(|| {
+            let next_start = match next_start {
+                Some(start) => start,
+                None => upcoming(data, 1).first()?.predicted_start,
+            };
+            let day = next_start - chrono::Duration::days(luteal_days);
+            Some((day, OvulationBasis::Calendar))
+        })
+    };
+    let info = |phase, phase_start: NaiveDate| PhaseInfo {
+        date,
+        phase,
+        cycle_day,
+        phase_day: (date - phase_start).num_days() as u32 + 1,
+        ovulation_day: ovulation.map(|(day, _)| day),
+        ovulation_basis: ovulation.map(|(_, basis)| basis),
+    };
+
+    if cycle.end_date.is_none_or(|end| date <= end) {
+        return Some(info(CyclePhase::Menstrual, cycle.start_date));
+    }
+    let (ovulation_day, _) = ovulation?;
+    let one_day = chrono::Duration::days(1);
+
+    Some(if date < ovulation_day - one_day {
+        let period_end = cycle.end_date.expect("open cycles are menstrual");
+        info(CyclePhase::Follicular, period_end + one_day)
+    } else if date <= ovulation_day + one_day {
+        info(CyclePhase::Ovulatory, ovulation_day - one_day)
+    } else {
+        info(CyclePhase::Luteal, ovulation_day + one_day + one_day)
+    })
+}
+
+/// Cycle day, phase and days until `next` (the next predicted period) for
+/// `today`.
+pub fn snapshot(data: &AppData, today: NaiveDate, next: Option<&Prediction>) -> CycleSnapshot {
+    let current_start = data
+        .cycles
+        .iter()
+        .map(|c| c.start_date)
+        .filter(|&start| start <= today)
+        .max();
+    CycleSnapshot {
+        today,
+        cycle_day: current_start.map(|start| (today - start).num_days() as u32 + 1),
+        days_until_next: next.map(|p| (p.predicted_start - today).num_days()),
+        phase: phase_info(today, data).map(|info| info.phase),
+    }
+}
+
+/// Estimated chance of conception on `date`, from published day-specific
+/// probabilities placed around the ovulation estimate for its cycle. Days
+/// outside the fertile window get zero.
+pub fn conception_likelihood(date: NaiveDate, data: &AppData) -> Option<ConceptionEstimate> {
+    let info = phase_info(date, data)?;
+    let ovulation_day = info.ovulation_day?;
+    let basis = info.ovulation_basis?;
+    let days_from_ovulation = (date - ovulation_day).num_days();
+    let probability = usize::try_from(days_from_ovulation + 5)
+        .ok()
+        .and_then(|i| CONCEPTION_BY_OVULATION_DAY.get(i))
+        .copied()
+        .unwrap_or(0.0);
+    Some(ConceptionEstimate {
+        date,
+        days_from_ovulation,
+        probability,
+        basis,
+    })
+}
+
+/// Symptom likelihoods for the `days` days after `today`. Each day's cycle
+/// day comes from the actual and predicted period starts, and each symptom's
+/// probability is the share of past cycles that reached that cycle day and
+/// had the symptom logged on it.
+pub fn symptom_forecast(data: &AppData, today: NaiveDate, days: u32) -> Vec<SymptomForecast> {
+    let aligned = analytics::align_symptoms(data);
+    let mut starts: Vec<NaiveDate> = data.cycles.iter().map(|c| c.start_date).collect();
+    starts.sort();
+
+    // Even short cycles are around three weeks
+    let horizon = days as usize / 21 + 1;
+    starts.extend(upcoming(data, horizon).iter().map(|p| p.predicted_start));
+    (1..=days as i64)
+        .filter_map(|offset| {
+            let date = today + chrono::Duration::days(offset);
+            let start = starts.iter().filter(|&&s| s <= date).max()?;
+            let cycle_day = (date - *start).num_days() as u32 + 1;
+            let cycles = aligned.reached.get(&cycle_day).copied().unwrap_or(0);
+            let mut symptoms: Vec<SymptomLikelihood> = if cycles < MIN_FORECAST_CYCLES {
+                Vec::new()
+            } else {
+                aligned
+                    .counts
+                    .iter()
+                    .filter(|((day, _), _)| *day == cycle_day)
+                    .map(|((_, key), &count)| SymptomLikelihood {
+                        symptom_type: key.to_string(),
+                        probability: count as f32 / cycles as f32,
+                    })
+                    .collect()
+            };
+            symptoms.sort_by(|a, b| {
+                b.probability
+                    .total_cmp(&a.probability)
+                    .then_with(|| a.symptom_type.cmp(&b.symptom_type))
+            });
+            Some(SymptomForecast {
+                date,
+                cycle_day,
+                symptoms,
+            })
+        })
+        .collect()
+}
+
+const PHASES: [CyclePhase; 4] = [
+    CyclePhase::Menstrual,
+    CyclePhase::Follicular,
+    CyclePhase::Ovulatory,
+    CyclePhase::Luteal,
+];
+
+/// Average logged libido per cycle phase, for phases with any ratings.
+pub fn libido_by_phase(data: &AppData) -> Vec<PhaseAverage> {
+    let mut totals = [(0.0f64, 0usize); 4];
+    let luteal_days = luteal_phase_days(data);
+
+    for log in data.day_logs.values() {
+        let Some(libido) = log.libido else { continue };
+        let Some(phase) = phase_info_with(log.date, data, luteal_days).map(|info| info.phase)
+        else {
+            continue;
+        };
+        let slot = &mut totals[PHASES.iter().position(|&p| p == phase).unwrap()];
+        slot.0 += libido as f64;
+        slot.1 += 1;
+    }
+
+    PHASES
+        .iter()
+        .zip(totals)
+        .filter(|(_, (_, samples))| *samples > 0)
+        .map(|(&phase, (sum, samples))| PhaseAverage {
+            phase,
+            average: (sum / samples as f64) as f32,
+            samples,
+        })
+        .collect()
+}
+
+/// How often each symptom was logged in each phase, over completed cycles
+/// only so every day has a settled phase. Counts are divided by the number
+/// of cycles, so they read as days per cycle.
+pub fn symptom_phase_stats(data: &AppData) -> Vec<SymptomPhaseStats> {
+    let mut starts: Vec<NaiveDate> = data.cycles.iter().map(|c| c.start_date).collect();
+    starts.sort();
+    let excluded = excluded_starts(&data.cycles, &data.settings);
+    let included: Vec<&[NaiveDate]> = starts
+        .windows(2)
+        .filter(|w| !excluded.contains(&w[0]))
+        .collect();
+    let cycles = included.len();
+    let luteal_days = luteal_phase_days(data);
+
+    let mut counts: BTreeMap<&str, [usize; 4]> = BTreeMap::new();
+    for w in included {
+        for (&date, symptoms) in data.symptoms.range(w[0]..w[1]) {
+            let Some(phase) = phase_info_with(date, data, luteal_days).map(|info| info.phase)
+            else {
+                continue;
+            };
+            let slot = PHASES.iter().position(|&p| p == phase).unwrap();
+            for symptom in symptoms {
+                counts.entry(&symptom.symptom_type).or_default()[slot] += 1;
+            }
+        }
+    }
+
+    counts
+        .into_iter()
+        .map(|(symptom_type, days)| SymptomPhaseStats {
+            symptom_type: symptom_type.to_string(),
+            phases: PHASES
+                .iter()
+                .zip(days)
+                .map(|(&phase, days)| PhaseFrequency {
+                    phase,
+                    days,
+                    per_cycle: days as f32 / cycles as f32,
+                })
+                .collect(),
+        })
+        .collect()
 }
 
 struct PredictionInternals {
     avg_cycle: f64,
+    /// Spread of cycle lengths as the chosen model measures it.
+    std_dev: f64,
+    /// The calendar model actually used.
+    model: PredictionModel,
     avg_period: f64,
     cycle_lengths: Vec<f64>,
-    last_start: chrono::NaiveDate,
+    last_start: NaiveDate,
 }
 
-fn calc_internals(cycles: &[Cycle]) -> Option<PredictionInternals> {
+fn calc_internals(cycles: &[Cycle], settings: &AppSettings) -> Option<PredictionInternals> {
     let mut completed: Vec<&Cycle> = cycles.iter().filter(|c| c.end_date.is_some()).collect();
 
     if completed.len() < 2 {
@@ -110,12 +895,22 @@ fn calc_internals(cycles: &[Cycle]) -> Option<PredictionInternals> {
 
     completed.sort_by_key(|c| c.start_date);
 
-    // Use last 6 cycles max
-    let recent: Vec<&Cycle> = completed.iter().rev().take(6).copied().collect();
+    // Use the configured number of recent cycles, newest first
+    let recent: Vec<&Cycle> = completed
+        .iter()
+        .rev()
+        .take(settings.prediction_history_cycles.max(2) as usize)
+        .copied()
+        .collect();
 
-    let cycle_lengths: Vec<f64> = recent
-        .windows(2)
-        .map(|w| (w[0].start_date - w[1].start_date).num_days().unsigned_abs() as f64)
+    // Outliers are judged against the whole history, not just the recent
+    // cycles
+    let cycle_lengths: Vec<f64> = measure_lengths(&completed, settings)
+        .iter()
+        .rev()
+        .take(recent.len() - 1)
+        .filter(|l| counts_towards_average(l, settings))
+        .map(|l| l.days as f64)
         .collect();
 
     if cycle_lengths.is_empty() {
@@ -127,7 +922,32 @@ fn calc_internals(cycles: &[Cycle]) -> Option<PredictionInternals> {
         .filter_map(|c| c.end_date.map(|end| (end - c.start_date).num_days() as f64 + 1.0))
         .collect();
 
-    let avg_cycle = mean(&cycle_lengths);
+    let mut model = settings.prediction_model;
+    if matches!(
+        model,
+        PredictionModel::Auto | PredictionModel::SymptoThermal
+    ) {
+        let cv = std_deviation(&cycle_lengths) / mean(&cycle_lengths);
+        model = if cv > HIGH_VARIANCE_CV {
+            PredictionModel::Robust
+        } else {
+            PredictionModel::Mean
+        };
+    }
+    let (avg_cycle, std_dev) = match model {
+        PredictionModel::Auto | PredictionModel::SymptoThermal | PredictionModel::Mean => {
+            (mean(&cycle_lengths), std_deviation(&cycle_lengths))
+        }
+        PredictionModel::Weighted => (
+            exponential_mean(&cycle_lengths),
+            std_deviation(&cycle_lengths),
+        ),
+        PredictionModel::Robust => {
+            let median = median(&cycle_lengths);
+            let deviations: Vec<f64> = cycle_lengths.iter().map(|l| (l - median).abs()).collect();
+            (median, MAD_TO_STD_DEV * self::median(&deviations))
+        }
+    };
     let avg_period = if period_lengths.is_empty() {
         5.0
     } else {
@@ -138,12 +958,95 @@ fn calc_internals(cycles: &[Cycle]) -> Option<PredictionInternals> {
 
     Some(PredictionInternals {
         avg_cycle,
+        std_dev,
+        model,
         avg_period,
         cycle_lengths,
         last_start,
     })
 }
 
+/// Re-detect cycles from flow logs. Cycles the user confirmed or entered
+/// are kept as they are, and detected cycles overlapping them are dropped.
+/// Completed cycles then get their likely ovulation day recorded.
+pub fn rebuild_cycles(data: &mut AppData) {
+    let mut cycles: Vec<Cycle> = data
+        .cycles
+        .iter()
+        .filter(|c| c.source != CycleSource::Detected)
+        .cloned()
+        .collect();
+    let mut detected: Vec<Cycle> = detect_cycles(&data.day_logs, data.settings.cycle_gap_days)
+        .into_iter()
+        .filter(|d| !cycles.iter().any(|c| c.overlaps(d)))
+        .collect();
+    // A cycle that still starts on the same day is the same cycle: keep its
+    // id and any stats override, with when that was set
+    for cycle in &mut detected {
+        if let Some(old) = data
+            .cycles
+            .iter()
+            .find(|c| c.source == CycleSource::Detected && c.start_date == cycle.start_date)
+        {
+            cycle.id = old.id;
+            cycle.include_in_stats = old.include_in_stats;
+            cycle.created_at = old.created_at;
+            cycle.updated_at = old.updated_at;
+        }
+    }
+    cycles.extend(detected);
+    cycles.sort_by_key(|c| c.start_date);
+    data.cycles = cycles;
+    ovulation::annotate(data);
+}
+
+fn detect_cycles(day_logs: &BTreeMap<NaiveDate, DayLog>, gap_days: u32) -> Vec<Cycle> {
+    // Already sorted and unique: day logs are keyed by date
+    let flow_days: Vec<NaiveDate> = day_logs
+        .values()
+        .filter(|l| l.flow_level != FlowLevel::None)
+        .map(|l| l.date)
+        .collect();
+
+    if flow_days.is_empty() {
+        return Vec::new();
+    }
+
+    let detected = |start_date, end_date| Cycle {
+        id: Uuid::new_v4(),
+        start_date,
+        end_date,
+        source: CycleSource::Detected,
+        include_in_stats: None,
+        ovulation: None,
+        created_at: None,
+        updated_at: None,
+    };
+    let mut cycles: Vec<Cycle> = Vec::new();
+    let mut cycle_start = flow_days[0];
+    let mut cycle_end = flow_days[0];
+
+    for &day in &flow_days[1..] {
+        if (day - cycle_end).num_days() <= i64::from(gap_days) {
+            cycle_end = day;
+        } else {
+            cycles.push(detected(cycle_start, Some(cycle_end)));
+            cycle_start = day;
+            cycle_end = day;
+        }
+    }
+
+    let today = chrono::Local::now().date_naive();
+    let last_end = if (today - cycle_end).num_days() <= i64::from(gap_days) {
+        None
+    } else {
+        Some(cycle_end)
+    };
+
+    cycles.push(detected(cycle_start, last_end));
+    cycles
+}
+
 fn mean(values: &[f64]) -> f64 {
     if values.is_empty() {
         return 0.0;
@@ -151,6 +1054,59 @@ fn mean(values: &[f64]) -> f64 {
     values.iter().sum::<f64>() / values.len() as f64
 }
 
+pub fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Median, quartiles and standard deviation, with quartiles interpolated
+/// between the nearest values.
+fn spread(values: &[f64]) -> Option<Spread> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let quantile = |q: f64| {
+        let position = (sorted.len() - 1) as f64 * q;
+        let (low, high) = (position.floor() as usize, position.ceil() as usize);
+        sorted[low] + (sorted[high] - sorted[low]) * (position - low as f64)
+    };
+    let (lower, upper) = (quantile(0.25), quantile(0.75));
+    Some(Spread {
+        median: median(&sorted) as f32,
+        lower_quartile: lower as f32,
+        upper_quartile: upper as f32,
+        interquartile_range: (upper - lower) as f32,
+        std_dev: std_deviation(&sorted) as f32,
+    })
+}
+
+/// Exponentially weighted mean of values ordered newest first.
+fn exponential_mean(newest_first: &[f64]) -> f64 {
+    let mut weighted_sum = 0.0;
+    let mut total_weight = 0.0;
+    let mut weight = 1.0;
+    for value in newest_first {
+        weighted_sum += weight * value;
+        total_weight += weight;
+        weight *= 1.0 - SMOOTHING_ALPHA;
+    }
+    if total_weight == 0.0 {
+        return 0.0;
+    }
+    weighted_sum / total_weight
+}
+
 fn std_deviation(values: &[f64]) -> f64 {
     if values.len() < 2 {
         return 0.0;
@@ -164,21 +1120,24 @@ fn std_deviation(values: &[f64]) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::NaiveDate;
-    use uuid::Uuid;
 
     fn make_cycle(start: &str, end: &str) -> Cycle {
         Cycle {
             id: Uuid::new_v4(),
             start_date: NaiveDate::parse_from_str(start, "%Y-%m-%d").unwrap(),
             end_date: Some(NaiveDate::parse_from_str(end, "%Y-%m-%d").unwrap()),
+            source: CycleSource::Detected,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: None,
+            updated_at: None,
         }
     }
 
     #[test]
     fn no_prediction_with_one_cycle() {
         let cycles = vec![make_cycle("2026-01-01", "2026-01-05")];
-        assert!(predict(&cycles).is_none());
+        assert!(predict(&cycles, &AppSettings::default()).is_none());
     }
 
     #[test]
@@ -187,20 +1146,452 @@ mod tests {
             make_cycle("2026-01-01", "2026-01-05"),
             make_cycle("2026-01-29", "2026-02-02"),
         ];
-        let pred = predict(&cycles).unwrap();
+        let pred = predict(&cycles, &AppSettings::default()).unwrap();
         assert_eq!(
             pred.predicted_start,
             NaiveDate::from_ymd_opt(2026, 2, 26).unwrap()
         );
     }
 
+    #[test]
+    fn later_cycles_compound_with_less_confidence() {
+        let cycles = vec![
+            make_cycle("2026-01-01", "2026-01-05"),
+            make_cycle("2026-01-29", "2026-02-02"),
+            make_cycle("2026-02-28", "2026-03-03"),
+        ];
+        let preds = predict_n(&cycles, &AppSettings::default(), 3);
+        let starts: Vec<_> = preds.iter().map(|p| p.predicted_start).collect();
+        // Average of 28 and 30 days, compounded from Feb 28
+        assert_eq!(
+            starts,
+            [
+                NaiveDate::from_ymd_opt(2026, 3, 29).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 4, 27).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 5, 26).unwrap(),
+            ]
+        );
+        assert!(preds[0].confidence > preds[1].confidence);
+        // Lengths 28 and 30: standard deviation ~1.4 days, ~2.4 by the third
+        assert_eq!(
+            preds[0].earliest_likely_start,
+            NaiveDate::from_ymd_opt(2026, 3, 28).unwrap()
+        );
+        assert_eq!(
+            preds[2].latest_likely_start,
+            NaiveDate::from_ymd_opt(2026, 5, 28).unwrap()
+        );
+        assert!(preds[1].confidence > preds[2].confidence);
+    }
+
+    #[test]
+    fn probabilities_peak_around_predicted_period() {
+        let cycles = vec![
+            make_cycle("2026-01-01", "2026-01-05"),
+            make_cycle("2026-01-29", "2026-02-02"),
+            make_cycle("2026-02-28", "2026-03-03"),
+        ];
+        let preds = predict_n(&cycles, &AppSettings::default(), 1);
+        let first_day = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let last_day = NaiveDate::from_ymd_opt(2026, 4, 30).unwrap();
+        let days = period_probabilities(&preds, &BTreeMap::new(), first_day, last_day);
+        assert_eq!(days.len(), 61);
+
+        let on = |m, d| {
+            let date = NaiveDate::from_ymd_opt(2026, m, d).unwrap();
+            days.iter().find(|p| p.date == date).unwrap().probability
+        };
+        // Predicted Mar 29 - Apr 2: most likely mid-period, fading either side
+        assert!(on(3, 31) > 0.9);
+        assert!(on(3, 27) > 0.0 && on(3, 27) < on(3, 29));
+        assert!(on(4, 15) < 0.001);
+    }
+
+    fn cycles_with_lengths(lengths: &[i64]) -> Vec<Cycle> {
+        let mut start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mut cycles = Vec::new();
+        for &len in lengths {
+            cycles.push(make_cycle(
+                &start.to_string(),
+                &(start + chrono::Duration::days(4)).to_string(),
+            ));
+            start += chrono::Duration::days(len);
+        }
+        cycles.push(make_cycle(
+            &start.to_string(),
+            &(start + chrono::Duration::days(4)).to_string(),
+        ));
+        cycles
+    }
+
+    #[test]
+    fn history_window_is_configurable() {
+        let cycles = cycles_with_lengths(&[28, 28, 28, 28, 33, 33]);
+        let avg = |history: u32| {
+            let settings = AppSettings {
+                prediction_history_cycles: history,
+                ..AppSettings::default()
+            };
+            calc_internals(&cycles, &settings).unwrap().avg_cycle
+        };
+        assert!((avg(6) - 30.0).abs() < 1e-9);
+        assert!((avg(3) - 33.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weighted_model_follows_a_shift_sooner() {
+        // Cycles lengthen from 28 to 33 days
+        let cycles = cycles_with_lengths(&[28, 28, 33, 33, 33]);
+        let last_start = cycles.last().unwrap().start_date;
+        let days_ahead = |model| {
+            let settings = AppSettings {
+                prediction_model: model,
+                ..AppSettings::default()
+            };
+            (predict(&cycles, &settings).unwrap().predicted_start - last_start).num_days()
+        };
+        assert_eq!(days_ahead(PredictionModel::Mean), 31);
+        assert_eq!(days_ahead(PredictionModel::Weighted), 32);
+
+        // With steady cycles the two models agree
+        let cycles = cycles_with_lengths(&[29, 29, 29, 29, 29]);
+        let mean = calc_internals(&cycles, &AppSettings::default()).unwrap();
+        let weighted = calc_internals(
+            &cycles,
+            &AppSettings {
+                prediction_model: PredictionModel::Weighted,
+                ..AppSettings::default()
+            },
+        )
+        .unwrap();
+        assert!((mean.avg_cycle - weighted.avg_cycle).abs() < 1e-9);
+    }
+
+    #[test]
+    fn robust_model_ignores_one_long_cycle() {
+        let mut cycles = cycles_with_lengths(&[28, 29, 45, 28, 29]);
+        // Kept in the averages, so only the model can discount it
+        cycles[2].include_in_stats = Some(true);
+        let last_start = cycles.last().unwrap().start_date;
+        let prediction = |model| {
+            let settings = AppSettings {
+                prediction_model: model,
+                ..AppSettings::default()
+            };
+            predict(&cycles, &settings).unwrap()
+        };
+        let days_ahead = |p: &Prediction| (p.predicted_start - last_start).num_days();
+
+        let mean = prediction(PredictionModel::Mean);
+        let robust = prediction(PredictionModel::Robust);
+        assert_eq!(days_ahead(&mean), 32);
+        assert_eq!(days_ahead(&robust), 29);
+        assert!(robust.confidence > mean.confidence);
+        // The outlier makes the variance high enough for auto to go robust
+        let auto = prediction(PredictionModel::Auto);
+        assert_eq!(auto.predicted_start, robust.predicted_start);
+    }
+
+    #[test]
+    fn outlier_cycle_excluded_unless_overridden() {
+        let mut cycles = cycles_with_lengths(&[28, 29, 28, 50, 29, 28]);
+        let stats = cycle_stats(&cycles, &AppSettings::default());
+        assert_eq!(stats.outliers.len(), 1);
+        assert_eq!(stats.outliers[0].length, 50);
+        assert!(stats.outliers[0].excluded);
+        assert_eq!(stats.avg_cycle_length, Some(28.4));
+        assert_eq!(stats.longest_cycle, Some(50));
+
+        cycles[3].include_in_stats = Some(true);
+        let stats = cycle_stats(&cycles, &AppSettings::default());
+        assert!(!stats.outliers[0].excluded);
+        assert_eq!(stats.avg_cycle_length, Some(32.0));
+    }
+
+    #[test]
+    fn implausible_lengths_flagged_and_excluded() {
+        // Spotting split one cycle in two, and a logging gap made a
+        // 200-day cycle
+        let cycles = cycles_with_lengths(&[28, 5, 23, 30, 200]);
+        let stats = cycle_stats(&cycles, &AppSettings::default());
+        let implausible: Vec<i64> = stats
+            .outliers
+            .iter()
+            .filter(|o| o.implausible && o.excluded)
+            .map(|o| o.length)
+            .collect();
+        assert_eq!(implausible, [5, 200]);
+        assert_eq!(stats.avg_cycle_length, Some(27.0));
+
+        let settings = AppSettings {
+            min_cycle_days: 5,
+            max_cycle_days: 200,
+            ..AppSettings::default()
+        };
+        assert!(cycle_stats(&cycles, &settings)
+            .outliers
+            .iter()
+            .all(|o| !o.implausible));
+    }
+
+    #[test]
+    fn snapshot_counts_cycle_day_and_days_until_next() {
+        let data = AppData {
+            cycles: vec![
+                make_cycle("2026-01-01", "2026-01-05"),
+                make_cycle("2026-01-29", "2026-02-02"),
+                make_cycle("2026-02-26", "2026-03-02"),
+            ],
+            ..AppData::default()
+        };
+        let today = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        let next = predict(&data.cycles, &data.settings);
+        let snapshot = snapshot(&data, today, next.as_ref());
+        assert_eq!(snapshot.cycle_day, Some(13));
+        assert_eq!(snapshot.days_until_next, Some(16));
+        assert_eq!(snapshot.phase, Some(CyclePhase::Follicular));
+
+        let empty = AppData::default();
+        let snapshot = super::snapshot(&empty, today, None);
+        assert_eq!(snapshot.cycle_day, None);
+        assert_eq!(snapshot.phase, None);
+    }
+
+    #[test]
+    fn symptoms_counted_per_phase() {
+        let mut data = AppData {
+            cycles: vec![
+                make_cycle("2026-01-01", "2026-01-05"),
+                make_cycle("2026-01-29", "2026-02-02"),
+                make_cycle("2026-02-26", "2026-03-02"),
+            ],
+            ..AppData::default()
+        };
+        // Headaches late in both completed cycles, one cramp day; the open
+        // cycle doesn't count
+        for d in ["2026-01-02", "2026-01-24", "2026-02-20", "2026-02-27"] {
+            let date = NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+            let symptom_type = if d == "2026-01-02" {
+                "cramps"
+            } else {
+                "headache"
+            };
+            data.symptoms.insert(
+                date,
+                vec![Symptom {
+                    date,
+                    symptom_type: symptom_type.to_string(),
+                    severity: 2,
+                    created_at: None,
+                    updated_at: None,
+                }],
+            );
+        }
+
+        let stats = symptom_phase_stats(&data);
+        assert_eq!(stats.len(), 2);
+        let headache = stats.iter().find(|s| s.symptom_type == "headache").unwrap();
+        let luteal = &headache.phases[3];
+        assert_eq!(luteal.phase, CyclePhase::Luteal);
+        assert_eq!(luteal.days, 2);
+        assert!((luteal.per_cycle - 1.0).abs() < 1e-6);
+        assert_eq!(stats[0].symptom_type, "cramps");
+        assert_eq!(stats[0].phases[0].days, 1);
+    }
+
+    #[test]
+    fn conception_peaks_before_ovulation() {
+        let data = AppData {
+            cycles: vec![
+                make_cycle("2026-01-01", "2026-01-05"),
+                make_cycle("2026-01-29", "2026-02-02"),
+                make_cycle("2026-02-26", "2026-03-02"),
+            ],
+            ..AppData::default()
+        };
+        let estimate = |d: &str| {
+            conception_likelihood(NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap(), &data).unwrap()
+        };
+        // Next period Mar 26, so calendar ovulation on Mar 12
+        let peak = estimate("2026-03-12");
+        assert_eq!(peak.days_from_ovulation, 0);
+        assert_eq!(peak.basis, OvulationBasis::Calendar);
+        assert!((peak.probability - 0.33).abs() < 1e-6);
+        assert!((estimate("2026-03-07").probability - 0.10).abs() < 1e-6);
+        assert_eq!(estimate("2026-03-06").probability, 0.0);
+        assert_eq!(estimate("2026-03-13").probability, 0.0);
+    }
+
+    #[test]
+    fn thermal_shift_anchors_next_period() {
+        let cycles = vec![
+            make_cycle("2026-01-01", "2026-01-05"),
+            make_cycle("2026-01-29", "2026-02-02"),
+            make_cycle("2026-02-26", "2026-03-02"),
+        ];
+        let temps = [
+            36.4, 36.5, 36.3, 36.4, 36.5, 36.4, 36.45, 36.6, 36.65, 36.75,
+        ];
+        let first_temp = NaiveDate::from_ymd_opt(2026, 3, 5).unwrap();
+        let day_logs = temps
+            .iter()
+            .enumerate()
+            .map(|(i, &t)| {
+                let date = first_temp + chrono::Duration::days(i as i64);
+                let log = DayLog {
+                    bbt: Some(t),
+                    ..DayLog::new(date)
+                };
+                (date, log)
+            })
+            .collect();
+        let data = AppData {
+            cycles,
+            day_logs,
+            ..AppData::default()
+        };
+
+        // Calendar alone says Mar 26; ovulation on Mar 11 moves it a day earlier
+        let preds = upcoming(&data, 2);
+        assert_eq!(
+            preds[0].predicted_start,
+            NaiveDate::from_ymd_opt(2026, 3, 25).unwrap()
+        );
+        assert_eq!(
+            preds[1].predicted_start,
+            NaiveDate::from_ymd_opt(2026, 4, 22).unwrap()
+        );
+        assert_eq!(preds[0].model, Some(PredictionModel::SymptoThermal));
+        let fw = fertility_window(&data).unwrap();
+        assert_eq!(fw.basis, OvulationBasis::Temperature);
+
+        // A calendar-only model ignores the shift
+        let data = AppData {
+            settings: AppSettings {
+                prediction_model: PredictionModel::Mean,
+                ..AppSettings::default()
+            },
+            ..data
+        };
+        let preds = upcoming(&data, 1);
+        assert_eq!(
+            preds[0].predicted_start,
+            NaiveDate::from_ymd_opt(2026, 3, 26).unwrap()
+        );
+        assert_eq!(preds[0].model, Some(PredictionModel::Mean));
+    }
+
+    #[test]
+    fn symptoms_forecast_by_cycle_day() {
+        let cycles = vec![
+            make_cycle("2026-01-01", "2026-01-05"),
+            make_cycle("2026-01-29", "2026-02-02"),
+            make_cycle("2026-02-26", "2026-03-02"),
+        ];
+        let symptom = |date: &str, key: &str| {
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+            let symptom = Symptom {
+                date,
+                symptom_type: key.to_string(),
+                severity: 2,
+                created_at: None,
+                updated_at: None,
+            };
+            (date, vec![symptom])
+        };
+        // Cramps on day 1 of both past cycles, headache on day 2 of one
+        let data = AppData {
+            cycles,
+            symptoms: [
+                symptom("2026-01-01", symptoms::CRAMPS),
+                symptom("2026-01-29", symptoms::CRAMPS),
+                symptom("2026-01-30", symptoms::HEADACHE),
+            ]
+            .into(),
+            ..AppData::default()
+        };
+
+        let today = NaiveDate::from_ymd_opt(2026, 3, 24).unwrap();
+        let forecast = symptom_forecast(&data, today, 3);
+        // Next period predicted Mar 26
+        assert_eq!(forecast.len(), 3);
+        assert_eq!(forecast[0].cycle_day, 28);
+        assert!(forecast[0].symptoms.is_empty());
+        assert_eq!(forecast[1].cycle_day, 1);
+        assert_eq!(forecast[1].symptoms[0].symptom_type, symptoms::CRAMPS);
+        assert_eq!(forecast[1].symptoms[0].probability, 1.0);
+        assert_eq!(forecast[2].symptoms[0].probability, 0.5);
+    }
+
+    #[test]
+    fn trend_detects_lengthening_cycles() {
+        let lengthening: Vec<i64> = (0..14).map(|i| 26 + i / 3 + i % 2).collect();
+        let trend = cycle_stats(&cycles_with_lengths(&lengthening), &AppSettings::default())
+            .trend
+            .unwrap();
+        assert_eq!(trend.cycles, 14);
+        assert!(trend.significant);
+        assert!(trend.days_per_year > 2.0);
+
+        let steady: Vec<i64> = (0..14).map(|i| 28 + i % 2).collect();
+        let trend = cycle_stats(&cycles_with_lengths(&steady), &AppSettings::default())
+            .trend
+            .unwrap();
+        assert!(!trend.significant);
+
+        let short = cycles_with_lengths(&[28; 10]);
+        assert!(cycle_stats(&short, &AppSettings::default()).trend.is_none());
+    }
+
+    #[test]
+    fn irregularity_scored_from_recent_lengths() {
+        let stats = cycle_stats(
+            &cycles_with_lengths(&[28, 29, 28, 28]),
+            &AppSettings::default(),
+        );
+        let irregularity = stats.irregularity.unwrap();
+        assert_eq!(irregularity.band, IrregularityBand::Regular);
+        assert!(irregularity.score < 10);
+
+        let stats = cycle_stats(
+            &cycles_with_lengths(&[25, 33, 27, 36]),
+            &AppSettings::default(),
+        );
+        assert_eq!(
+            stats.irregularity.unwrap().band,
+            IrregularityBand::Irregular
+        );
+
+        // Scored after each cycle once there are three lengths
+        let history: Vec<IrregularityBand> = stats
+            .irregularity_history
+            .iter()
+            .map(|p| p.irregularity.band)
+            .collect();
+        assert_eq!(
+            history,
+            [
+                IrregularityBand::SomewhatIrregular,
+                IrregularityBand::Irregular
+            ]
+        );
+
+        let stats = cycle_stats(&cycles_with_lengths(&[28, 29]), &AppSettings::default());
+        assert!(stats.irregularity.is_none());
+        assert!(stats.irregularity_history.is_empty());
+    }
+
     #[test]
     fn fertility_window_calculated() {
         let cycles = vec![
             make_cycle("2026-01-01", "2026-01-05"),
             make_cycle("2026-01-29", "2026-02-02"),
         ];
-        let fw = fertility_window(&cycles).unwrap();
+        let data = AppData {
+            cycles,
+            ..AppData::default()
+        };
+        let fw = fertility_window(&data).unwrap();
         // Predicted period: Feb 26. Ovulation: Feb 26 - 14 = Feb 12
         assert_eq!(
             fw.ovulation_day,
@@ -219,9 +1610,310 @@ mod tests {
             make_cycle("2026-01-01", "2026-01-05"),
             make_cycle("2026-01-29", "2026-02-02"),
         ];
-        let stats = cycle_stats(&cycles);
+        let stats = cycle_stats(&cycles, &AppSettings::default());
         assert_eq!(stats.total_cycles, 2);
         assert_eq!(stats.avg_cycle_length, Some(28.0));
         assert_eq!(stats.avg_period_length, Some(5.0));
     }
+
+    #[test]
+    fn spread_of_lengths() {
+        assert!(spread(&[]).is_none());
+        let lengths = spread(&[26.0, 28.0, 28.0, 29.0, 34.0]).unwrap();
+        assert_eq!(lengths.median, 28.0);
+        assert_eq!(lengths.lower_quartile, 28.0);
+        assert_eq!(lengths.upper_quartile, 29.0);
+        assert_eq!(lengths.interquartile_range, 1.0);
+        assert!((lengths.std_dev - 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn pill_predicts_withdrawal_bleed_in_break() {
+        let pack_start = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 1, 10).unwrap();
+        let pred = pill_predictions(pack_start, 21, 7, today, 1).remove(0);
+        // Break runs Jan 22 - Jan 28, bleed expected from Jan 24
+        assert_eq!(
+            pred.predicted_start,
+            NaiveDate::from_ymd_opt(2026, 1, 24).unwrap()
+        );
+        assert_eq!(
+            pred.predicted_end,
+            NaiveDate::from_ymd_opt(2026, 1, 28).unwrap()
+        );
+        assert_eq!(pred.bleed_kind, BleedKind::WithdrawalBleed);
+
+        // After the break has ended, the next pack's break is predicted
+        let today = NaiveDate::from_ymd_opt(2026, 1, 29).unwrap();
+        let pred = pill_predictions(pack_start, 21, 7, today, 1).remove(0);
+        assert_eq!(
+            pred.predicted_start,
+            NaiveDate::from_ymd_opt(2026, 2, 21).unwrap()
+        );
+    }
+
+    #[test]
+    fn contraception_suppresses_fertility_window() {
+        let cycles = vec![
+            make_cycle("2026-01-01", "2026-01-05"),
+            make_cycle("2026-01-29", "2026-02-02"),
+        ];
+        let settings = AppSettings {
+            contraception_mode: ContraceptionMode::Continuous,
+            ..AppSettings::default()
+        };
+        assert!(predict(&cycles, &settings).is_none());
+        assert_eq!(
+            cycle_stats(&cycles, &settings).bleed_kind,
+            BleedKind::Bleeding
+        );
+        let data = AppData {
+            cycles,
+            settings,
+            ..AppData::default()
+        };
+        assert!(fertility_window(&data).is_none());
+    }
+
+    #[test]
+    fn perimenopause_excludes_skipped_cycles() {
+        let cycles = vec![
+            make_cycle("2026-01-01", "2026-01-05"),
+            make_cycle("2026-01-29", "2026-02-02"),
+            make_cycle("2026-05-01", "2026-05-04"),
+        ];
+        let settings = AppSettings {
+            perimenopause_mode: true,
+            ..AppSettings::default()
+        };
+        let stats = cycle_stats(&cycles, &settings);
+        assert_eq!(stats.avg_cycle_length, Some(28.0));
+        assert_eq!(stats.longest_cycle, Some(92));
+
+        let pred = predict(&cycles, &settings).unwrap();
+        assert_eq!(
+            pred.predicted_start,
+            NaiveDate::from_ymd_opt(2026, 5, 29).unwrap()
+        );
+    }
+
+    #[test]
+    fn perimenopause_widens_likely_start_window() {
+        let cycles = vec![
+            make_cycle("2026-01-01", "2026-01-05"),
+            make_cycle("2026-01-29", "2026-02-02"),
+            make_cycle("2026-02-27", "2026-03-03"),
+        ];
+        let width = |perimenopause_mode| {
+            let settings = AppSettings {
+                perimenopause_mode,
+                ..AppSettings::default()
+            };
+            let pred = predict(&cycles, &settings).unwrap();
+            (pred.latest_likely_start - pred.earliest_likely_start).num_days()
+        };
+        assert_eq!(width(false), 2);
+        assert_eq!(width(true), 2 * PERIMENOPAUSE_MIN_MARGIN_DAYS);
+    }
+
+    #[test]
+    fn perimenopause_stats_computed() {
+        let cycles = vec![
+            make_cycle("2026-01-01", "2026-01-05"),
+            make_cycle("2026-04-10", "2026-04-12"),
+        ];
+        let symptoms = [(2026, 6, 20), (2026, 6, 25)]
+            .into_iter()
+            .map(|(y, m, d)| {
+                let date = NaiveDate::from_ymd_opt(y, m, d).unwrap();
+                let hot_flash = Symptom {
+                    date,
+                    symptom_type: symptoms::HOT_FLASH.to_string(),
+                    severity: 2,
+                    created_at: None,
+                    updated_at: None,
+                };
+                (date, vec![hot_flash])
+            })
+            .collect();
+        let today = NaiveDate::from_ymd_opt(2026, 7, 1).unwrap();
+        let stats = perimenopause_stats(&cycles, &symptoms, today);
+        assert_eq!(stats.skipped_cycles, 1);
+        assert_eq!(stats.months_since_last_period, Some(2));
+        assert_eq!(stats.hot_flashes_per_week, 0.5);
+    }
+
+    fn cervix_log(date: &str, height: CervixHeight, openness: CervixOpenness) -> DayLog {
+        DayLog {
+            cervix: Some(CervixObservation {
+                height,
+                firmness: if height == CervixHeight::High {
+                    CervixFirmness::Soft
+                } else {
+                    CervixFirmness::Firm
+                },
+                openness,
+            }),
+            ..DayLog::new(NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap())
+        }
+    }
+
+    #[test]
+    fn cervix_peak_anchors_ovulation() {
+        let cycles = vec![
+            make_cycle("2026-01-01", "2026-01-05"),
+            make_cycle("2026-01-29", "2026-02-02"),
+        ];
+        let mut day_logs: BTreeMap<_, _> = [
+            cervix_log(
+                "2026-02-08",
+                CervixHeight::Medium,
+                CervixOpenness::PartlyOpen,
+            ),
+            cervix_log("2026-02-09", CervixHeight::High, CervixOpenness::Open),
+            cervix_log("2026-02-10", CervixHeight::High, CervixOpenness::Open),
+        ]
+        .into_iter()
+        .map(|log| (log.date, log))
+        .collect();
+        let data = AppData {
+            cycles: cycles.clone(),
+            day_logs: day_logs.clone(),
+            ..AppData::default()
+        };
+        // Peak not yet confirmed by a closing cervix: calendar estimate
+        let fw = fertility_window(&data).unwrap();
+        assert_eq!(fw.basis, OvulationBasis::Calendar);
+
+        let closed = cervix_log("2026-02-11", CervixHeight::Low, CervixOpenness::Closed);
+        day_logs.insert(closed.date, closed);
+        let data = AppData {
+            cycles,
+            day_logs,
+            ..AppData::default()
+        };
+        let fw = fertility_window(&data).unwrap();
+        assert_eq!(fw.basis, OvulationBasis::Cervix);
+        assert_eq!(
+            fw.ovulation_day,
+            NaiveDate::from_ymd_opt(2026, 2, 10).unwrap()
+        );
+    }
+
+    #[test]
+    fn phases_follow_cycle_boundaries() {
+        let data = AppData {
+            cycles: vec![
+                make_cycle("2026-01-01", "2026-01-05"),
+                make_cycle("2026-01-29", "2026-02-02"),
+            ],
+            ..AppData::default()
+        };
+        let info = |d: &str| phase_info(NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap(), &data);
+        let phase = |d: &str| info(d).map(|i| i.phase);
+        // Next period Jan 29, so ovulation around Jan 15
+        assert_eq!(phase("2025-12-20"), None);
+        assert_eq!(phase("2026-01-03"), Some(CyclePhase::Menstrual));
+        assert_eq!(phase("2026-01-10"), Some(CyclePhase::Follicular));
+        assert_eq!(phase("2026-01-15"), Some(CyclePhase::Ovulatory));
+        assert_eq!(phase("2026-01-20"), Some(CyclePhase::Luteal));
+
+        let luteal = info("2026-01-20").unwrap();
+        assert_eq!(luteal.cycle_day, 20);
+        // Luteal phase starts Jan 17, two days after ovulation
+        assert_eq!(luteal.phase_day, 4);
+        assert_eq!(luteal.ovulation_basis, Some(OvulationBasis::Calendar));
+    }
+
+    #[test]
+    fn libido_averaged_by_phase() {
+        let rated = |date: &str, libido: u8| DayLog {
+            libido: Some(libido),
+            ..DayLog::new(NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap())
+        };
+        let data = AppData {
+            cycles: vec![
+                make_cycle("2026-01-01", "2026-01-05"),
+                make_cycle("2026-01-29", "2026-02-02"),
+            ],
+            day_logs: [
+                rated("2026-01-14", 3),
+                rated("2026-01-15", 2),
+                rated("2026-01-22", 1),
+            ]
+            .into_iter()
+            .map(|log| (log.date, log))
+            .collect(),
+            ..AppData::default()
+        };
+        let stats = libido_by_phase(&data);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].phase, CyclePhase::Ovulatory);
+        assert_eq!(stats[0].average, 2.5);
+        assert_eq!(stats[1].phase, CyclePhase::Luteal);
+        assert_eq!(stats[1].samples, 1);
+    }
+
+    #[test]
+    fn rebuild_keeps_user_cycles() {
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut data = AppData::default();
+        for date in ["2026-01-01", "2026-01-02", "2026-02-01", "2026-02-02"] {
+            data.day_logs.insert(
+                day(date),
+                DayLog {
+                    flow_level: FlowLevel::Medium,
+                    ..DayLog::new(day(date))
+                },
+            );
+        }
+        rebuild_cycles(&mut data);
+        assert_eq!(data.cycles.len(), 2);
+        data.cycles[0].source = CycleSource::Confirmed;
+        let confirmed = data.cycles[0].id;
+        data.cycles.push(Cycle {
+            id: Uuid::new_v4(),
+            start_date: day("2026-01-31"),
+            end_date: Some(day("2026-02-04")),
+            source: CycleSource::Manual,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: None,
+            updated_at: None,
+        });
+
+        rebuild_cycles(&mut data);
+        let sources: Vec<_> = data.cycles.iter().map(|c| c.source).collect();
+        assert_eq!(sources, [CycleSource::Confirmed, CycleSource::Manual]);
+        assert_eq!(data.cycles[0].id, confirmed);
+    }
+
+    #[test]
+    fn gap_setting_controls_detection() {
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let mut day_logs = BTreeMap::new();
+        // Bleeding stops for three days mid-period
+        for date in ["2026-01-01", "2026-01-02", "2026-01-06", "2026-01-07"] {
+            day_logs.insert(
+                day(date),
+                DayLog {
+                    flow_level: FlowLevel::Medium,
+                    ..DayLog::new(day(date))
+                },
+            );
+        }
+        assert_eq!(detect_cycles(&day_logs, 2).len(), 2);
+        let fused = detect_cycles(&day_logs, 4);
+        assert_eq!(fused.len(), 1);
+        assert_eq!(fused[0].end_date, Some(day("2026-01-07")));
+
+        // Three days without bleeding may still be a pause within the
+        // period, so it stays open
+        let stopped = chrono::Local::now().date_naive() - chrono::Duration::days(3);
+        let mut recent = day_logs[&day("2026-01-07")].clone();
+        recent.date = stopped;
+        let day_logs = BTreeMap::from([(stopped, recent)]);
+        assert_eq!(detect_cycles(&day_logs, 2)[0].end_date, Some(stopped));
+        assert_eq!(detect_cycles(&day_logs, 4)[0].end_date, None);
+    }
 }
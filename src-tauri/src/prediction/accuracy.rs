@@ -0,0 +1,189 @@
+use chrono::NaiveDate;
+
+use crate::models::*;
+
+/// Upper bounds of the confidence bands predictions are grouped into.
+const CONFIDENCE_BANDS: [f32; 3] = [0.4, 0.7, 1.0];
+/// Settled predictions needed before confidence is taken from them rather
+/// than from the spread of cycle lengths.
+const MIN_CALIBRATION_SAMPLES: usize = 5;
+
+/// Settle open predictions whose period has since started, then record the
+/// current prediction for the next period if none is open. Only the first
+/// prediction made in each cycle is kept, so later refinements (from a
+/// thermal shift, say) don't flatter the numbers.
+pub fn record(data: &mut AppData, today: NaiveDate) {
+    let mut starts: Vec<NaiveDate> = data.cycles.iter().map(|c| c.start_date).collect();
+    starts.sort();
+
+    for record in data
+        .prediction_history
+        .iter_mut()
+        .filter(|r| r.actual_start.is_none())
+    {
+        if let Some(&actual) = starts.iter().find(|&&s| s > record.cycle_start) {
+            record.actual_start = Some(actual);
+        }
+    }
+
+    let Some(&cycle_start) = starts.last() else {
+        return;
+    };
+    if data
+        .prediction_history
+        .iter()
+        .any(|r| r.actual_start.is_none())
+    {
+        return;
+    }
+    if let Some(prediction) = super::upcoming(data, 1).into_iter().next() {
+        data.prediction_history.push(PredictionRecord {
+            made_on: today,
+            cycle_start,
+            predicted_start: prediction.predicted_start,
+            earliest_likely_start: prediction.earliest_likely_start,
+            latest_likely_start: prediction.latest_likely_start,
+            confidence: prediction.confidence,
+            actual_start: None,
+        });
+    }
+}
+
+/// Confidence for a prediction `k` cycles ahead with a likely window of
+/// `margin_days` either side: the share of settled predictions whose error
+/// would have fallen inside it. Errors are scaled by √k, as the window is.
+/// `None` until there are enough settled predictions.
+pub fn calibrated_confidence(
+    history: &[PredictionRecord],
+    margin_days: i64,
+    k: usize,
+) -> Option<f32> {
+    let errors: Vec<f64> = history
+        .iter()
+        .filter_map(|r| Some((r.actual_start? - r.predicted_start).num_days().abs() as f64))
+        .collect();
+    if errors.len() < MIN_CALIBRATION_SAMPLES {
+        return None;
+    }
+    let reach = margin_days as f64 / (k as f64).sqrt();
+    let hits = errors.iter().filter(|&&e| e <= reach).count();
+    Some((hits as f32 / errors.len() as f32).clamp(0.1, 0.95))
+}
+
+/// How far off settled predictions were, and whether their stated
+/// confidence matched how often the period started inside the likely window.
+pub fn report(history: &[PredictionRecord]) -> PredictionAccuracy {
+    let settled: Vec<(&PredictionRecord, i64)> = history
+        .iter()
+        .filter_map(|r| Some((r, (r.actual_start? - r.predicted_start).num_days())))
+        .collect();
+    let mean = |values: Vec<f32>| {
+        (!values.is_empty()).then(|| values.iter().sum::<f32>() / values.len() as f32)
+    };
+
+    let mut lower = 0.0;
+    let calibration = CONFIDENCE_BANDS
+        .iter()
+        .map(|&upper| {
+            let in_band: Vec<bool> = settled
+                .iter()
+                .filter(|(r, _)| r.confidence >= lower && r.confidence < upper)
+                .map(|(r, _)| r.hit())
+                .collect();
+            let hits = in_band.iter().filter(|&&hit| hit).count();
+            let bucket = CalibrationBucket {
+                confidence_low: lower,
+                confidence_high: upper,
+                predictions: in_band.len(),
+                hit_rate: (!in_band.is_empty()).then(|| hits as f32 / in_band.len() as f32),
+            };
+            lower = upper;
+            bucket
+        })
+        .collect();
+
+    PredictionAccuracy {
+        samples: settled.len(),
+        mean_absolute_error: mean(settled.iter().map(|(_, e)| e.abs() as f32).collect()),
+        bias: mean(settled.iter().map(|(_, e)| *e as f32).collect()),
+        calibration,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn date(d: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap()
+    }
+
+    fn cycle(start: &str, end: &str) -> Cycle {
+        Cycle {
+            id: Uuid::new_v4(),
+            start_date: date(start),
+            end_date: Some(date(end)),
+            source: CycleSource::Detected,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn records_then_settles_prediction() {
+        let mut data = AppData {
+            cycles: vec![
+                cycle("2026-01-01", "2026-01-05"),
+                cycle("2026-01-29", "2026-02-02"),
+            ],
+            ..AppData::default()
+        };
+        record(&mut data, date("2026-02-03"));
+        record(&mut data, date("2026-02-10"));
+        assert_eq!(data.prediction_history.len(), 1);
+        assert_eq!(
+            data.prediction_history[0].predicted_start,
+            date("2026-02-26")
+        );
+
+        // Period arrives two days late; a new prediction opens
+        data.cycles.push(cycle("2026-02-28", "2026-03-03"));
+        record(&mut data, date("2026-02-28"));
+        assert_eq!(data.prediction_history.len(), 2);
+        assert_eq!(
+            data.prediction_history[0].actual_start,
+            Some(date("2026-02-28"))
+        );
+
+        let accuracy = report(&data.prediction_history);
+        assert_eq!(accuracy.samples, 1);
+        assert_eq!(accuracy.mean_absolute_error, Some(2.0));
+        assert_eq!(accuracy.bias, Some(2.0));
+        let predictions: usize = accuracy.calibration.iter().map(|b| b.predictions).sum();
+        assert_eq!(predictions, 1);
+    }
+
+    #[test]
+    fn confidence_matches_past_hit_rate() {
+        let predicted = date("2026-01-29");
+        let history: Vec<PredictionRecord> = [0, -1, 1, 2, 5]
+            .iter()
+            .map(|&error| PredictionRecord {
+                made_on: date("2026-01-01"),
+                cycle_start: date("2026-01-01"),
+                predicted_start: predicted,
+                earliest_likely_start: predicted,
+                latest_likely_start: predicted,
+                confidence: 0.5,
+                actual_start: Some(predicted + chrono::Duration::days(error)),
+            })
+            .collect();
+        assert_eq!(calibrated_confidence(&history, 2, 1), Some(0.8));
+        assert_eq!(calibrated_confidence(&history, 4, 4), Some(0.8));
+        assert_eq!(calibrated_confidence(&history, 0, 1), Some(0.2));
+        assert_eq!(calibrated_confidence(&history[..4], 2, 1), None);
+    }
+}
@@ -0,0 +1,388 @@
+use std::collections::BTreeMap;
+use std::ops::{Range, RangeBounds};
+
+use chrono::NaiveDate;
+
+use crate::models::*;
+
+/// Luteal phases outside this range point at a misread chart rather than
+/// a real measurement.
+const PLAUSIBLE_LUTEAL_DAYS: std::ops::RangeInclusive<i64> = 7..=20;
+/// Number of earlier temperatures the shift has to rise above.
+const LOW_TEMPS: usize = 6;
+/// Number of consecutive raised temperatures that make a shift.
+const HIGH_TEMPS: usize = 3;
+/// How far the last raised temperature must clear the coverline, in Celsius.
+const THIRD_HIGH_MARGIN: f32 = 0.2;
+/// Nights either side a wearable temperature is smoothed over.
+const WEARABLE_SMOOTHING_NIGHTS: i64 = 1;
+
+/// Record the likely ovulation day on each completed cycle: observed signs
+/// when there are any, otherwise the user's luteal phase counted back from
+/// the next period. Open cycles are left blank until they end.
+pub fn annotate(data: &mut AppData) {
+    let luteal_days = super::luteal_phase_days(data);
+    let mut starts: Vec<NaiveDate> = data.cycles.iter().map(|c| c.start_date).collect();
+    starts.sort();
+
+    for cycle in &mut data.cycles {
+        let next_start = starts.iter().find(|&&s| s > cycle.start_date).copied();
+        cycle.ovulation = next_start.map(|next| {
+            let (day, basis) = observed(&data.day_logs, cycle.start_date..next).unwrap_or((
+                next - chrono::Duration::days(luteal_days),
+                OvulationBasis::Calendar,
+            ));
+            RecordedOvulation { day, basis }
+        });
+    }
+}
+
+/// Ovulation observed within `days` (one cycle), with what it is based on.
+/// A thermal shift confirms ovulation after the fact, so it wins over an LH
+/// peak, which in turn is more direct than a cervix peak.
+pub fn observed(
+    day_logs: &BTreeMap<NaiveDate, DayLog>,
+    days: Range<NaiveDate>,
+) -> Option<(NaiveDate, OvulationBasis)> {
+    if let Some(day) = thermal_shift(day_logs, days.clone()) {
+        return Some((day, OvulationBasis::Temperature));
+    }
+    if let Some(day) = lh_peak_day(day_logs, days.clone()) {
+        return Some((day + chrono::Duration::days(1), OvulationBasis::LhTest));
+    }
+    cervix_peak_day(day_logs, days).map(|day| (day, OvulationBasis::Cervix))
+}
+
+/// Ovulation confirmed by hard evidence within `days`: a thermal shift, or
+/// failing that the day after an LH peak.
+pub fn confirmed(
+    day_logs: &BTreeMap<NaiveDate, DayLog>,
+    days: Range<NaiveDate>,
+) -> Option<NaiveDate> {
+    thermal_shift(day_logs, days.clone())
+        .or_else(|| lh_peak_day(day_logs, days).map(|day| day + chrono::Duration::days(1)))
+}
+
+/// Luteal phase lengths, oldest first, of every completed cycle with a
+/// confirmed ovulation: days from ovulation to the next period.
+pub fn luteal_lengths(cycles: &[Cycle], day_logs: &BTreeMap<NaiveDate, DayLog>) -> Vec<i64> {
+    let mut starts: Vec<NaiveDate> = cycles.iter().map(|c| c.start_date).collect();
+    starts.sort();
+    starts
+        .windows(2)
+        .filter_map(|w| {
+            let ovulation = confirmed(day_logs, w[0]..w[1])?;
+            Some((w[1] - ovulation).num_days())
+        })
+        .filter(|days| PLAUSIBLE_LUTEAL_DAYS.contains(days))
+        .collect()
+}
+
+/// The LH peak: the last positive test of the first run of positives.
+/// A run is broken only by a logged negative, not by untested days, and
+/// ends with the last test logged so far.
+pub fn lh_peak_day(
+    day_logs: &BTreeMap<NaiveDate, DayLog>,
+    days: impl RangeBounds<NaiveDate>,
+) -> Option<NaiveDate> {
+    let mut peak = None;
+    for (&date, result) in day_logs
+        .range(days)
+        .filter_map(|(date, l)| l.lh_test.map(|r| (date, r)))
+    {
+        match result {
+            LhTestResult::Positive => peak = Some(date),
+            LhTestResult::Negative if peak.is_some() => break,
+            LhTestResult::Negative => {}
+        }
+    }
+    peak
+}
+
+/// Ovulation day by the sympto-thermal three-over-six rule: three
+/// consecutive temperatures above the highest of the six before them (the
+/// coverline), the third at least 0.2 °C above it. Ovulation is taken as
+/// the day before the first raised temperature. Days without a temperature,
+/// or with a disturbed one, are skipped rather than breaking the sequence.
+/// Wearable temperatures are smoothed first (see [`smooth_wearable`]).
+pub fn thermal_shift(
+    day_logs: &BTreeMap<NaiveDate, DayLog>,
+    days: impl RangeBounds<NaiveDate>,
+) -> Option<NaiveDate> {
+    coverline(day_logs, days).map(|(day, _)| day)
+}
+
+/// The ovulation day found by [`thermal_shift`] with the coverline, in
+/// Celsius, that the shift cleared.
+pub fn coverline(
+    day_logs: &BTreeMap<NaiveDate, DayLog>,
+    days: impl RangeBounds<NaiveDate>,
+) -> Option<(NaiveDate, f32)> {
+    let readings: Vec<(NaiveDate, f32, TemperatureSource)> = day_logs
+        .range(days)
+        .filter(|(_, l)| l.bbt_disturbances.is_empty())
+        .filter_map(|(&date, l)| l.bbt.map(|t| (date, t, l.bbt_source)))
+        .collect();
+    let temps = smooth_wearable(&readings);
+
+    (LOW_TEMPS..=temps.len().saturating_sub(HIGH_TEMPS)).find_map(|i| {
+        let coverline = temps[i - LOW_TEMPS..i]
+            .iter()
+            .map(|&(_, t)| t)
+            .fold(f32::MIN, f32::max);
+        let highs = &temps[i..i + HIGH_TEMPS];
+        let shifted = highs.iter().all(|&(_, t)| t > coverline)
+            && highs[HIGH_TEMPS - 1].1 >= coverline + THIRD_HIGH_MARGIN;
+        shifted.then(|| (highs[0].0 - chrono::Duration::days(1), coverline))
+    })
+}
+
+/// Each wearable temperature replaced by the median of it and the wearable
+/// readings from the nights either side. A night-long reading moves with
+/// sleep and room temperature, so one restless night would otherwise look
+/// like a shift or lift the coverline; the median drops such a night while
+/// keeping a real rise on the day it happened.
+fn smooth_wearable(readings: &[(NaiveDate, f32, TemperatureSource)]) -> Vec<(NaiveDate, f32)> {
+    readings
+        .iter()
+        .map(|&(date, t, source)| {
+            if source != TemperatureSource::Wearable {
+                return (date, t);
+            }
+            let mut window: Vec<f32> = readings
+                .iter()
+                .filter(|&&(d, _, s)| {
+                    s == TemperatureSource::Wearable
+                        && (d - date).num_days().abs() <= WEARABLE_SMOOTHING_NIGHTS
+                })
+                .map(|&(_, t, _)| t)
+                .collect();
+            window.sort_by(f32::total_cmp);
+            let mid = window.len() / 2;
+            let median = if window.len().is_multiple_of(2) {
+                (window[mid - 1] + window[mid]) / 2.0
+            } else {
+                window[mid]
+            };
+            (date, median)
+        })
+        .collect()
+}
+
+/// The cervix peak day within `days`: the last day the cervix was high,
+/// soft and open, once a later check shows it closing again. Ovulation
+/// happens around this day.
+fn cervix_peak_day(
+    day_logs: &BTreeMap<NaiveDate, DayLog>,
+    days: Range<NaiveDate>,
+) -> Option<NaiveDate> {
+    let observations: Vec<(NaiveDate, &CervixObservation)> = day_logs
+        .range(days)
+        .filter_map(|(&date, l)| l.cervix.as_ref().map(|c| (date, c)))
+        .collect();
+
+    let peak_index = observations.iter().rposition(|(_, c)| is_peak_cervix(c))?;
+    // Without a following non-peak check, the peak may still be ongoing
+    observations.get(peak_index + 1)?;
+    Some(observations[peak_index].0)
+}
+
+fn is_peak_cervix(observation: &CervixObservation) -> bool {
+    observation.height == CervixHeight::High
+        && observation.firmness == CervixFirmness::Soft
+        && observation.openness == CervixOpenness::Open
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lh_tests(start: &str, results: &[LhTestResult]) -> BTreeMap<NaiveDate, DayLog> {
+        let mut logs = chart(start, &vec![36.5; results.len()]);
+        for (log, &result) in logs.values_mut().zip(results) {
+            log.lh_test = Some(result);
+        }
+        logs
+    }
+
+    fn chart(start: &str, temps: &[f32]) -> BTreeMap<NaiveDate, DayLog> {
+        let start = NaiveDate::parse_from_str(start, "%Y-%m-%d").unwrap();
+        temps
+            .iter()
+            .enumerate()
+            .map(|(i, &t)| {
+                let date = start + chrono::Duration::days(i as i64);
+                let log = DayLog {
+                    bbt: Some(t),
+                    ..DayLog::new(date)
+                };
+                (date, log)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn three_over_six_finds_shift() {
+        let logs = chart(
+            "2026-03-05",
+            &[
+                36.4, 36.5, 36.3, 36.4, 36.5, 36.4, 36.45, 36.6, 36.65, 36.75, 36.8,
+            ],
+        );
+        // Coverline 36.5; 36.6, 36.65, 36.75 from Mar 12 clear it
+        assert_eq!(
+            thermal_shift(&logs, ..),
+            NaiveDate::from_ymd_opt(2026, 3, 11)
+        );
+    }
+
+    #[test]
+    fn no_shift_without_margin_or_enough_temps() {
+        // Three raised temps, but the third is only 0.1 above the coverline
+        let logs = chart(
+            "2026-03-05",
+            &[36.4, 36.5, 36.3, 36.4, 36.5, 36.4, 36.55, 36.6, 36.6],
+        );
+        assert_eq!(thermal_shift(&logs, ..), None);
+
+        let logs = chart("2026-03-05", &[36.4, 36.4, 36.8, 36.9]);
+        assert_eq!(thermal_shift(&logs, ..), None);
+    }
+
+    #[test]
+    fn disturbed_temperature_skipped() {
+        let mut logs = chart(
+            "2026-03-05",
+            &[
+                36.4, 36.5, 36.3, 36.4, 36.5, 36.4, 36.45, 36.6, 36.65, 36.75, 36.8,
+            ],
+        );
+        // A feverish reading would otherwise raise the coverline past the shift
+        let fever = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        let log = logs.get_mut(&fever).unwrap();
+        log.bbt = Some(37.2);
+        log.bbt_disturbances = vec![BbtDisturbance::Illness];
+        assert_eq!(
+            coverline(&logs, ..),
+            Some((NaiveDate::from_ymd_opt(2026, 3, 11).unwrap(), 36.5))
+        );
+    }
+
+    #[test]
+    fn wearable_spike_smoothed_out() {
+        let temps = [
+            36.4, 36.4, 36.4, 36.75, 36.4, 36.4, 36.4, 36.4, 36.8, 36.8, 36.9, 36.9, 36.9,
+        ];
+        // Read as thermometer temperatures, the one warm night lifts the
+        // coverline above the shift
+        let mut logs = chart("2026-03-05", &temps);
+        assert_eq!(thermal_shift(&logs, ..), None);
+
+        for log in logs.values_mut() {
+            log.bbt_source = TemperatureSource::Wearable;
+        }
+        assert_eq!(
+            thermal_shift(&logs, ..),
+            NaiveDate::from_ymd_opt(2026, 3, 12)
+        );
+    }
+
+    #[test]
+    fn luteal_lengths_from_confirmed_ovulations() {
+        let cycle = |start: &str| Cycle {
+            id: uuid::Uuid::new_v4(),
+            start_date: NaiveDate::parse_from_str(start, "%Y-%m-%d").unwrap(),
+            end_date: None,
+            source: CycleSource::Detected,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let mut logs = chart(
+            "2026-03-05",
+            &[
+                36.4, 36.5, 36.3, 36.4, 36.5, 36.4, 36.45, 36.6, 36.65, 36.75,
+            ],
+        );
+        logs.extend(lh_tests(
+            "2026-04-10",
+            &[LhTestResult::Positive, LhTestResult::Negative],
+        ));
+        // Ovulation Mar 11 and Apr 11; the last cycle has no next period yet
+        let cycles = [
+            cycle("2026-02-26"),
+            cycle("2026-03-24"),
+            cycle("2026-04-23"),
+        ];
+        assert_eq!(luteal_lengths(&cycles, &logs), [13, 12]);
+    }
+
+    #[test]
+    fn annotates_completed_cycles() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let cycle = |start: &str| Cycle {
+            id: uuid::Uuid::new_v4(),
+            start_date: date(start),
+            end_date: Some(date(start) + chrono::Duration::days(4)),
+            source: CycleSource::Detected,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let mut data = AppData {
+            cycles: vec![
+                cycle("2026-02-01"),
+                cycle("2026-02-26"),
+                cycle("2026-03-24"),
+            ],
+            day_logs: chart(
+                "2026-03-05",
+                &[
+                    36.4, 36.5, 36.3, 36.4, 36.5, 36.4, 36.45, 36.6, 36.65, 36.75,
+                ],
+            ),
+            ..AppData::default()
+        };
+        annotate(&mut data);
+        let recorded: Vec<Option<RecordedOvulation>> =
+            data.cycles.iter().map(|c| c.ovulation).collect();
+        assert_eq!(
+            recorded,
+            [
+                // No signs logged: 14 days back from the next period
+                Some(RecordedOvulation {
+                    day: date("2026-02-12"),
+                    basis: OvulationBasis::Calendar,
+                }),
+                Some(RecordedOvulation {
+                    day: date("2026-03-11"),
+                    basis: OvulationBasis::Temperature,
+                }),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn lh_peak_is_last_positive_of_first_surge() {
+        use LhTestResult::*;
+        let logs = lh_tests(
+            "2026-03-08",
+            &[Negative, Negative, Positive, Positive, Negative, Positive],
+        );
+        assert_eq!(lh_peak_day(&logs, ..), NaiveDate::from_ymd_opt(2026, 3, 11));
+        let since = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        assert_eq!(
+            observed(&logs, since..NaiveDate::MAX),
+            Some((
+                NaiveDate::from_ymd_opt(2026, 3, 12).unwrap(),
+                OvulationBasis::LhTest
+            ))
+        );
+
+        let logs = lh_tests("2026-03-08", &[Negative, Negative]);
+        assert_eq!(lh_peak_day(&logs, ..), None);
+    }
+}
@@ -0,0 +1,105 @@
+use chrono::NaiveDate;
+use rand::Rng;
+
+use crate::models::*;
+
+/// Fewer cycle lengths than this say too little about their distribution.
+const MIN_SIMULATION_LENGTHS: usize = 3;
+/// Each drawn length is moved up to this many days either way, so a short
+/// history with a handful of distinct lengths still gives a smooth spread.
+const JITTER_DAYS: i64 = 1;
+/// Central share of simulated starts covered by the likely range.
+const LIKELY_SHARE: f64 = 0.8;
+
+/// Simulate `runs` next-period starts by resampling the user's own cycle
+/// lengths, with no assumption about their shape. Returns `None` under
+/// hormonal contraception or with too little history.
+pub fn next_start(data: &AppData, runs: u32, rng: &mut impl Rng) -> Option<StartDistribution> {
+    if data.settings.contraception_mode != ContraceptionMode::None || runs == 0 {
+        return None;
+    }
+    let mut completed: Vec<&Cycle> = data
+        .cycles
+        .iter()
+        .filter(|c| c.end_date.is_some())
+        .collect();
+    completed.sort_by_key(|c| c.start_date);
+    let lengths: Vec<i64> = super::measure_lengths(&completed, &data.settings)
+        .iter()
+        .filter(|l| super::counts_towards_average(l, &data.settings))
+        .map(|l| l.days)
+        .collect();
+    if lengths.len() < MIN_SIMULATION_LENGTHS {
+        return None;
+    }
+    let last_start = data.cycles.iter().map(|c| c.start_date).max()?;
+
+    let mut starts: Vec<NaiveDate> = (0..runs)
+        .map(|_| {
+            let length = lengths[rng.gen_range(0..lengths.len())]
+                + rng.gen_range(-JITTER_DAYS..=JITTER_DAYS);
+            last_start + chrono::Duration::days(length)
+        })
+        .collect();
+    starts.sort();
+
+    let quantile = |q: f64| starts[((starts.len() - 1) as f64 * q).round() as usize];
+    let mut days: Vec<StartProbability> = Vec::new();
+    for &start in &starts {
+        match days.last_mut() {
+            Some(day) if day.date == start => day.probability += 1.0,
+            _ => days.push(StartProbability {
+                date: start,
+                probability: 1.0,
+            }),
+        }
+    }
+    for day in &mut days {
+        day.probability /= runs as f32;
+    }
+
+    Some(StartDistribution {
+        runs,
+        median_start: quantile(0.5),
+        earliest_likely_start: quantile((1.0 - LIKELY_SHARE) / 2.0),
+        latest_likely_start: quantile((1.0 + LIKELY_SHARE) / 2.0),
+        days,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use uuid::Uuid;
+
+    #[test]
+    fn skewed_history_gives_skewed_range() {
+        // Mostly 28 days, with the occasional much longer cycle
+        let mut start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mut data = AppData::default();
+        for length in [28, 28, 40, 28, 28, 28, 41, 28, 0] {
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: start,
+                end_date: Some(start + chrono::Duration::days(4)),
+                source: CycleSource::Detected,
+                include_in_stats: Some(true),
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+            start += chrono::Duration::days(length);
+        }
+        let last_start = data.cycles.last().unwrap().start_date;
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let result = next_start(&data, 5000, &mut rng).unwrap();
+        let total: f32 = result.days.iter().map(|d| d.probability).sum();
+        assert!((total - 1.0).abs() < 1e-3);
+        assert_eq!((result.median_start - last_start).num_days(), 28);
+        assert_eq!((result.earliest_likely_start - last_start).num_days(), 27);
+        assert!((result.latest_likely_start - last_start).num_days() >= 39);
+    }
+}
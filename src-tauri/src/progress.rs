@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use crate::error::CommandError;
+
+/// Event carrying a [`ProgressEvent`].
+pub const PROGRESS_EVENT: &str = "cykel://progress";
+
+/// How far a long-running operation has got.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressEvent {
+    pub operation_id: Uuid,
+    pub operation: &'static str,
+    pub stage: &'static str,
+    /// 0 to 100.
+    pub percent: u8,
+}
+
+/// Operations running now, and whether the user asked each to stop. Each
+/// operation checks in at every stage and gives up with
+/// [`CommandError::Cancelled`] once it has been asked.
+#[derive(Default)]
+pub struct Cancellations(Mutex<HashMap<Uuid, bool>>);
+
+impl Cancellations {
+    /// Ask the operation running under `operation_id` to stop. Ids of
+    /// operations that have already finished are ignored.
+    pub fn cancel(&self, operation_id: Uuid) -> Result<(), CommandError> {
+        if let Some(cancelled) = self.0.lock()?.get_mut(&operation_id) {
+            *cancelled = true;
+        }
+        Ok(())
+    }
+
+    fn start(&self, operation_id: Uuid) -> Result<(), CommandError> {
+        self.0.lock()?.insert(operation_id, false);
+        Ok(())
+    }
+
+    fn is_cancelled(&self, operation_id: Uuid) -> Result<bool, CommandError> {
+        Ok(self.0.lock()?.get(&operation_id) == Some(&true))
+    }
+
+    /// Drop `operation_id` once the operation is over, however it ended.
+    fn forget(&self, operation_id: Uuid) {
+        if let Ok(mut operations) = self.0.lock() {
+            operations.remove(&operation_id);
+        }
+    }
+}
+
+/// Reports one operation's stages to the frontend. The frontend picks the
+/// operation id so it can cancel before the command returns. The operation
+/// counts as running until this is dropped.
+pub struct Progress<'a> {
+    app: &'a AppHandle,
+    cancellations: &'a Cancellations,
+    operation_id: Uuid,
+    operation: &'static str,
+}
+
+impl<'a> Progress<'a> {
+    pub fn new(
+        app: &'a AppHandle,
+        cancellations: &'a Cancellations,
+        operation_id: Option<Uuid>,
+        operation: &'static str,
+    ) -> Result<Self, CommandError> {
+        let operation_id = operation_id.unwrap_or_else(Uuid::new_v4);
+        cancellations.start(operation_id)?;
+        Ok(Self {
+            app,
+            cancellations,
+            operation_id,
+            operation,
+        })
+    }
+
+    /// Announce that `stage` has started, or fail if the operation was
+    /// cancelled in the meantime.
+    pub fn stage(&self, stage: &'static str, percent: u8) -> Result<(), CommandError> {
+        if self.cancellations.is_cancelled(self.operation_id)? {
+            return Err(CommandError::Cancelled);
+        }
+        self.emit(stage, percent);
+        Ok(())
+    }
+
+    pub fn finish(&self) {
+        self.emit("done", 100);
+    }
+
+    fn emit(&self, stage: &'static str, percent: u8) {
+        // Progress is informational; a window that went away mustn't fail
+        // the operation
+        let _ = self.app.emit(
+            PROGRESS_EVENT,
+            ProgressEvent {
+                operation_id: self.operation_id,
+                operation: self.operation,
+                stage,
+                percent: percent.min(100),
+            },
+        );
+    }
+}
+
+impl Drop for Progress<'_> {
+    fn drop(&mut self) {
+        self.cancellations.forget(self.operation_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancellation_forgotten_once_over() {
+        let cancellations = Cancellations::default();
+        let id = Uuid::new_v4();
+        cancellations.start(id).unwrap();
+        assert!(!cancellations.is_cancelled(id).unwrap());
+        cancellations.cancel(id).unwrap();
+        assert!(cancellations.is_cancelled(id).unwrap());
+        cancellations.forget(id);
+        assert!(!cancellations.is_cancelled(id).unwrap());
+
+        // A cancel arriving after the end isn't kept around
+        cancellations.cancel(id).unwrap();
+        assert!(cancellations.0.lock().unwrap().is_empty());
+    }
+}
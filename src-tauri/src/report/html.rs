@@ -0,0 +1,97 @@
+use super::{Block, Report};
+use crate::models::FlowLevel;
+
+/// Inline so the file stands alone, and plain enough to print well.
+const STYLE: &str = "body{font-family:Helvetica,Arial,sans-serif;font-size:11pt;\
+max-width:50em;margin:2em auto;color:#111}\
+table{border-collapse:collapse;margin:.5em 0}\
+th,td{text-align:left;padding:.2em 1em .2em 0}\
+th{border-bottom:1px solid #888}\
+.flow span{display:inline-block;width:.9em;height:.9em;margin-right:2px;\
+border:1px solid #888;-webkit-print-color-adjust:exact;print-color-adjust:exact}";
+
+/// `report` as a standalone HTML page.
+pub fn render(report: &Report) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>{title}</h1>\n<p>{}</p>\n",
+        escape(&report.subtitle),
+        title = escape(&report.title),
+    );
+    for block in &report.blocks {
+        match block {
+            Block::Heading(heading) => out.push_str(&format!("<h2>{}</h2>\n", escape(heading))),
+            Block::Text(text) => out.push_str(&format!("<p>{}</p>\n", escape(text))),
+            Block::Table { header, rows } => {
+                out.push_str("<table>\n<tr>");
+                for cell in header {
+                    out.push_str(&format!("<th>{}</th>", escape(cell)));
+                }
+                out.push_str("</tr>\n");
+                for cells in rows {
+                    out.push_str("<tr>");
+                    for cell in cells {
+                        out.push_str(&format!("<td>{}</td>", escape(cell)));
+                    }
+                    out.push_str("</tr>\n");
+                }
+                out.push_str("</table>\n");
+            }
+            Block::FlowStrips(strips) => {
+                out.push_str("<table class=\"flow\">\n");
+                for (label, levels) in strips {
+                    out.push_str(&format!("<tr><td>{}</td><td>", escape(label)));
+                    for level in levels {
+                        out.push_str(&format!(
+                            "<span title=\"{level:?}\" style=\"background:{}\"></span>",
+                            flow_colour(level)
+                        ));
+                    }
+                    out.push_str("</td></tr>\n");
+                }
+                out.push_str("</table>\n");
+            }
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// The same grays as the PDF, darker for heavier flow.
+fn flow_colour(level: &FlowLevel) -> &'static str {
+    match level {
+        FlowLevel::None => "#fff",
+        FlowLevel::Light => "#bfbfbf",
+        FlowLevel::Medium => "#737373",
+        FlowLevel::Heavy => "#262626",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_escaped() {
+        let report = Report {
+            title: "Cycle report".to_string(),
+            subtitle: "2026".to_string(),
+            blocks: vec![Block::Table {
+                header: vec!["Symptom".to_string()],
+                rows: vec![vec!["<script>&".to_string()]],
+            }],
+        };
+        let html = render(&report);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<td>&lt;script&gt;&amp;</td>"));
+        assert!(!html.contains("<script>"));
+    }
+}
@@ -0,0 +1,89 @@
+use super::{Block, Report};
+use crate::models::FlowLevel;
+
+/// `report` as Markdown, with tables in the GitHub style.
+pub fn render(report: &Report) -> String {
+    let mut out = format!(
+        "# {}\n\n{}\n",
+        escape(&report.title),
+        escape(&report.subtitle)
+    );
+    for block in &report.blocks {
+        out.push('\n');
+        match block {
+            Block::Heading(heading) => out.push_str(&format!("## {}\n", escape(heading))),
+            Block::Text(text) => out.push_str(&format!("{}\n", escape(text))),
+            Block::Table { header, rows } => {
+                out.push_str(&row(header));
+                out.push_str(&row(&vec!["---".to_string(); header.len()]));
+                for cells in rows {
+                    out.push_str(&row(cells));
+                }
+            }
+            Block::FlowStrips(strips) => {
+                out.push_str("| Cycle start | Flow by day |\n| --- | --- |\n");
+                for (label, levels) in strips {
+                    let flow: String = levels.iter().map(flow_symbol).collect();
+                    out.push_str(&format!("| {} | `{flow}` |\n", escape(label)));
+                }
+                out.push_str("\n`·` none, `░` light, `▒` medium, `█` heavy\n");
+            }
+        }
+    }
+    out
+}
+
+fn flow_symbol(level: &FlowLevel) -> char {
+    match level {
+        FlowLevel::None => '·',
+        FlowLevel::Light => '░',
+        FlowLevel::Medium => '▒',
+        FlowLevel::Heavy => '█',
+    }
+}
+
+fn row(cells: &[String]) -> String {
+    let cells: Vec<String> = cells.iter().map(|c| escape(c)).collect();
+    format!("| {} |\n", cells.join(" | "))
+}
+
+/// `text` with Markdown's special characters taken literally.
+fn escape(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '|' | '<' | '>' | '[' | ']' | '#'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_and_strips_rendered() {
+        let report = Report {
+            title: "Cycle report".to_string(),
+            subtitle: "2026".to_string(),
+            blocks: vec![
+                Block::Table {
+                    header: vec!["Symptom".to_string(), "Days".to_string()],
+                    rows: vec![vec!["a|b *c*".to_string(), "2".to_string()]],
+                },
+                Block::FlowStrips(vec![(
+                    "2026-01-04".to_string(),
+                    vec![FlowLevel::Heavy, FlowLevel::Light, FlowLevel::None],
+                )]),
+            ],
+        };
+        let markdown = render(&report);
+        assert!(markdown.contains("| Symptom | Days |\n| --- | --- |\n| a\\|b \\*c\\* | 2 |\n"));
+        assert!(markdown.contains("| 2026-01-04 | `█░·` |"));
+    }
+}
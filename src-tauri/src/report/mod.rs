@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use crate::models::*;
+use crate::prediction;
+
+pub mod html;
+pub mod markdown;
+pub mod pdf;
+
+/// Days of flow shown per cycle in the flow chart.
+const FLOW_STRIP_DAYS: i64 = 10;
+/// Shown in place of a value there isn't enough data for.
+const MISSING: &str = "–";
+
+/// A report laid out as plain blocks, so each output format only has to
+/// know how to draw these.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub title: String,
+    pub subtitle: String,
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Heading(String),
+    Text(String),
+    Table {
+        header: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+    /// A label and the flow on each day from a cycle's start.
+    FlowStrips(Vec<(String, Vec<FlowLevel>)>),
+}
+
+/// The chosen `sections` of a report on cycles starting between `from`
+/// and `to`, in the order asked for. Notes are never included.
+pub fn build(
+    data: &AppData,
+    from: NaiveDate,
+    to: NaiveDate,
+    sections: &[ReportSection],
+    today: NaiveDate,
+) -> Report {
+    let mut all: Vec<&Cycle> = data.cycles.iter().collect();
+    all.sort_by_key(|c| c.start_date);
+    // Cycle length needs the next start, even when that is past `to`
+    let next_starts = all.iter().skip(1).map(|c| Some(c.start_date));
+    let cycles: Vec<(&Cycle, Option<NaiveDate>)> = all
+        .iter()
+        .copied()
+        .zip(next_starts.chain([None]))
+        .filter(|(c, _)| (from..=to).contains(&c.start_date))
+        .collect();
+
+    let mut blocks = Vec::new();
+    let mut done = Vec::new();
+    for section in sections {
+        if done.contains(section) {
+            continue;
+        }
+        done.push(section.clone());
+        match section {
+            ReportSection::CycleSummary => {
+                blocks.push(Block::Heading("Cycles".to_string()));
+                blocks.push(cycle_table(&cycles));
+            }
+            ReportSection::Stats => {
+                blocks.push(Block::Heading("Statistics".to_string()));
+                let in_range: Vec<Cycle> = cycles.iter().map(|(c, _)| (*c).clone()).collect();
+                blocks.push(stats_table(&prediction::cycle_stats(
+                    &in_range,
+                    &data.settings,
+                )));
+            }
+            ReportSection::SymptomFrequency => {
+                blocks.push(Block::Heading("Symptoms".to_string()));
+                blocks.push(symptom_table(data, from, to));
+            }
+            ReportSection::FlowChart => {
+                blocks.push(Block::Heading("Flow".to_string()));
+                blocks.push(flow_strips(data, &cycles));
+            }
+        }
+    }
+
+    Report {
+        title: "Cycle report".to_string(),
+        subtitle: format!("{from} to {to}, generated {today}"),
+        blocks,
+    }
+}
+
+fn cycle_table(cycles: &[(&Cycle, Option<NaiveDate>)]) -> Block {
+    if cycles.is_empty() {
+        return Block::Text("No cycles started in this period.".to_string());
+    }
+    Block::Table {
+        header: strings(&["Start", "End", "Period days", "Cycle days"]),
+        rows: cycles
+            .iter()
+            .map(|(c, next_start)| {
+                vec![
+                    c.start_date.to_string(),
+                    or_missing(c.end_date),
+                    or_missing(c.end_date.map(|end| (end - c.start_date).num_days() + 1)),
+                    or_missing(next_start.map(|next| (next - c.start_date).num_days())),
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn stats_table(stats: &CycleStats) -> Block {
+    let days = |value: Option<f32>| or_missing(value.map(|d| format!("{d:.1} days")));
+    let range = |spread: &Option<Spread>| {
+        or_missing(
+            spread
+                .as_ref()
+                .map(|s| format!("{:.0}–{:.0} days", s.lower_quartile, s.upper_quartile)),
+        )
+    };
+    Block::Table {
+        header: strings(&["Measure", "Value"]),
+        rows: vec![
+            vec!["Cycles".to_string(), stats.total_cycles.to_string()],
+            vec![
+                "Average cycle length".to_string(),
+                days(stats.avg_cycle_length),
+            ],
+            vec![
+                "Typical cycle range".to_string(),
+                range(&stats.cycle_length_spread),
+            ],
+            vec![
+                "Shortest cycle".to_string(),
+                or_missing(stats.shortest_cycle.map(|d| format!("{d} days"))),
+            ],
+            vec![
+                "Longest cycle".to_string(),
+                or_missing(stats.longest_cycle.map(|d| format!("{d} days"))),
+            ],
+            vec![
+                "Average period length".to_string(),
+                days(stats.avg_period_length),
+            ],
+            vec![
+                "Typical period range".to_string(),
+                range(&stats.period_length_spread),
+            ],
+        ],
+    }
+}
+
+/// Days each symptom was logged between `from` and `to`, most frequent
+/// first.
+fn symptom_table(data: &AppData, from: NaiveDate, to: NaiveDate) -> Block {
+    let mut counts: BTreeMap<&str, (usize, u32)> = BTreeMap::new();
+    for symptom in data.symptoms.range(from..=to).flat_map(|(_, s)| s) {
+        let entry = counts.entry(&symptom.symptom_type).or_default();
+        entry.0 += 1;
+        entry.1 += u32::from(symptom.severity);
+    }
+    if counts.is_empty() {
+        return Block::Text("No symptoms logged in this period.".to_string());
+    }
+
+    let mut counts: Vec<(&str, (usize, u32))> = counts.into_iter().collect();
+    counts.sort_by_key(|(_, (days, _))| std::cmp::Reverse(*days));
+    Block::Table {
+        header: strings(&["Symptom", "Days", "Average severity"]),
+        rows: counts
+            .into_iter()
+            .map(|(key, (days, severity))| {
+                let label = data
+                    .symptom_registry
+                    .iter()
+                    .find(|d| d.key == key)
+                    .map_or(key, |d| d.label.as_str());
+                vec![
+                    label.to_string(),
+                    days.to_string(),
+                    format!("{:.1}", severity as f32 / days as f32),
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn flow_strips(data: &AppData, cycles: &[(&Cycle, Option<NaiveDate>)]) -> Block {
+    if cycles.is_empty() {
+        return Block::Text("No cycles started in this period.".to_string());
+    }
+    Block::FlowStrips(
+        cycles
+            .iter()
+            .map(|(c, _)| {
+                let levels = (0..FLOW_STRIP_DAYS)
+                    .map(|i| c.start_date + chrono::Duration::days(i))
+                    .map(|date| {
+                        data.day_logs
+                            .get(&date)
+                            .map_or(FlowLevel::None, |l| l.flow_level.clone())
+                    })
+                    .collect();
+                (c.start_date.to_string(), levels)
+            })
+            .collect(),
+    )
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+fn or_missing(value: Option<impl ToString>) -> String {
+    value.map_or(MISSING.to_string(), |v| v.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn date(d: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn sections_built_over_range() {
+        let mut data = AppData::default();
+        for (start, end) in [
+            ("2025-12-07", "2025-12-11"),
+            ("2026-01-04", "2026-01-08"),
+            ("2026-02-01", "2026-02-04"),
+        ] {
+            data.cycles.push(Cycle {
+                id: Uuid::new_v4(),
+                start_date: date(start),
+                end_date: Some(date(end)),
+                source: CycleSource::Detected,
+                include_in_stats: None,
+                ovulation: None,
+                created_at: None,
+                updated_at: None,
+            });
+        }
+        for (day, severity) in [("2026-01-04", 3), ("2026-02-01", 1), ("2025-12-07", 2)] {
+            data.symptoms.insert(
+                date(day),
+                vec![Symptom {
+                    date: date(day),
+                    symptom_type: "cramps".to_string(),
+                    severity,
+                    created_at: None,
+                    updated_at: None,
+                }],
+            );
+        }
+
+        let sections = [
+            ReportSection::CycleSummary,
+            ReportSection::SymptomFrequency,
+            ReportSection::CycleSummary,
+        ];
+        let report = build(
+            &data,
+            date("2026-01-01"),
+            date("2026-02-28"),
+            &sections,
+            date("2026-03-01"),
+        );
+
+        assert_eq!(report.blocks.len(), 4);
+        let Block::Table { rows, .. } = &report.blocks[1] else {
+            panic!("cycle summary is a table");
+        };
+        assert_eq!(rows[0], ["2026-01-04", "2026-01-08", "5", "28"]);
+        assert_eq!(rows[1], ["2026-02-01", "2026-02-04", "4", MISSING]);
+        let Block::Table { rows, .. } = &report.blocks[3] else {
+            panic!("symptom frequency is a table");
+        };
+        assert_eq!(rows[0][1..], ["2", "2.0"]);
+    }
+}
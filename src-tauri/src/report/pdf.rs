@@ -0,0 +1,295 @@
+//! Just enough PDF to lay out a [`Report`]: A4 pages of text in the
+//! standard Helvetica fonts, rules and filled boxes. Nothing is embedded,
+//! so any viewer can open it without extra fonts.
+
+use super::{Block, Report};
+use crate::models::FlowLevel;
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const BODY_SIZE: f32 = 10.0;
+const LINE_HEIGHT: f32 = 15.0;
+/// Helvetica's average character width, as a share of the font size.
+/// Only used to decide where to wrap and truncate.
+const AVERAGE_CHAR_WIDTH: f32 = 0.5;
+const FLOW_CELL: f32 = 14.0;
+
+/// `report` as the bytes of a PDF file.
+pub fn render(report: &Report) -> Vec<u8> {
+    let mut pages = Pages::new();
+    pages.text(MARGIN, 20.0, true, &report.title);
+    pages.text(MARGIN, BODY_SIZE, false, &report.subtitle);
+
+    for block in &report.blocks {
+        match block {
+            Block::Heading(heading) => {
+                pages.gap(LINE_HEIGHT);
+                pages.ensure(3.0 * LINE_HEIGHT);
+                pages.text(MARGIN, 14.0, true, heading);
+            }
+            Block::Text(text) => {
+                for line in wrap(text, PAGE_WIDTH - 2.0 * MARGIN, BODY_SIZE) {
+                    pages.text(MARGIN, BODY_SIZE, false, &line);
+                }
+            }
+            Block::Table { header, rows } => {
+                let width = (PAGE_WIDTH - 2.0 * MARGIN) / header.len().max(1) as f32;
+                pages.row(header, width, true);
+                let y = pages.y + LINE_HEIGHT - BODY_SIZE - 3.0;
+                pages.rule(y);
+                for row in rows {
+                    pages.row(row, width, false);
+                }
+            }
+            Block::FlowStrips(strips) => {
+                for (label, levels) in strips {
+                    pages.ensure(LINE_HEIGHT);
+                    pages.text(MARGIN, BODY_SIZE, false, label);
+                    let y = pages.y + LINE_HEIGHT - BODY_SIZE;
+                    for (i, level) in levels.iter().enumerate() {
+                        let x = MARGIN + 90.0 + i as f32 * (FLOW_CELL + 2.0);
+                        pages.cell(x, y, flow_gray(level));
+                    }
+                }
+            }
+        }
+    }
+    pages.finish()
+}
+
+/// Darker for heavier flow; `None` for no flow, drawn as an outline.
+fn flow_gray(level: &FlowLevel) -> Option<f32> {
+    match level {
+        FlowLevel::None => None,
+        FlowLevel::Light => Some(0.75),
+        FlowLevel::Medium => Some(0.45),
+        FlowLevel::Heavy => Some(0.15),
+    }
+}
+
+/// Content streams for each page, filled top to bottom.
+struct Pages {
+    done: Vec<Vec<u8>>,
+    current: Vec<u8>,
+    /// Baseline of the next line.
+    y: f32,
+}
+
+impl Pages {
+    fn new() -> Self {
+        Self {
+            done: Vec::new(),
+            current: Vec::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    /// Start a new page unless `height` more fits on this one.
+    fn ensure(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.done.push(std::mem::take(&mut self.current));
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    fn gap(&mut self, height: f32) {
+        self.y -= height / 2.0;
+    }
+
+    /// A line of text at `x`, moving down a line.
+    fn text(&mut self, x: f32, size: f32, bold: bool, text: &str) {
+        self.ensure(LINE_HEIGHT);
+        self.put_text(x, size, bold, text);
+        self.y -= LINE_HEIGHT.max(size + 4.0);
+    }
+
+    fn put_text(&mut self, x: f32, size: f32, bold: bool, text: &str) {
+        let font = if bold { "F2" } else { "F1" };
+        self.current
+            .extend(format!("BT /{font} {size} Tf {x:.1} {:.1} Td (", self.y).bytes());
+        self.current.extend(encode(text));
+        self.current.extend(b") Tj ET\n");
+    }
+
+    /// Table cells `width` apart, each cut short to fit.
+    fn row(&mut self, cells: &[String], width: f32, bold: bool) {
+        self.ensure(LINE_HEIGHT);
+        for (i, cell) in cells.iter().enumerate() {
+            let x = MARGIN + i as f32 * width;
+            self.put_text(x, BODY_SIZE, bold, &truncate(cell, width - 6.0, BODY_SIZE));
+        }
+        self.y -= LINE_HEIGHT;
+    }
+
+    /// A thin line across the page at `y`.
+    fn rule(&mut self, y: f32) {
+        self.current.extend(
+            format!(
+                "0.5 w {MARGIN:.1} {y:.1} m {:.1} {y:.1} l S\n",
+                PAGE_WIDTH - MARGIN
+            )
+            .bytes(),
+        );
+    }
+
+    /// A square with its bottom left at `(x, y)`, filled with `gray`
+    /// (0 black, 1 white) or just outlined.
+    fn cell(&mut self, x: f32, y: f32, gray: Option<f32>) {
+        let size = FLOW_CELL - 4.0;
+        let op = match gray {
+            Some(gray) => format!("{gray:.2} g {x:.1} {y:.1} {size} {size} re f 0 g\n"),
+            None => format!("0.5 w {x:.1} {y:.1} {size} {size} re S\n"),
+        };
+        self.current.extend(op.bytes());
+    }
+
+    /// The finished file: catalog, page tree, the two fonts, then a page
+    /// object and content stream per page, and the cross-reference table.
+    fn finish(mut self) -> Vec<u8> {
+        self.done.push(self.current);
+        let page_count = self.done.len();
+
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {page_count} >>",
+                (0..page_count)
+                    .map(|i| format!("{} 0 R", 5 + 2 * i))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )
+            .into_bytes(),
+            font("Helvetica"),
+            font("Helvetica-Bold"),
+        ];
+        for (i, content) in self.done.into_iter().enumerate() {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                     /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    6 + 2 * i
+                )
+                .into_bytes(),
+            );
+            let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+            stream.extend(content);
+            stream.extend(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut out = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend(format!("{} 0 obj\n", i + 1).bytes());
+            out.extend(object);
+            out.extend(b"\nendobj\n");
+        }
+        let xref = out.len();
+        out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+        for offset in offsets {
+            out.extend(format!("{offset:010} 00000 n \n").bytes());
+        }
+        out.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+                objects.len() + 1
+            )
+            .bytes(),
+        );
+        out
+    }
+}
+
+fn font(name: &str) -> Vec<u8> {
+    format!("<< /Type /Font /Subtype /Type1 /BaseFont /{name} /Encoding /WinAnsiEncoding >>")
+        .into_bytes()
+}
+
+/// `text` in WinAnsi, escaped for a PDF string. Characters the standard
+/// fonts can't show become `?`.
+fn encode(text: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    for c in text.chars() {
+        let byte = match c {
+            '–' => 0x96,
+            '—' => 0x97,
+            '•' => 0x95,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            _ => b'?',
+        };
+        if matches!(byte, b'(' | b')' | b'\\') {
+            out.push(b'\\');
+        }
+        out.push(byte);
+    }
+    out
+}
+
+fn max_chars(width: f32, size: f32) -> usize {
+    (width / (size * AVERAGE_CHAR_WIDTH)) as usize
+}
+
+fn truncate(text: &str, width: f32, size: f32) -> String {
+    let max = max_chars(width, size);
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+/// `text` broken into lines at spaces, as near `width` as the estimate
+/// allows.
+fn wrap(text: &str, width: f32, size: f32) -> Vec<String> {
+    let max = max_chars(width, size);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn objects_found_at_xref_offsets() {
+        let report = Report {
+            title: "Cycle report".to_string(),
+            subtitle: "(test)".to_string(),
+            blocks: (0..80)
+                .map(|i| Block::Text(format!("line {i} – déjà")))
+                .collect(),
+        };
+        let pdf = render(&report);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.contains("/Count 2"));
+        assert!(pdf.windows(8).any(|w| w == b"\\(test\\)"));
+
+        let xref = text.find("xref\n").unwrap();
+        let entries = text[xref..].lines().skip(3);
+        for (i, entry) in entries.take_while(|l| l.ends_with(" n ")).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            let object = format!("{} 0 obj", i + 1);
+            assert_eq!(&pdf[offset..offset + object.len()], object.as_bytes());
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use chrono::NaiveDate;
+
+use crate::models::*;
+
+/// Characters of note text shown either side of a match.
+const SNIPPET_CONTEXT_CHARS: usize = 40;
+/// Matches returned at most, newest first.
+const MAX_SEARCH_RESULTS: usize = 200;
+
+/// Days whose notes contain `query` (ignoring case) and that pass every
+/// filter, newest first. An empty query matches on the filters alone.
+pub fn search(data: &AppData, query: &str, filters: &SearchFilters) -> Vec<SearchHit> {
+    let query = query.trim().to_lowercase();
+    let from = filters.from.unwrap_or(NaiveDate::MIN);
+    let to = filters.to.unwrap_or(NaiveDate::MAX);
+
+    let mut dates: Vec<NaiveDate> = data
+        .day_logs
+        .range(from..=to)
+        .map(|(&d, _)| d)
+        .chain(data.symptoms.range(from..=to).map(|(&d, _)| d))
+        .collect();
+    dates.sort();
+    dates.dedup();
+
+    dates
+        .into_iter()
+        .rev()
+        .filter_map(|date| {
+            let log = data.day_logs.get(&date);
+            let symptoms = data.symptoms.get(&date).map_or(&[][..], Vec::as_slice);
+
+            if let Some(flow_level) = &filters.flow_level {
+                if log.map(|l| &l.flow_level) != Some(flow_level) {
+                    return None;
+                }
+            }
+            let wanted = |s: &&Symptom| {
+                filters
+                    .symptom_type
+                    .as_ref()
+                    .is_none_or(|key| &s.symptom_type == key)
+                    && s.severity >= filters.min_severity.unwrap_or(0)
+            };
+            let symptom_filtered = filters.symptom_type.is_some() || filters.min_severity.is_some();
+            if symptom_filtered && !symptoms.iter().any(|s| wanted(&s)) {
+                return None;
+            }
+
+            let snippet = if query.is_empty() {
+                None
+            } else {
+                Some(snippet(&log?.notes, &query)?)
+            };
+            Some(SearchHit {
+                date,
+                snippet,
+                flow_level: log.map_or(FlowLevel::None, |l| l.flow_level.clone()),
+                symptom_types: symptoms.iter().map(|s| s.symptom_type.clone()).collect(),
+            })
+        })
+        .take(MAX_SEARCH_RESULTS)
+        .collect()
+}
+
+/// The first match of `query` (already lowercase) in `notes` with some
+/// context either side, or `None` when it doesn't occur.
+fn snippet(notes: &str, query: &str) -> Option<String> {
+    let chars: Vec<char> = notes.chars().collect();
+    let lower: Vec<char> = chars.iter().flat_map(|c| c.to_lowercase()).collect();
+    let query: Vec<char> = query.chars().collect();
+    let found = lower.windows(query.len()).position(|w| w == query)?;
+    // Lowercasing can change the length of some characters; fall back to
+    // showing the start of the note rather than a misplaced window
+    let at = if lower.len() == chars.len() { found } else { 0 };
+
+    let start = at.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let end = (at + query.len() + SNIPPET_CONTEXT_CHARS).min(chars.len());
+    let mut snippet: String = chars[start..end].iter().collect();
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(d: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap()
+    }
+
+    fn log(data: &mut AppData, day: &str, flow_level: FlowLevel, notes: &str) {
+        data.day_logs.insert(
+            date(day),
+            DayLog {
+                flow_level,
+                notes: notes.to_string(),
+                ..DayLog::new(date(day))
+            },
+        );
+    }
+
+    #[test]
+    fn notes_matched_with_filters() {
+        let mut data = AppData::default();
+        log(
+            &mut data,
+            "2026-01-03",
+            FlowLevel::Heavy,
+            "Started new Iron tablets",
+        );
+        log(
+            &mut data,
+            "2026-02-03",
+            FlowLevel::None,
+            "iron again, felt better",
+        );
+        log(&mut data, "2026-02-10", FlowLevel::None, "nothing much");
+        data.symptoms.insert(
+            date("2026-02-03"),
+            vec![Symptom {
+                date: date("2026-02-03"),
+                symptom_type: "fatigue".to_string(),
+                severity: 1,
+                created_at: None,
+                updated_at: None,
+            }],
+        );
+
+        let hits = search(&data, "IRON", &SearchFilters::default());
+        let dates: Vec<NaiveDate> = hits.iter().map(|h| h.date).collect();
+        assert_eq!(dates, [date("2026-02-03"), date("2026-01-03")]);
+        assert_eq!(hits[1].snippet.as_deref(), Some("Started new Iron tablets"));
+
+        let heavy = SearchFilters {
+            flow_level: Some(FlowLevel::Heavy),
+            ..SearchFilters::default()
+        };
+        assert_eq!(search(&data, "iron", &heavy).len(), 1);
+
+        let tired = SearchFilters {
+            symptom_type: Some("fatigue".to_string()),
+            min_severity: Some(2),
+            ..SearchFilters::default()
+        };
+        assert!(search(&data, "", &tired).is_empty());
+    }
+
+    #[test]
+    fn snippet_trimmed_around_match() {
+        let notes = format!("{}needle{}", "a".repeat(60), "b".repeat(60));
+        let snippet = snippet(&notes, "needle").unwrap();
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert_eq!(snippet.chars().count(), 2 + 40 + 6 + 40);
+    }
+}
@@ -0,0 +1,341 @@
+//! The data split into small encrypted files, for keeping in a folder that
+//! a file-sync tool such as Syncthing replicates:
+//!
+//! ```text
+//! salt         32 random bytes, the same for every file
+//! core.chunk   settings, cycles, the symptom registry and other undated data
+//! 2026-01.chunk  every dated record (day logs, symptoms, pain, pills,
+//!                appointments, lab results) of one month
+//! ```
+//!
+//! Each `.chunk` is nonce || ciphertext under one key derived from the
+//! passphrase and the salt, with the chunk's name as associated data so a
+//! file renamed or swapped for another month's fails to load. A save only rewrites the files whose contents
+//! changed, usually this month's and the core, so older months stay
+//! untouched and a conflict between devices is confined to one month.
+//! Conflict copies a sync tool leaves beside a chunk are merged in record
+//! by record on load (see [`merge`]) and then removed.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::StorageError;
+use crate::crypto::{self, Key};
+use crate::merge;
+use crate::models::*;
+use crate::prediction;
+
+pub const SALT_FILE: &str = "salt";
+const CORE_CHUNK: &str = "core";
+const CHUNK_EXTENSION: &str = "chunk";
+const MONTH_FORMAT: &str = "%Y-%m";
+
+/// A month's dated records.
+#[derive(Serialize, Deserialize, Default)]
+struct MonthChunk {
+    #[serde(default)]
+    day_logs: Vec<DayLog>,
+    #[serde(default)]
+    symptoms: Vec<Symptom>,
+    #[serde(default)]
+    pain: Vec<PainEntry>,
+    #[serde(default)]
+    pill_doses: Vec<PillDose>,
+    #[serde(default)]
+    appointments: Vec<Appointment>,
+    #[serde(default)]
+    lab_results: Vec<LabResult>,
+}
+
+impl MonthChunk {
+    fn add_to(self, data: &mut AppData) {
+        for log in self.day_logs {
+            data.day_logs.insert(log.date, log);
+        }
+        for symptom in self.symptoms {
+            data.symptoms.entry(symptom.date).or_default().push(symptom);
+        }
+        data.pain.extend(self.pain);
+        data.pill_doses.extend(self.pill_doses);
+        data.appointments.extend(self.appointments);
+        data.lab_results.extend(self.lab_results);
+    }
+}
+
+fn month(date: NaiveDate) -> String {
+    date.format(MONTH_FORMAT).to_string()
+}
+
+fn at(months: &mut BTreeMap<String, MonthChunk>, date: NaiveDate) -> &mut MonthChunk {
+    months.entry(month(date)).or_default()
+}
+
+fn chunk_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.{CHUNK_EXTENSION}"))
+}
+
+/// The month a file name is the chunk for, if it is one. Anything else in
+/// the folder, such as a sync tool's conflict copies, is left alone.
+fn month_of(path: &Path) -> Option<String> {
+    if path.extension()? != CHUNK_EXTENSION {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    NaiveDate::parse_from_str(&format!("{stem}-01"), "%Y-%m-%d").ok()?;
+    Some(stem.to_string())
+}
+
+/// The chunk `path` is a sync tool's conflict copy of, if it is one, such
+/// as `2026-01` for Syncthing's
+/// `2026-01.sync-conflict-20260105-101500-ABCDEFG.chunk`.
+fn conflict_copy_of(path: &Path) -> Option<&str> {
+    if path.extension()? != CHUNK_EXTENSION {
+        return None;
+    }
+    let (name, _) = path.file_stem()?.to_str()?.split_once(".sync-conflict-")?;
+    Some(name)
+}
+
+/// The core and each month's records, serialized.
+fn split(data: &AppData) -> Result<BTreeMap<String, Vec<u8>>, StorageError> {
+    let mut months: BTreeMap<String, MonthChunk> = BTreeMap::new();
+    for log in data.day_logs.values() {
+        at(&mut months, log.date).day_logs.push(log.clone());
+    }
+    for symptom in data.symptoms.values().flatten() {
+        at(&mut months, symptom.date).symptoms.push(symptom.clone());
+    }
+    for entry in &data.pain {
+        at(&mut months, entry.date).pain.push(entry.clone());
+    }
+    for dose in &data.pill_doses {
+        at(&mut months, dose.date).pill_doses.push(dose.clone());
+    }
+    for appointment in &data.appointments {
+        at(&mut months, appointment.date)
+            .appointments
+            .push(appointment.clone());
+    }
+    for result in &data.lab_results {
+        at(&mut months, result.date)
+            .lab_results
+            .push(result.clone());
+    }
+
+    let core = AppData {
+        day_logs: BTreeMap::new(),
+        symptoms: BTreeMap::new(),
+        pain: Vec::new(),
+        pill_doses: Vec::new(),
+        appointments: Vec::new(),
+        lab_results: Vec::new(),
+        ..data.clone()
+    };
+    let mut chunks = BTreeMap::from([(CORE_CHUNK.to_string(), serde_json::to_vec(&core)?)]);
+    for (month, chunk) in months {
+        chunks.insert(month, serde_json::to_vec(&chunk)?);
+    }
+    Ok(chunks)
+}
+
+fn salt(dir: &Path) -> Result<Vec<u8>, StorageError> {
+    let path = dir.join(SALT_FILE);
+    if path.exists() {
+        return Ok(fs::read(path)?);
+    }
+    let salt = crypto::new_salt();
+    fs::write(path, salt)?;
+    Ok(salt.to_vec())
+}
+
+/// Save into `dir`, rewriting only the chunks that changed and removing
+/// months that no longer have any records.
+pub fn save(dir: &Path, passphrase: &str, data: &AppData) -> Result<(), StorageError> {
+    fs::create_dir_all(dir)?;
+    let key = Key::derive(passphrase, &salt(dir)?)?;
+    let chunks = split(data)?;
+
+    for (name, plaintext) in &chunks {
+        let path = chunk_path(dir, name);
+        // A chunk that can't be read, e.g. written under an old
+        // passphrase, is simply replaced
+        let unchanged = fs::read(&path)
+            .ok()
+            .and_then(|sealed| crypto::open(&key, &sealed, name.as_bytes()).ok())
+            .is_some_and(|existing| &existing == plaintext);
+        if !unchanged {
+            fs::write(path, crypto::seal(&key, plaintext, name.as_bytes())?)?;
+        }
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if month_of(&path).is_some_and(|m| !chunks.contains_key(&m)) {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn load(dir: &Path, passphrase: &str) -> Result<AppData, StorageError> {
+    let key = Key::derive(passphrase, &fs::read(dir.join(SALT_FILE))?)?;
+    let core = crypto::open(
+        &key,
+        &fs::read(chunk_path(dir, CORE_CHUNK))?,
+        CORE_CHUNK.as_bytes(),
+    )?;
+    let mut data: AppData = serde_json::from_slice(&core)?;
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    paths.sort();
+    for path in &paths {
+        let Some(month) = month_of(path) else {
+            continue;
+        };
+        let plaintext = crypto::open(&key, &fs::read(path)?, month.as_bytes())?;
+        let chunk: MonthChunk = serde_json::from_slice(&plaintext)?;
+        chunk.add_to(&mut data);
+    }
+
+    let mut resolved = Vec::new();
+    for path in &paths {
+        let Some(name) = conflict_copy_of(path) else {
+            continue;
+        };
+        // A copy under another passphrase can't be read here; leave it
+        let Ok(plaintext) = crypto::open(&key, &fs::read(path)?, name.as_bytes()) else {
+            continue;
+        };
+        let theirs = match serde_json::from_slice::<AppData>(&plaintext) {
+            Ok(core) => core,
+            Err(_) => {
+                let mut theirs = AppData::default();
+                serde_json::from_slice::<MonthChunk>(&plaintext)?.add_to(&mut theirs);
+                theirs
+            }
+        };
+        data = merge::merge(&data, &theirs).0;
+        resolved.push(path);
+    }
+    if !resolved.is_empty() {
+        // Days merged in may start or extend a period
+        prediction::rebuild_cycles(&mut data);
+        save(dir, passphrase, &data)?;
+        for path in resolved {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(data)
+}
+
+/// Total size of the files in `dir`.
+pub fn size(dir: &Path) -> Result<u64, StorageError> {
+    let mut total = 0;
+    for entry in fs::read_dir(dir)? {
+        total += entry?.metadata()?.len();
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn day_log(date: NaiveDate, notes: &str) -> DayLog {
+        DayLog {
+            flow_level: FlowLevel::Light,
+            notes: notes.to_string(),
+            ..DayLog::new(date)
+        }
+    }
+
+    #[test]
+    fn only_changed_months_rewritten() {
+        let dir = std::env::temp_dir().join(format!("cykel-chunks-{}", uuid::Uuid::new_v4()));
+        let jan = NaiveDate::from_ymd_opt(2026, 1, 4).unwrap();
+        let feb = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+        let mut data = AppData::default();
+        data.day_logs.insert(jan, day_log(jan, "january"));
+        data.day_logs.insert(feb, day_log(feb, "february"));
+
+        save(&dir, "pass", &data).unwrap();
+        let modified = |name: &str| -> SystemTime {
+            fs::metadata(chunk_path(&dir, name))
+                .unwrap()
+                .modified()
+                .unwrap()
+        };
+        let january_written = modified("2026-01");
+        std::thread::sleep(std::time::Duration::from_millis(20));
+
+        data.day_logs.insert(feb, day_log(feb, "february, edited"));
+        save(&dir, "pass", &data).unwrap();
+        assert_eq!(modified("2026-01"), january_written);
+        assert_eq!(load(&dir, "pass").unwrap().day_logs, data.day_logs);
+
+        data.day_logs.remove(&feb);
+        fs::write(dir.join("2026-01.sync-conflict-x.chunk"), b"kept").unwrap();
+        save(&dir, "pass", &data).unwrap();
+        assert!(!chunk_path(&dir, "2026-02").exists());
+        assert!(dir.join("2026-01.sync-conflict-x.chunk").exists());
+        assert!(load(&dir, "wrong").is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn swapped_chunks_not_loaded() {
+        let dir = std::env::temp_dir().join(format!("cykel-chunks-{}", uuid::Uuid::new_v4()));
+        let jan = NaiveDate::from_ymd_opt(2026, 1, 4).unwrap();
+        let feb = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+        let mut data = AppData::default();
+        data.day_logs.insert(jan, day_log(jan, "january"));
+        data.day_logs.insert(feb, day_log(feb, "february"));
+        save(&dir, "pass", &data).unwrap();
+
+        let january = fs::read(chunk_path(&dir, "2026-01")).unwrap();
+        fs::copy(chunk_path(&dir, "2026-02"), chunk_path(&dir, "2026-01")).unwrap();
+        fs::write(chunk_path(&dir, "2026-02"), january).unwrap();
+        assert!(load(&dir, "pass").is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn conflict_copies_merged_and_removed() {
+        let dir = std::env::temp_dir().join(format!("cykel-chunks-{}", uuid::Uuid::new_v4()));
+        let jan = NaiveDate::from_ymd_opt(2026, 1, 4).unwrap();
+        let other = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+        let mut data = AppData::default();
+        let mut unbled = day_log(jan, "this device");
+        unbled.flow_level = FlowLevel::None;
+        data.day_logs.insert(jan, unbled);
+        save(&dir, "pass", &data).unwrap();
+
+        let key = Key::derive("pass", &fs::read(dir.join(SALT_FILE)).unwrap()).unwrap();
+        let theirs = MonthChunk {
+            day_logs: vec![day_log(other, "other device")],
+            ..MonthChunk::default()
+        };
+        let copy = dir.join("2026-01.sync-conflict-20260105-101500-ABCDEFG.chunk");
+        let sealed = crypto::seal(&key, &serde_json::to_vec(&theirs).unwrap(), b"2026-01").unwrap();
+        fs::write(&copy, sealed).unwrap();
+
+        let loaded = load(&dir, "pass").unwrap();
+        assert_eq!(loaded.day_logs.len(), 2);
+        assert!(!copy.exists());
+        // The other device's flow day starts a cycle
+        let starts: Vec<NaiveDate> = loaded.cycles.iter().map(|c| c.start_date).collect();
+        assert_eq!(starts, [other]);
+        assert_eq!(load(&dir, "pass").unwrap().day_logs, loaded.day_logs);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
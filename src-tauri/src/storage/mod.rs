@@ -0,0 +1,128 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::crypto;
+use crate::models::{AppData, StorageLayout};
+
+pub mod chunked;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("crypto error: {0}")]
+    Crypto(#[from] crypto::CryptoError),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("data directory not found")]
+    NoDataDir,
+}
+
+fn data_dir() -> Result<PathBuf, StorageError> {
+    let dir = dirs::data_local_dir()
+        .ok_or(StorageError::NoDataDir)?
+        .join("cykel");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Get the path to the encrypted data file.
+fn data_file_path() -> Result<PathBuf, StorageError> {
+    Ok(data_dir()?.join("data.cykel"))
+}
+
+/// The folder of [`chunked`] files, for pointing a sync tool at.
+fn chunk_dir_path() -> Result<PathBuf, StorageError> {
+    Ok(data_dir()?.join("chunks"))
+}
+
+/// How the data is stored, or `None` before setup.
+pub fn layout() -> Result<Option<StorageLayout>, StorageError> {
+    if chunk_dir_path()?.join(chunked::SALT_FILE).exists() {
+        Ok(Some(StorageLayout::MonthlyChunks))
+    } else if data_file_path()?.exists() {
+        Ok(Some(StorageLayout::SingleFile))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Check if a data file exists (i.e., app has been set up before).
+pub fn data_exists() -> Result<bool, StorageError> {
+    Ok(layout()?.is_some())
+}
+
+/// Size of the encrypted data in bytes, if there is any.
+pub fn data_file_size() -> Result<Option<u64>, StorageError> {
+    match layout()? {
+        Some(StorageLayout::SingleFile) => Ok(Some(fs::metadata(data_file_path()?)?.len())),
+        Some(StorageLayout::MonthlyChunks) => Ok(Some(chunked::size(&chunk_dir_path()?)?)),
+        None => Ok(None),
+    }
+}
+
+/// Save app data encrypted with the given passphrase, in whichever layout
+/// it is already stored in.
+pub fn save(passphrase: &str, data: &AppData) -> Result<(), StorageError> {
+    if layout()? == Some(StorageLayout::MonthlyChunks) {
+        return chunked::save(&chunk_dir_path()?, passphrase, data);
+    }
+    let json = serde_json::to_vec(data)?;
+    let encrypted = crypto::encrypt(passphrase, &json)?;
+    let path = data_file_path()?;
+    fs::write(path, encrypted)?;
+    Ok(())
+}
+
+/// Load and decrypt app data with the given passphrase.
+pub fn load(passphrase: &str) -> Result<AppData, StorageError> {
+    if layout()? == Some(StorageLayout::MonthlyChunks) {
+        return chunked::load(&chunk_dir_path()?, passphrase);
+    }
+    let path = data_file_path()?;
+    let encrypted = fs::read(path)?;
+    let decrypted = crypto::decrypt(passphrase, &encrypted)?;
+    let data: AppData = serde_json::from_slice(&decrypted)?;
+    Ok(data)
+}
+
+/// Store `data` in `layout` from now on. The new copy is written in full
+/// before the old one is removed.
+pub fn convert(
+    passphrase: &str,
+    data: &AppData,
+    layout: StorageLayout,
+) -> Result<(), StorageError> {
+    match layout {
+        StorageLayout::SingleFile => {
+            let encrypted = crypto::encrypt(passphrase, &serde_json::to_vec(data)?)?;
+            fs::write(data_file_path()?, encrypted)?;
+            remove_chunks()?;
+        }
+        StorageLayout::MonthlyChunks => {
+            chunked::save(&chunk_dir_path()?, passphrase, data)?;
+            let path = data_file_path()?;
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn remove_chunks() -> Result<(), StorageError> {
+    let dir = chunk_dir_path()?;
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+/// Delete all data permanently.
+pub fn wipe() -> Result<(), StorageError> {
+    let path = data_file_path()?;
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    remove_chunks()
+}
@@ -0,0 +1,142 @@
+use crate::models::{AppSettings, CatalogueSymptom, SymptomCategory, SymptomDescriptor};
+
+pub const CRAMPS: &str = "cramps";
+pub const HEADACHE: &str = "headache";
+pub const MOOD_LOW: &str = "mood_low";
+pub const MOOD_HIGH: &str = "mood_high";
+pub const FATIGUE: &str = "fatigue";
+pub const BLOATING: &str = "bloating";
+pub const BREAST_TENDERNESS: &str = "breast_tenderness";
+pub const ACNE: &str = "acne";
+pub const HOT_FLASH: &str = "hot_flash";
+
+/// Prefix for keys of user-defined symptoms, so they never collide with
+/// built-ins added later.
+const CUSTOM_PREFIX: &str = "custom_";
+
+/// Built-in symptoms: (key, label, legacy enum name, category).
+/// The legacy name is what data files stored before symptoms became keys.
+const BUILTINS: &[(&str, &str, &str, SymptomCategory)] = &[
+    (CRAMPS, "Cramps", "Cramps", SymptomCategory::Pain),
+    (HEADACHE, "Headache", "Headache", SymptomCategory::Pain),
+    (MOOD_LOW, "Low mood", "MoodLow", SymptomCategory::Mood),
+    (MOOD_HIGH, "High mood", "MoodHigh", SymptomCategory::Mood),
+    (FATIGUE, "Fatigue", "Fatigue", SymptomCategory::Energy),
+    (BLOATING, "Bloating", "Bloating", SymptomCategory::Digestion),
+    (
+        BREAST_TENDERNESS,
+        "Breast tenderness",
+        "BreastTenderness",
+        SymptomCategory::Pain,
+    ),
+    (ACNE, "Acne", "Acne", SymptomCategory::Skin),
+    (
+        HOT_FLASH,
+        "Hot flash",
+        "HotFlash",
+        SymptomCategory::Vasomotor,
+    ),
+];
+
+/// Default severity scale for built-in symptoms (1 = mild, 3 = severe).
+const DEFAULT_MAX_SEVERITY: u8 = 3;
+
+pub fn builtin_descriptors() -> Vec<SymptomDescriptor> {
+    BUILTINS
+        .iter()
+        .map(|&(key, label, _, category)| SymptomDescriptor {
+            key: key.to_string(),
+            label: label.to_string(),
+            category,
+            max_severity: DEFAULT_MAX_SEVERITY,
+            builtin: true,
+        })
+        .collect()
+}
+
+/// The registry in the user's preferred order, hidden symptoms marked.
+/// Keys in the preferences that aren't in the registry are ignored.
+pub fn catalogue(registry: &[SymptomDescriptor], settings: &AppSettings) -> Vec<CatalogueSymptom> {
+    let position = |key: &str| {
+        settings
+            .symptom_order
+            .iter()
+            .position(|k| k == key)
+            .unwrap_or(usize::MAX)
+    };
+    let mut ordered: Vec<&SymptomDescriptor> = registry.iter().collect();
+    // Stable, so unlisted symptoms keep their registry order at the end
+    ordered.sort_by_key(|d| position(&d.key));
+    ordered
+        .into_iter()
+        .map(|d| CatalogueSymptom {
+            descriptor: d.clone(),
+            hidden: settings.hidden_symptoms.contains(&d.key),
+        })
+        .collect()
+}
+
+/// Map a pre-registry enum name (e.g. `"MoodLow"`) to its stable key.
+pub fn key_for_legacy(name: &str) -> Option<&'static str> {
+    BUILTINS
+        .iter()
+        .find(|(_, _, legacy, _)| *legacy == name)
+        .map(|(key, ..)| *key)
+}
+
+/// Look up a symptom by key, also accepting legacy enum names from older
+/// frontends.
+pub fn resolve<'a>(registry: &'a [SymptomDescriptor], key: &str) -> Option<&'a SymptomDescriptor> {
+    let key = key_for_legacy(key).unwrap_or(key);
+    registry.iter().find(|d| d.key == key)
+}
+
+/// Derive a new, unused key for a custom symptom from its label.
+pub fn custom_key(label: &str, registry: &[SymptomDescriptor]) -> String {
+    let slug: String = label
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let base = format!("{CUSTOM_PREFIX}{}", slug.trim_matches('_'));
+
+    let mut key = base.clone();
+    let mut n = 2;
+    while registry.iter().any(|d| d.key == key) {
+        key = format!("{base}_{n}");
+        n += 1;
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalogue_follows_preferences() {
+        let settings = AppSettings {
+            symptom_order: vec![
+                ACNE.to_string(),
+                "custom_gone".to_string(),
+                FATIGUE.to_string(),
+            ],
+            hidden_symptoms: vec![HOT_FLASH.to_string()],
+            ..AppSettings::default()
+        };
+        let catalogue = catalogue(&builtin_descriptors(), &settings);
+        let keys: Vec<&str> = catalogue
+            .iter()
+            .map(|c| c.descriptor.key.as_str())
+            .collect();
+        assert_eq!(&keys[..3], [ACNE, FATIGUE, CRAMPS]);
+        assert_eq!(keys.len(), BUILTINS.len());
+        let hidden: Vec<&str> = catalogue
+            .iter()
+            .filter(|c| c.hidden)
+            .map(|c| c.descriptor.key.as_str())
+            .collect();
+        assert_eq!(hidden, [HOT_FLASH]);
+    }
+}
@@ -0,0 +1,139 @@
+use chrono::NaiveDate;
+
+use crate::models::*;
+
+/// Every record from `from` to `to` (inclusive) in date order, covering up
+/// to `limit` dates so a day's records are never split across pages.
+/// Within a day, cycle boundaries come first and appointments last.
+pub fn timeline(data: &AppData, from: NaiveDate, to: NaiveDate, limit: usize) -> TimelinePage {
+    let within = |date: NaiveDate| (from..=to).contains(&date);
+    let mut entries: Vec<TimelineEntry> = Vec::new();
+
+    for cycle in &data.cycles {
+        if within(cycle.start_date) {
+            entries.push(TimelineEntry::CycleStart(cycle.clone()));
+        }
+        if cycle.end_date.is_some_and(within) {
+            entries.push(TimelineEntry::PeriodEnd(cycle.clone()));
+        }
+    }
+    entries.extend(
+        data.day_logs
+            .range(from..=to)
+            .map(|(_, l)| TimelineEntry::DayLog(l.clone())),
+    );
+    entries.extend(
+        data.symptoms
+            .range(from..=to)
+            .flat_map(|(_, s)| s.iter().cloned().map(TimelineEntry::Symptom)),
+    );
+    entries.extend(
+        data.pain
+            .iter()
+            .filter(|p| within(p.date))
+            .cloned()
+            .map(TimelineEntry::Pain),
+    );
+    entries.extend(
+        data.pill_doses
+            .iter()
+            .filter(|d| within(d.date))
+            .cloned()
+            .map(TimelineEntry::PillDose),
+    );
+    entries.extend(
+        data.lab_results
+            .iter()
+            .filter(|r| within(r.date))
+            .cloned()
+            .map(TimelineEntry::LabResult),
+    );
+    entries.extend(
+        data.appointments
+            .iter()
+            .filter(|a| within(a.date))
+            .cloned()
+            .map(TimelineEntry::Appointment),
+    );
+    // Stable, so the order above is kept within each day
+    entries.sort_by_key(TimelineEntry::date);
+
+    let mut dates = entries.iter().map(TimelineEntry::date).collect::<Vec<_>>();
+    dates.dedup();
+    let next_cursor = dates.get(limit).copied();
+    if let Some(cursor) = next_cursor {
+        entries.retain(|e| e.date() < cursor);
+    }
+    TimelinePage {
+        entries,
+        next_cursor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn date(d: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn records_interleaved_and_paged_by_day() {
+        let mut data = AppData::default();
+        data.cycles.push(Cycle {
+            id: Uuid::new_v4(),
+            start_date: date("2026-01-04"),
+            end_date: Some(date("2026-01-08")),
+            source: CycleSource::Manual,
+            include_in_stats: None,
+            ovulation: None,
+            created_at: None,
+            updated_at: None,
+        });
+        data.appointments.push(Appointment {
+            id: Uuid::new_v4(),
+            date: date("2026-01-04"),
+            appointment_type: AppointmentType::Checkup,
+            notes: String::new(),
+            follow_up_date: None,
+        });
+        data.pill_doses.push(PillDose {
+            date: date("2026-01-06"),
+            taken: true,
+        });
+        data.symptoms.insert(
+            date("2026-01-04"),
+            vec![Symptom {
+                date: date("2026-01-04"),
+                symptom_type: "cramps".to_string(),
+                severity: 2,
+                created_at: None,
+                updated_at: None,
+            }],
+        );
+
+        let page = timeline(&data, date("2026-01-01"), date("2026-01-31"), 2);
+        let kinds: Vec<&str> = page
+            .entries
+            .iter()
+            .map(|e| match e {
+                TimelineEntry::CycleStart(_) => "cycle_start",
+                TimelineEntry::Symptom(_) => "symptom",
+                TimelineEntry::Appointment(_) => "appointment",
+                TimelineEntry::PillDose(_) => "pill_dose",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            ["cycle_start", "symptom", "appointment", "pill_dose"]
+        );
+        assert_eq!(page.next_cursor, Some(date("2026-01-08")));
+
+        let rest = timeline(&data, date("2026-01-08"), date("2026-01-31"), 2);
+        assert!(matches!(rest.entries[..], [TimelineEntry::PeriodEnd(_)]));
+        assert_eq!(rest.next_cursor, None);
+    }
+}
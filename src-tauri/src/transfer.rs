@@ -0,0 +1,183 @@
+//! Moving a `.cykelbak` bundle between devices as a run of QR codes. The
+//! frontend draws and scans the codes; this only splits and rejoins their
+//! text. Each chunk reads
+//!
+//! ```text
+//! CYK:1:<transfer id>:<index>:<total>:<base45 bytes>
+//! ```
+//!
+//! Base45 (RFC 9285) keeps to the characters of QR's alphanumeric mode,
+//! which packs denser than bytes. Nothing here is secret beyond the bundle
+//! itself, which is encrypted before it's split.
+
+use std::collections::BTreeMap;
+
+use uuid::Uuid;
+
+use crate::models::QrTransferProgress;
+use crate::validation::{ValidationErrors, Validator};
+
+const PREFIX: &str = "CYK";
+const CHUNK_FORMAT_VERSION: &str = "1";
+/// Bundle bytes per code, 900 characters once encoded: small enough for a
+/// laptop camera to read off a screen reliably.
+const CHUNK_BYTES: usize = 600;
+const BASE45: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// `bundle` as QR code texts, in order.
+pub fn split(bundle: &[u8]) -> Vec<String> {
+    let id = Uuid::new_v4().simple().to_string()[..8].to_uppercase();
+    let total = bundle.len().div_ceil(CHUNK_BYTES).max(1);
+    let mut chunks: Vec<&[u8]> = bundle.chunks(CHUNK_BYTES).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, bytes)| {
+            format!(
+                "{PREFIX}:{CHUNK_FORMAT_VERSION}:{id}:{}:{total}:{}",
+                i + 1,
+                encode(bytes)
+            )
+        })
+        .collect()
+}
+
+/// Which chunks of a transfer have been scanned so far. Scanning the same
+/// code twice is harmless.
+pub fn progress(chunks: &[String]) -> Result<QrTransferProgress, ValidationErrors> {
+    let (total, parts) = parse(chunks)?;
+    Ok(QrTransferProgress {
+        received: parts.len(),
+        total,
+        missing: (1..=total).filter(|i| !parts.contains_key(i)).collect(),
+    })
+}
+
+/// The bundle the chunks came from, once every one has been scanned.
+pub fn join(chunks: &[String]) -> Result<Vec<u8>, ValidationErrors> {
+    let (total, parts) = parse(chunks)?;
+    let mut v = Validator::new();
+    if parts.len() != total {
+        v.error(
+            "chunks",
+            format!("{} of {total} codes still to scan", total - parts.len()),
+        );
+    }
+    v.finish()?;
+    Ok(parts.into_values().flatten().collect())
+}
+
+/// The transfer's size and its chunks by index, checking they all belong
+/// to one transfer.
+fn parse(chunks: &[String]) -> Result<(usize, BTreeMap<usize, Vec<u8>>), ValidationErrors> {
+    let mut v = Validator::new();
+    let mut transfer: Option<(&str, usize)> = None;
+    let mut parts = BTreeMap::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let field = format!("chunks[{i}]");
+        let fields: Vec<&str> = chunk.trim().splitn(6, ':').collect();
+        let [PREFIX, CHUNK_FORMAT_VERSION, id, index, total, payload] = fields[..] else {
+            v.error(&field, "is not a cykel transfer code");
+            continue;
+        };
+        let (Ok(index), Ok(total)) = (index.parse::<usize>(), total.parse::<usize>()) else {
+            v.error(&field, "is not a cykel transfer code");
+            continue;
+        };
+        if index == 0 || index > total {
+            v.error(&field, "is not a cykel transfer code");
+            continue;
+        }
+        match transfer {
+            None => transfer = Some((id, total)),
+            Some(first) if first != (id, total) => {
+                v.error(&field, "is from a different transfer");
+                continue;
+            }
+            Some(_) => {}
+        }
+        match decode(payload) {
+            Some(bytes) => {
+                parts.insert(index, bytes);
+            }
+            None => v.error(&field, "is damaged; scan it again"),
+        }
+    }
+    if chunks.is_empty() {
+        v.error("chunks", "no codes scanned yet");
+    }
+    v.finish()?;
+    Ok((transfer.map_or(0, |(_, total)| total), parts))
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() / 2 * 3 + 2);
+    for pair in bytes.chunks(2) {
+        let (mut n, digits) = match *pair {
+            [a, b] => (usize::from(a) * 256 + usize::from(b), 3),
+            [a] => (usize::from(a), 2),
+            _ => unreachable!("chunks of two"),
+        };
+        for _ in 0..digits {
+            out.push(char::from(BASE45[n % 45]));
+            n /= 45;
+        }
+    }
+    out
+}
+
+/// `None` when `text` isn't valid base45.
+fn decode(text: &str) -> Option<Vec<u8>> {
+    let values: Vec<usize> = text
+        .bytes()
+        .map(|c| BASE45.iter().position(|&b| b == c))
+        .collect::<Option<_>>()?;
+    let mut out = Vec::with_capacity(values.len() / 3 * 2 + 1);
+    for group in values.chunks(3) {
+        match *group {
+            [c, d, e] => {
+                let n = c + d * 45 + e * 45 * 45;
+                out.extend(u16::try_from(n).ok()?.to_be_bytes());
+            }
+            [c, d] => out.push(u8::try_from(c + d * 45).ok()?),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base45_matches_rfc_examples() {
+        assert_eq!(encode(b"AB"), "BB8");
+        assert_eq!(encode(b"Hello!!"), "%69 VD92EX0");
+        assert_eq!(decode("QED8WEX0").unwrap(), b"ietf!");
+        assert!(decode("GGW").is_none());
+    }
+
+    #[test]
+    fn chunks_rejoined_in_any_order() {
+        let bundle: Vec<u8> = (0..1500u32).map(|i| (i * 7 % 256) as u8).collect();
+        let mut chunks = split(&bundle);
+        assert_eq!(chunks.len(), 3);
+
+        let first = chunks.remove(0);
+        let progress = progress(&chunks).unwrap();
+        assert_eq!((progress.received, progress.total), (2, 3));
+        assert_eq!(progress.missing, [1]);
+        assert!(join(&chunks).is_err());
+
+        chunks.push(chunks[0].clone());
+        chunks.push(first);
+        assert_eq!(join(&chunks).unwrap(), bundle);
+
+        chunks.push(split(&bundle)[0].clone());
+        assert!(join(&chunks).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+
+use chrono::NaiveDate;
+
+use crate::error::CommandError;
+use crate::models::*;
+
+/// Earlier versions kept for undo.
+const UNDO_DEPTH: usize = 20;
+
+/// Recent versions of the data as saved, so the latest changes can be
+/// undone. Kept in memory only and dropped on lock.
+#[derive(Default)]
+pub struct History(Mutex<Versions>);
+
+#[derive(Default)]
+struct Versions {
+    /// The data as last saved.
+    saved: Option<AppData>,
+    /// What it was before each of the last changes, oldest first.
+    previous: VecDeque<AppData>,
+}
+
+impl History {
+    /// Note that `data` was just saved.
+    pub fn record(&self, data: &AppData) -> Result<(), CommandError> {
+        let mut versions = self.0.lock()?;
+        if let Some(saved) = versions.saved.take() {
+            if versions.previous.len() == UNDO_DEPTH {
+                versions.previous.pop_front();
+            }
+            versions.previous.push_back(saved);
+        }
+        versions.saved = Some(data.clone());
+        Ok(())
+    }
+
+    /// The version before the last change, if any. The caller saves it,
+    /// which records it as current again.
+    pub fn undo(&self) -> Result<Option<AppData>, CommandError> {
+        let mut versions = self.0.lock()?;
+        let previous = versions.previous.pop_back();
+        if previous.is_some() {
+            versions.saved = None;
+        }
+        Ok(previous)
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut versions) = self.0.lock() {
+            *versions = Versions::default();
+        }
+    }
+}
+
+/// What going back from `current` to `previous` reverts, in a few words,
+/// e.g. "removed log for 2026-03-04".
+pub fn describe(current: &AppData, previous: &AppData) -> String {
+    let dated = [
+        reverted(
+            "log",
+            by_date(current.day_logs.values(), |l| l.date),
+            by_date(previous.day_logs.values(), |l| l.date),
+        ),
+        reverted(
+            "symptoms",
+            by_date(current.symptoms.values().flatten(), |s| s.date),
+            by_date(previous.symptoms.values().flatten(), |s| s.date),
+        ),
+        reverted(
+            "pain",
+            by_date(&current.pain, |p| p.date),
+            by_date(&previous.pain, |p| p.date),
+        ),
+        reverted(
+            "pill dose",
+            by_date(&current.pill_doses, |d| d.date),
+            by_date(&previous.pill_doses, |d| d.date),
+        ),
+        reverted(
+            "appointment",
+            by_date(&current.appointments, |a| a.date),
+            by_date(&previous.appointments, |a| a.date),
+        ),
+        reverted(
+            "lab result",
+            by_date(&current.lab_results, |r| r.date),
+            by_date(&previous.lab_results, |r| r.date),
+        ),
+    ];
+    if let Some(description) = dated.into_iter().flatten().next() {
+        return description;
+    }
+
+    if current.settings != previous.settings {
+        "restored previous settings".to_string()
+    } else if current.symptom_registry != previous.symptom_registry {
+        "restored the symptom list".to_string()
+    } else if current.cycles != previous.cycles {
+        "restored previous cycles".to_string()
+    } else {
+        "reverted the last change".to_string()
+    }
+}
+
+fn by_date<'a, T: 'a>(
+    items: impl IntoIterator<Item = &'a T>,
+    date: impl Fn(&T) -> NaiveDate,
+) -> BTreeMap<NaiveDate, Vec<&'a T>> {
+    let mut grouped: BTreeMap<NaiveDate, Vec<&T>> = BTreeMap::new();
+    for item in items {
+        grouped.entry(date(item)).or_default().push(item);
+    }
+    grouped
+}
+
+/// How undoing changes `what` on the dates where it differs, or `None`
+/// when it doesn't.
+fn reverted<T: PartialEq>(
+    what: &str,
+    current: BTreeMap<NaiveDate, Vec<&T>>,
+    previous: BTreeMap<NaiveDate, Vec<&T>>,
+) -> Option<String> {
+    let mut dates: Vec<NaiveDate> = current.keys().chain(previous.keys()).copied().collect();
+    dates.sort();
+    dates.dedup();
+    dates.retain(|d| current.get(d) != previous.get(d));
+
+    match dates[..] {
+        [] => None,
+        [date] => {
+            let verb = match (current.contains_key(&date), previous.contains_key(&date)) {
+                (true, false) => "removed",
+                (false, true) => "restored",
+                _ => "restored earlier",
+            };
+            Some(format!("{verb} {what} for {date}"))
+        }
+        _ => Some(format!("reverted {what} for {} days", dates.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(d: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap()
+    }
+
+    fn with_log(data: &AppData, day: &str, notes: &str) -> AppData {
+        let mut data = data.clone();
+        data.day_logs.insert(
+            date(day),
+            DayLog {
+                flow_level: FlowLevel::Light,
+                notes: notes.to_string(),
+                ..DayLog::new(date(day))
+            },
+        );
+        data
+    }
+
+    #[test]
+    fn undo_steps_back_through_saves() {
+        let history = History::default();
+        let empty = AppData::default();
+        let logged = with_log(&empty, "2026-03-04", "");
+        let edited = with_log(&logged, "2026-03-04", "spotting");
+        for data in [&empty, &logged, &edited] {
+            history.record(data).unwrap();
+        }
+
+        let previous = history.undo().unwrap().unwrap();
+        assert_eq!(
+            describe(&edited, &previous),
+            "restored earlier log for 2026-03-04"
+        );
+        history.record(&previous).unwrap();
+
+        let previous = history.undo().unwrap().unwrap();
+        assert_eq!(describe(&logged, &previous), "removed log for 2026-03-04");
+        history.record(&previous).unwrap();
+        assert!(history.undo().unwrap().is_none());
+    }
+}
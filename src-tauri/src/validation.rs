@@ -0,0 +1,232 @@
+use std::fmt;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// How far ahead a logged (already happened) date may be. One day of slack
+/// covers timezone differences between the frontend and backend clocks.
+pub const MAX_FUTURE_LOG_DAYS: i64 = 1;
+/// How far ahead a planned date (appointment, follow-up) may be.
+pub const MAX_FUTURE_PLAN_DAYS: i64 = 5 * 366;
+pub const MAX_NOTE_CHARS: usize = 5_000;
+pub const MAX_LABEL_CHARS: usize = 60;
+/// Plausible basal body temperature range, in Celsius.
+pub const MIN_BBT_CELSIUS: f32 = 34.0;
+pub const MAX_BBT_CELSIUS: f32 = 42.0;
+
+/// Earliest date accepted anywhere in the app.
+fn min_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1950, 1, 1).unwrap()
+}
+
+/// A problem with one input field, e.g. `symptoms[2].severity`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationErrors(pub Vec<FieldError>);
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<String> = self
+            .0
+            .iter()
+            .map(|e| format!("{}: {}", e.field, e.message))
+            .collect();
+        f.write_str(&messages.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// Collects field errors so a command can report everything wrong with its
+/// input at once rather than failing on the first problem.
+pub struct Validator {
+    today: NaiveDate,
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::at(chrono::Local::now().date_naive())
+    }
+
+    fn at(today: NaiveDate) -> Self {
+        Self {
+            today,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn error(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// A date something was observed on, which can't be in the future.
+    pub fn past_date(&mut self, field: &str, value: &str) -> Option<NaiveDate> {
+        self.date(field, value, MAX_FUTURE_LOG_DAYS)
+    }
+
+    /// A date that may be scheduled ahead, such as an appointment.
+    pub fn planned_date(&mut self, field: &str, value: &str) -> Option<NaiveDate> {
+        self.date(field, value, MAX_FUTURE_PLAN_DAYS)
+    }
+
+    /// Like [`Validator::past_date`], in a caller-chosen strftime format.
+    pub fn past_date_as(&mut self, field: &str, value: &str, format: &str) -> Option<NaiveDate> {
+        self.date_as(field, value, format, MAX_FUTURE_LOG_DAYS)
+    }
+
+    fn date(&mut self, field: &str, value: &str, max_future_days: i64) -> Option<NaiveDate> {
+        self.date_as(field, value, "%Y-%m-%d", max_future_days)
+    }
+
+    fn date_as(
+        &mut self,
+        field: &str,
+        value: &str,
+        format: &str,
+        max_future_days: i64,
+    ) -> Option<NaiveDate> {
+        let Ok(date) = NaiveDate::parse_from_str(value, format) else {
+            let example = min_date().format(format).to_string();
+            let message = if format == "%Y-%m-%d" {
+                "must be a date in YYYY-MM-DD format".to_string()
+            } else {
+                format!("must be a date like {example}")
+            };
+            self.error(field, message);
+            return None;
+        };
+        if date < min_date() {
+            self.error(field, format!("must not be before {}", min_date()));
+            return None;
+        }
+        if (date - self.today).num_days() > max_future_days {
+            let message = if max_future_days <= MAX_FUTURE_LOG_DAYS {
+                "must not be in the future".to_string()
+            } else {
+                format!("must be within {max_future_days} days from today")
+            };
+            self.error(field, message);
+            return None;
+        }
+        Some(date)
+    }
+
+    /// A strftime-style format that can format and parse dates.
+    pub fn date_format(&mut self, field: &str, format: &str) {
+        let invalid = StrftimeItems::new(format).any(|item| item == Item::Error);
+        if invalid || format.trim().is_empty() {
+            self.error(field, "is not a valid date format");
+        }
+    }
+
+    pub fn required(&mut self, field: &str, value: &str) {
+        if value.trim().is_empty() {
+            self.error(field, "is required");
+        }
+    }
+
+    /// Whether `value` fits, so callers that carry on past errors can
+    /// leave it out.
+    pub fn max_chars(&mut self, field: &str, value: &str, max: usize) -> bool {
+        let fits = value.chars().count() <= max;
+        if !fits {
+            self.error(field, format!("must be at most {max} characters"));
+        }
+        fits
+    }
+
+    /// Whether `value` is in range, like [`Self::max_chars`].
+    pub fn in_range<T: PartialOrd + fmt::Display>(
+        &mut self,
+        field: &str,
+        value: T,
+        min: T,
+        max: T,
+    ) -> bool {
+        let fits = value >= min && value <= max;
+        if !fits {
+            self.error(field, format!("must be between {min} and {max}"));
+        }
+        fits
+    }
+
+    pub fn finite(&mut self, field: &str, value: f64) {
+        if !value.is_finite() {
+            self.error(field, "must be a finite number");
+        }
+    }
+
+    /// Errors so far, for callers that report problems without failing.
+    pub fn into_errors(self) -> Vec<FieldError> {
+        self.errors
+    }
+
+    pub fn finish(self) -> Result<(), ValidationErrors> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(self.errors))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validator() -> Validator {
+        Validator::at(NaiveDate::from_ymd_opt(2026, 3, 10).unwrap())
+    }
+
+    #[test]
+    fn dates_bounded() {
+        let mut v = validator();
+        assert!(v.past_date("date", "2026-03-11").is_some());
+        assert!(v.past_date("date", "2026-03-12").is_none());
+        assert!(v.past_date("date", "1949-12-31").is_none());
+        assert!(v.past_date("date", "10/03/2026").is_none());
+        assert!(v.planned_date("date", "2026-09-01").is_some());
+        assert_eq!(v.finish().unwrap_err().0.len(), 3);
+    }
+
+    #[test]
+    fn every_field_error_reported() {
+        let mut v = validator();
+        v.past_date("date", "03/04/2026");
+        v.in_range("symptoms[1].severity", 0, 1, 3);
+        let error = crate::error::CommandError::from(v.finish().unwrap_err());
+
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["kind"], "InvalidInput");
+        assert_eq!(value["field"], "date");
+        assert_eq!(value["errors"].as_array().unwrap().len(), 2);
+        assert_eq!(value["errors"][1]["field"], "symptoms[1].severity");
+    }
+
+    #[test]
+    fn collects_all_field_errors() {
+        let mut v = validator();
+        v.required("analyte", " ");
+        v.max_chars("notes", &"x".repeat(MAX_NOTE_CHARS + 1), MAX_NOTE_CHARS);
+        v.in_range("symptoms[0].severity", 4, 1, 3);
+        v.in_range("auto_lock_minutes", 5, 1, 60);
+        v.finite("value", f64::NAN);
+
+        let errors = v.finish().unwrap_err();
+        let fields: Vec<&str> = errors.0.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            ["analyte", "notes", "symptoms[0].severity", "value"]
+        );
+    }
+}